@@ -47,7 +47,10 @@ impl IKernel for Kernel {
 
     fn activate_mmu(&self, _pt: &dyn IMMU) {
         #[cfg_accessible(platform_specific::activate_pt)]
-        platform_specific::activate_pt(_pt.platform_payload())
+        platform_specific::activate_pt(
+            _pt.platform_payload()
+                .expect("The mmu can not be activated on this platform"),
+        )
     }
 
     fn time(&self) -> TimeSpec {
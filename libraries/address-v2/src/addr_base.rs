@@ -11,6 +11,12 @@ macro_rules! impl_addr {
             #[allow(non_upper_case_globals)]
             pub const null: Self = Self(0);
 
+            /// The null address, an alias of [`Self::null`].
+            pub const ZERO: Self = Self::null;
+
+            /// The highest address, e.g. the end of the address space.
+            pub const MAX: Self = Self::new(usize::MAX);
+
             /// Checks if the address is null (0).
             #[inline(always)]
             pub const fn is_null(self) -> bool {
@@ -104,6 +110,54 @@ macro_rules! impl_addr {
                     self.0 % align
                 }
             }
+
+            /// Adds `rhs` to the address, clamping at the highest address instead of overflowing.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::PhysAddr;
+            /// assert_eq!(PhysAddr::new(usize::MAX - 1).saturating_add(0x10), PhysAddr::new(usize::MAX));
+            /// ```
+            #[inline(always)]
+            pub const fn saturating_add(self, rhs: usize) -> Self {
+                Self(self.0.saturating_add(rhs))
+            }
+
+            /// Subtracts `rhs` from the address, clamping at null instead of underflowing.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::PhysAddr;
+            /// assert_eq!(PhysAddr::new(0x10).saturating_sub(0x100), PhysAddr::null);
+            /// ```
+            #[inline(always)]
+            pub const fn saturating_sub(self, rhs: usize) -> Self {
+                Self(self.0.saturating_sub(rhs))
+            }
+
+            /// Adds `rhs` to the address, wrapping around the address space on overflow.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::PhysAddr;
+            /// assert_eq!(PhysAddr::new(usize::MAX).wrapping_add(2), PhysAddr::new(1));
+            /// ```
+            #[inline(always)]
+            pub const fn wrapping_add(self, rhs: usize) -> Self {
+                Self(self.0.wrapping_add(rhs))
+            }
+
+            /// Subtracts `rhs` from the address, wrapping around the address space on underflow.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::PhysAddr;
+            /// assert_eq!(PhysAddr::new(1).wrapping_sub(2), PhysAddr::new(usize::MAX));
+            /// ```
+            #[inline(always)]
+            pub const fn wrapping_sub(self, rhs: usize) -> Self {
+                Self(self.0.wrapping_sub(rhs))
+            }
         }
 
         impl const ::core::default::Default for $type {
@@ -141,6 +195,17 @@ macro_rules! impl_addr {
             }
         }
 
+        /// Fails instead of truncating when the value does not fit in `usize`, e.g. 64-bit
+        /// addresses on 32-bit hosts.
+        impl ::core::convert::TryFrom<u64> for $type {
+            type Error = ::core::num::TryFromIntError;
+
+            #[inline(always)]
+            fn try_from(value: u64) -> Result<Self, Self::Error> {
+                usize::try_from(value).map(Self)
+            }
+        }
+
         impl const ::core::ops::Deref for $type {
             type Target = usize;
 
@@ -394,6 +459,30 @@ macro_rules! impl_addr {
         mod tests {
             use super::*;
 
+            fn addr(value: u64) -> $type {
+                $type::try_from(value).expect("The address does not fit in usize")
+            }
+
+            #[test]
+            fn test_try_from_u64() {
+                assert_eq!(addr(0x1234), $type::new(0x1234));
+                assert_eq!($type::try_from(u32::MAX as u64), Ok($type::new(u32::MAX as usize)));
+            }
+
+            #[test]
+            #[cfg(target_pointer_width = "64")]
+            fn test_try_from_u64_wide() {
+                assert_eq!(*addr(0x7fff_0000_0000), 0x7fff_0000_0000);
+                assert_eq!(*addr(u64::MAX), usize::MAX);
+            }
+
+            #[test]
+            #[cfg(target_pointer_width = "32")]
+            fn test_try_from_u64_rejects_truncation() {
+                assert!($type::try_from(0x7fff_0000_0000u64).is_err());
+                assert!($type::try_from(u32::MAX as u64 + 1).is_err());
+            }
+
             #[test]
             fn test_const_constructors() {
                 const ADDR1: $type = $type::new(0x1234);
@@ -793,6 +882,43 @@ macro_rules! impl_addr {
                 assert_eq!(aligned_up.offset_from_alignment(100), 0);
             }
 
+            #[test]
+            fn test_zero_and_max() {
+                assert_eq!(*$type::MAX, usize::MAX);
+                assert_eq!(*$type::null, 0);
+                assert_eq!($type::ZERO, $type::null);
+                assert!($type::ZERO.is_null());
+                assert!($type::ZERO < $type::MAX);
+            }
+
+            #[test]
+            fn test_saturating_arithmetic() {
+                const MAX: $type = $type::MAX;
+                const SATURATED: $type = MAX.saturating_add(1);
+
+                assert_eq!(SATURATED, MAX);
+                assert_eq!($type::new(usize::MAX - 1).saturating_add(0x10), MAX);
+                assert_eq!($type::null.saturating_add(0x10), $type::new(0x10));
+
+                assert_eq!($type::null.saturating_sub(1), $type::null);
+                assert_eq!($type::new(0x10).saturating_sub(0x100), $type::null);
+                assert_eq!(MAX.saturating_sub(1), $type::new(usize::MAX - 1));
+            }
+
+            #[test]
+            fn test_wrapping_arithmetic() {
+                const MAX: $type = $type::MAX;
+                const WRAPPED: $type = MAX.wrapping_add(1);
+
+                assert_eq!(WRAPPED, $type::null);
+                assert_eq!(MAX.wrapping_add(0x10), $type::new(0xf));
+                assert_eq!($type::null.wrapping_add(0x10), $type::new(0x10));
+
+                assert_eq!($type::null.wrapping_sub(1), MAX);
+                assert_eq!($type::new(0x10).wrapping_sub(0x11), MAX);
+                assert_eq!(MAX.wrapping_sub(1), $type::new(usize::MAX - 1));
+            }
+
             #[test]
             #[cfg(debug_assertions)]
             #[should_panic]
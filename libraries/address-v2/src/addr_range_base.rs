@@ -121,6 +121,38 @@ macro_rules! impl_range {
                 *range.start >= *self.start && *range.end <= *self.end
             }
 
+            /// Checks if this range is fully contained in another range.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let outer = PhysAddrRange::new(PhysAddr::new(0x1000), PhysAddr::new(0x3000));
+            /// let inner = PhysAddrRange::new(PhysAddr::new(0x1500), PhysAddr::new(0x2500));
+            /// assert!(inner.contained_by(outer));
+            /// assert!(!outer.contained_by(inner));
+            /// ```
+            #[inline(always)]
+            pub const fn contained_by(&self, other: $range_type) -> bool {
+                other.contains(*self)
+            }
+
+            /// Checks if this range contains another range without sharing any of its
+            /// boundaries, so a range never strictly contains itself.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let outer = PhysAddrRange::new(PhysAddr::new(0x1000), PhysAddr::new(0x3000));
+            /// let inner = PhysAddrRange::new(PhysAddr::new(0x1500), PhysAddr::new(0x2500));
+            /// let head = PhysAddrRange::new(PhysAddr::new(0x1000), PhysAddr::new(0x2000));
+            /// assert!(outer.strictly_contains(inner));
+            /// assert!(outer.contains(head) && !outer.strictly_contains(head));
+            /// ```
+            #[inline(always)]
+            pub const fn strictly_contains(&self, range: $range_type) -> bool {
+                *range.start > *self.start && *range.end < *self.end
+            }
+
             /// Checks if this range overlaps with another range.
             ///
             /// # Examples
@@ -201,6 +233,22 @@ macro_rules! impl_range {
                 }
             }
 
+            /// Clamps the range to `bounds`, e.g. the valid user address window.
+            /// Returns None if the range lies entirely outside of `bounds`.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let bounds = PhysAddrRange::new(PhysAddr::new(0x1000), PhysAddr::new(0x3000));
+            /// let range = PhysAddrRange::new(PhysAddr::new(0x2000), PhysAddr::new(0x4000));
+            /// let clamped = range.clamp_to(bounds).unwrap();
+            /// assert_eq!(clamped.end(), PhysAddr::new(0x3000));
+            /// ```
+            #[inline(always)]
+            pub const fn clamp_to(self, bounds: Self) -> Option<Self> {
+                self.intersection(bounds)
+            }
+
             /// Aligns the range to the given alignment.
             /// The start is aligned down and the end is aligned up.
             ///
@@ -220,6 +268,69 @@ macro_rules! impl_range {
                 )
             }
 
+            /// Returns the number of pages of `page_size` needed to hold the length of this range,
+            /// rounded up. The start address is not taken into account.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let range = PhysAddrRange::from_start_len(PhysAddr::new(0x1800), 0x1000);
+            /// assert_eq!(range.page_count(0x1000), 1);
+            /// ```
+            #[inline(always)]
+            pub const fn page_count(&self, page_size: usize) -> usize {
+                debug_assert!(page_size != 0);
+
+                self.len().div_ceil(page_size)
+            }
+
+            /// Returns the number of pages of `page_size` this range touches, i.e. the page count
+            /// of the range after [`Self::align_to`].
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let range = PhysAddrRange::from_start_len(PhysAddr::new(0x1800), 0x1000);
+            /// assert_eq!(range.aligned_page_count(0x1000), 2);
+            /// ```
+            #[inline(always)]
+            pub const fn aligned_page_count(&self, page_size: usize) -> usize {
+                self.align_to(page_size).len() / page_size
+            }
+
+            /// Splits the range into an unaligned head, a middle aligned to `align` and an unaligned tail.
+            /// Any of the pieces may be empty, and together they cover the original range exactly.
+            ///
+            /// If no address in the range is aligned, the whole range is the head.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let range = PhysAddrRange::new(PhysAddr::new(0x1234), PhysAddr::new(0x3456));
+            /// let (head, middle, tail) = range.split_by_alignment(0x1000);
+            /// assert_eq!(head, PhysAddrRange::new(PhysAddr::new(0x1234), PhysAddr::new(0x2000)));
+            /// assert_eq!(middle, PhysAddrRange::new(PhysAddr::new(0x2000), PhysAddr::new(0x3000)));
+            /// assert_eq!(tail, PhysAddrRange::new(PhysAddr::new(0x3000), PhysAddr::new(0x3456)));
+            /// ```
+            pub const fn split_by_alignment(self, align: usize) -> (Self, Self, Self) {
+                let middle_start = self.start.align_up(align);
+                let middle_end = self.end.align_down(align);
+
+                if *middle_start > *middle_end {
+                    return (
+                        self,
+                        Self::new(self.end, self.end),
+                        Self::new(self.end, self.end),
+                    );
+                }
+
+                (
+                    Self::new(self.start, middle_start),
+                    Self::new(middle_start, middle_end),
+                    Self::new(middle_end, self.end),
+                )
+            }
+
             /// Returns an iterator over addresses in this range with the given step size.
             /// Returns `None` if `step == 0` or if the range length is not a multiple of `step`.
             ///
@@ -255,6 +366,25 @@ macro_rules! impl_range {
             pub const fn iter_pages_sized(self, page_size: usize) -> Option<RangeIterator> {
                 self.iter_step(page_size)
             }
+
+            /// Returns an iterator over the start of every page of `page_size` this range touches.
+            /// Unlike [`Self::iter_pages_sized`], the range is aligned with [`Self::align_to`] first,
+            /// so this always succeeds for unaligned ranges.
+            ///
+            /// # Examples
+            /// ```
+            /// # use address_v2::{PhysAddr, PhysAddrRange};
+            /// let range = PhysAddrRange::new(PhysAddr::new(0x1234), PhysAddr::new(0x3456));
+            /// let pages: Vec<_> = range.iter_pages_aligned(0x1000).collect();
+            /// assert_eq!(pages, [PhysAddr::new(0x1000), PhysAddr::new(0x2000), PhysAddr::new(0x3000)]);
+            /// ```
+            #[inline(always)]
+            pub const fn iter_pages_aligned(self, page_size: usize) -> RangeIterator {
+                debug_assert!(page_size.is_power_of_two());
+
+                // The aligned range's length is always a multiple of the page size
+                unsafe { RangeIterator::new_unchecked(self.align_to(page_size), page_size) }
+            }
         }
 
         impl ::core::fmt::Display for $range_type {
@@ -409,6 +539,37 @@ macro_rules! impl_range {
                 assert!(!range1.contains(range4));
             }
 
+            #[test]
+            fn test_range_contained_by() {
+                let outer = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x3000));
+                let inner = $range_type::new($addr_type::new(0x1500), $addr_type::new(0x2500));
+                let crossing = $range_type::new($addr_type::new(0x2500), $addr_type::new(0x3500));
+
+                assert!(inner.contained_by(outer));
+                assert!(!outer.contained_by(inner));
+                assert!(!crossing.contained_by(outer));
+                assert!(outer.contained_by(outer));
+            }
+
+            #[test]
+            fn test_range_strictly_contains() {
+                let outer = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x3000));
+                let inner = $range_type::new($addr_type::new(0x1500), $addr_type::new(0x2500));
+                let head = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x2000));
+                let tail = $range_type::new($addr_type::new(0x2000), $addr_type::new(0x3000));
+
+                assert!(outer.strictly_contains(inner));
+                assert!(!inner.strictly_contains(outer));
+
+                // Equal ranges are contained, but not strictly
+                assert!(outer.contains(outer));
+                assert!(!outer.strictly_contains(outer));
+
+                // So are ranges sharing a boundary
+                assert!(outer.contains(head) && !outer.strictly_contains(head));
+                assert!(outer.contains(tail) && !outer.strictly_contains(tail));
+            }
+
 
             #[test]
             fn test_range_overlaps() {
@@ -469,6 +630,46 @@ macro_rules! impl_range {
                 assert!(range1.intersection(range3).is_none());
             }
 
+            #[test]
+            fn test_clamp_to_inside() {
+                let bounds = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x5000));
+                let range = $range_type::new($addr_type::new(0x2000), $addr_type::new(0x3000));
+
+                assert_eq!(range.clamp_to(bounds), Some(range));
+                assert_eq!(bounds.clamp_to(bounds), Some(bounds));
+            }
+
+            #[test]
+            fn test_clamp_to_partially_overlapping() {
+                let bounds = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x5000));
+
+                let tail = $range_type::new($addr_type::new(0x4000), $addr_type::new(0x8000));
+                assert_eq!(
+                    tail.clamp_to(bounds),
+                    Some($range_type::new($addr_type::new(0x4000), $addr_type::new(0x5000)))
+                );
+
+                let head = $range_type::new($addr_type::null, $addr_type::new(0x2000));
+                assert_eq!(
+                    head.clamp_to(bounds),
+                    Some($range_type::new($addr_type::new(0x1000), $addr_type::new(0x2000)))
+                );
+
+                let whole = $range_type::new($addr_type::null, $addr_type::MAX);
+                assert_eq!(whole.clamp_to(bounds), Some(bounds));
+            }
+
+            #[test]
+            fn test_clamp_to_disjoint() {
+                let bounds = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x5000));
+
+                let above = $range_type::new($addr_type::new(0x5000), $addr_type::new(0x6000));
+                let below = $range_type::new($addr_type::null, $addr_type::new(0x1000));
+
+                assert_eq!(above.clamp_to(bounds), None);
+                assert_eq!(below.clamp_to(bounds), None);
+            }
+
             #[test]
             fn test_range_alignment() {
                 let range = $range_type::new($addr_type::new(0x1234), $addr_type::new(0x2345));
@@ -507,6 +708,25 @@ macro_rules! impl_range {
                 assert_eq!(pages[2], $addr_type::new(0x3000));
             }
 
+            #[test]
+            fn test_range_iter_pages_aligned() {
+                let range = $range_type::new($addr_type::new(0x1234), $addr_type::new(0x3456));
+
+                assert!(range.iter_pages().is_none());
+
+                let pages: Vec<_> = range.iter_pages_aligned(0x1000).collect();
+                assert_eq!(pages.len(), 3);
+                assert_eq!(pages.first(), Some(&$addr_type::new(0x1000)));
+                assert_eq!(pages.last(), Some(&$addr_type::new(0x3000)));
+
+                // An unaligned range within a single page still touches it
+                let small = $range_type::new($addr_type::new(0x1234), $addr_type::new(0x1235));
+                assert!(small.iter_pages_aligned(0x1000).eq([$addr_type::new(0x1000)]));
+
+                let empty = $range_type::new($addr_type::new(0x2000), $addr_type::new(0x2000));
+                assert_eq!(empty.iter_pages_aligned(0x1000).count(), 0);
+            }
+
             #[test]
             fn test_iterator_length_less_than_step() {
                 let range = $range_type::new($addr_type::new(0x1000), $addr_type::new(0x2000));
@@ -528,6 +748,64 @@ macro_rules! impl_range {
                 assert_eq!(display_str, expected);
             }
 
+            fn assert_split_reconstructs(range: $range_type, align: usize) -> ($range_type, $range_type, $range_type) {
+                let (head, middle, tail) = range.split_by_alignment(align);
+
+                assert_eq!(head.start(), range.start());
+                assert_eq!(head.end(), middle.start());
+                assert_eq!(middle.end(), tail.start());
+                assert_eq!(tail.end(), range.end());
+                assert_eq!(head.len() + middle.len() + tail.len(), range.len());
+
+                if head.end() != range.end() {
+                    assert!(middle.start().is_aligned(align));
+                    assert!(middle.end().is_aligned(align));
+                }
+
+                (head, middle, tail)
+            }
+
+            #[test]
+            fn test_range_split_by_alignment() {
+                let range = $range_type::new($addr_type::new(0x1234), $addr_type::new(0x203456));
+
+                let (head, middle, tail) = assert_split_reconstructs(range, 0x1000);
+                assert_eq!(head, $range_type::new($addr_type::new(0x1234), $addr_type::new(0x2000)));
+                assert_eq!(middle, $range_type::new($addr_type::new(0x2000), $addr_type::new(0x203000)));
+                assert_eq!(tail, $range_type::new($addr_type::new(0x203000), $addr_type::new(0x203456)));
+            }
+
+            #[test]
+            fn test_range_split_by_large_alignment() {
+                let range = $range_type::new($addr_type::new(0x1234), $addr_type::new(0x203456));
+
+                // 0x200000 is the only aligned address, so the middle is empty
+                let (head, middle, tail) = assert_split_reconstructs(range, 0x200000);
+                assert_eq!(head, $range_type::new($addr_type::new(0x1234), $addr_type::new(0x200000)));
+                assert!(middle.is_empty());
+                assert_eq!(tail, $range_type::new($addr_type::new(0x200000), $addr_type::new(0x203456)));
+            }
+
+            #[test]
+            fn test_range_split_aligned() {
+                let range = $range_type::new($addr_type::new(0x200000), $addr_type::new(0x600000));
+
+                let (head, middle, tail) = assert_split_reconstructs(range, 0x200000);
+                assert!(head.is_empty());
+                assert_eq!(middle, range);
+                assert!(tail.is_empty());
+            }
+
+            #[test]
+            fn test_range_split_without_aligned_region() {
+                let range = $range_type::new($addr_type::new(0x1234), $addr_type::new(0x1456));
+
+                let (head, middle, tail) = assert_split_reconstructs(range, 0x1000);
+                assert_eq!(head, range);
+                assert!(middle.is_empty());
+                assert!(tail.is_empty());
+            }
+
             #[test]
             fn test_range_default() {
                 let range = $range_type::default();
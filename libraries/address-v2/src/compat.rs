@@ -0,0 +1,112 @@
+//! Conversions between the types of the `address` crate and `address-v2`.
+//!
+//! Both directions preserve the raw address, so code can be migrated to `address-v2`
+//! incrementally by converting at the boundaries.
+
+use abstractions::IUsizeAlias;
+use address::{PhysicalAddress, PhysicalAddressRange, VirtualAddress, VirtualAddressRange};
+
+use crate::{PhysAddr, PhysAddrRange, VirtAddr, VirtAddrRange};
+
+macro_rules! impl_addr_compat {
+    ($v1_type:ty, $v2_type:ty) => {
+        impl From<$v1_type> for $v2_type {
+            #[inline(always)]
+            fn from(value: $v1_type) -> Self {
+                <$v2_type>::new(value.as_usize())
+            }
+        }
+
+        impl From<$v2_type> for $v1_type {
+            #[inline(always)]
+            fn from(value: $v2_type) -> Self {
+                <$v1_type>::from_usize(*value)
+            }
+        }
+    };
+}
+
+macro_rules! impl_range_compat {
+    ($v1_type:ty, $v2_type:ty) => {
+        impl From<$v1_type> for $v2_type {
+            #[inline(always)]
+            fn from(value: $v1_type) -> Self {
+                <$v2_type>::new(value.start().into(), value.end().into())
+            }
+        }
+
+        impl From<$v2_type> for $v1_type {
+            #[inline(always)]
+            fn from(value: $v2_type) -> Self {
+                <$v1_type>::from_start_end(value.start().into(), value.end().into())
+            }
+        }
+    };
+}
+
+impl_addr_compat!(PhysicalAddress, PhysAddr);
+impl_addr_compat!(VirtualAddress, VirtAddr);
+
+impl_range_compat!(PhysicalAddressRange, PhysAddrRange);
+impl_range_compat!(VirtualAddressRange, VirtAddrRange);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phys_addr_round_trip() {
+        let v1 = PhysicalAddress::from_usize(0x8020_1234);
+
+        let v2: PhysAddr = v1.into();
+        assert_eq!(*v2, 0x8020_1234);
+
+        let back: PhysicalAddress = v2.into();
+        assert_eq!(back, v1);
+    }
+
+    #[test]
+    fn test_virt_addr_round_trip() {
+        let v2 = VirtAddr::new(0xffff_ffc0_8020_0000);
+
+        let v1: VirtualAddress = v2.into();
+        assert_eq!(v1.as_usize(), 0xffff_ffc0_8020_0000);
+
+        let back: VirtAddr = v1.into();
+        assert_eq!(back, v2);
+    }
+
+    #[test]
+    fn test_null_round_trip() {
+        let v1: VirtualAddress = VirtAddr::new(0).into();
+        assert_eq!(v1.as_usize(), 0);
+
+        let v2: PhysAddr = PhysicalAddress::from_usize(0).into();
+        assert_eq!(*v2, 0);
+    }
+
+    #[test]
+    fn test_phys_range_round_trip() {
+        let v1 = PhysicalAddressRange::from_start_len(PhysicalAddress::from_usize(0x1000), 0x3000);
+
+        let v2: PhysAddrRange = v1.into();
+        assert_eq!(v2.start(), PhysAddr::new(0x1000));
+        assert_eq!(v2.end(), PhysAddr::new(0x4000));
+
+        let back: PhysicalAddressRange = v2.into();
+        assert_eq!(back, v1);
+    }
+
+    #[test]
+    fn test_virt_range_round_trip() {
+        let v2 = VirtAddrRange::new(VirtAddr::new(0x40_1234), VirtAddr::new(0x40_5678));
+
+        let v1: VirtualAddressRange = v2.into();
+        assert_eq!(v1.start().as_usize(), 0x40_1234);
+        assert_eq!(v1.end().as_usize(), 0x40_5678);
+        assert_eq!(v1.len(), v2.len());
+
+        let back: VirtAddrRange = v1.into();
+        assert_eq!(back, v2);
+    }
+}
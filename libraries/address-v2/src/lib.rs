@@ -27,6 +27,9 @@ mod virt_addr_range;
 mod virt_page;
 mod virt_page_range;
 
+#[cfg(any(feature = "compat", test))]
+pub mod compat;
+
 pub use phys_addr::PhysAddr;
 pub use phys_addr_range::PhysAddrRange;
 pub use phys_page::PhysPage;
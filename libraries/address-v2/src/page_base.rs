@@ -167,6 +167,22 @@ macro_rules! impl_page {
             pub const fn new_custom_unchecked(addr: $addr_type, size: usize) -> Self {
                 Self { addr, size }
             }
+
+            /// Splits an address into the 4KB page containing it and the offset within that page.
+            ///
+            /// # Examples
+            /// ```rust
+            /// # use address_v2::{PhysPage, PhysAddr};
+            /// let (page, offset) = PhysPage::split_addr(PhysAddr::new(0x1234));
+            /// assert_eq!(page.addr(), PhysAddr::new(0x1000));
+            /// assert_eq!(offset, 0x234);
+            /// ```
+            #[inline(always)]
+            pub const fn split_addr(addr: $addr_type) -> (Self, usize) {
+                let page = Self::new_custom_unchecked(addr.align_down(Self::SIZE_4K), Self::SIZE_4K);
+
+                (page, addr.offset_from_alignment(Self::SIZE_4K))
+            }
         }
 
         impl $page_type {
@@ -319,6 +335,24 @@ macro_rules! impl_page {
             }
         }
 
+        impl ::core::cmp::PartialOrd for $page_type {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &Self) -> Option<::core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        /// Pages are ordered by their address first and by their size second, so pages of
+        /// different sizes at the same address are still distinct.
+        impl ::core::cmp::Ord for $page_type {
+            #[inline(always)]
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                self.addr
+                    .cmp(&other.addr)
+                    .then(self.size.cmp(&other.size))
+            }
+        }
+
         #[cfg(test)]
         mod tests {
             use super::*;
@@ -417,6 +451,36 @@ macro_rules! impl_page {
                 assert_eq!(page3.size(), 0x80000000);
             }
 
+            #[test]
+            fn test_split_aligned_addr() {
+                let (page, offset) = $page_type::split_addr($addr_type::new(0x2000));
+
+                assert_eq!(page, $page_type::new_4k($addr_type::new(0x2000)).unwrap());
+                assert_eq!(offset, 0);
+
+                let (page, offset) = $page_type::split_addr($addr_type::null);
+                assert_eq!(*page.addr(), 0);
+                assert_eq!(offset, 0);
+            }
+
+            #[test]
+            fn test_split_unaligned_addr() {
+                let (page, offset) = $page_type::split_addr($addr_type::new(0x2fff));
+
+                assert_eq!(page, $page_type::new_4k($addr_type::new(0x2000)).unwrap());
+                assert_eq!(offset, 0xfff);
+
+                // The page is always 4K, even inside a huge page
+                let (page, offset) = $page_type::split_addr($addr_type::new(0x20_1234));
+                assert_eq!(*page.addr(), 0x20_1000);
+                assert_eq!(page.size(), $page_type::SIZE_4K);
+                assert_eq!(offset, 0x234);
+
+                let (page, offset) = $page_type::split_addr($addr_type::MAX);
+                assert_eq!(*page.addr(), usize::MAX & !0xfff);
+                assert_eq!(offset, 0xfff);
+            }
+
             /// Test accessor methods
             #[test]
             fn test_accessors() {
@@ -760,6 +824,43 @@ macro_rules! impl_page {
                 assert!(page_3k.is_some());
                 assert_eq!(page_3k.unwrap().size(), size_3k);
             }
+
+            #[test]
+            fn test_order_by_addr_then_size() {
+                let small = $page_type::new_4k($addr_type::new(0x200000)).unwrap();
+                let huge = $page_type::new_2m($addr_type::new(0x200000)).unwrap();
+                let next = $page_type::new_4k($addr_type::new(0x201000)).unwrap();
+
+                assert!(small < huge);
+                assert!(huge < next);
+                assert_eq!(small.cmp(&small), ::core::cmp::Ordering::Equal);
+            }
+
+            #[test]
+            fn test_mixed_sizes_in_btree_set() {
+                use std::collections::BTreeSet;
+
+                let pages = [
+                    $page_type::new_4k($addr_type::new(0x0)).unwrap(),
+                    $page_type::new_2m($addr_type::new(0x0)).unwrap(),
+                    $page_type::new_1g($addr_type::new(0x0)).unwrap(),
+                    $page_type::new_4k($addr_type::new(0x1000)).unwrap(),
+                    $page_type::new_2m($addr_type::new(0x200000)).unwrap(),
+                ];
+
+                let mut set = BTreeSet::new();
+                for page in pages {
+                    assert!(set.insert(page));
+                }
+
+                // Only the very same page collides
+                assert!(!set.insert(pages[1]));
+                assert_eq!(set.len(), pages.len());
+
+                for page in pages {
+                    assert!(set.contains(&page));
+                }
+            }
         }
     };
 }
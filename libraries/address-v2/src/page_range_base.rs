@@ -79,6 +79,87 @@ macro_rules! impl_page_range {
                     page_count: ((end.addr() - start.addr()) as usize) / start.size(),
                 })
             }
+
+            /// Creates a new page range starting at `start` and covering `byte_len` bytes.
+            ///
+            /// # Returns
+            /// - `Some(range)` if `byte_len` is a multiple of the page size of `start`
+            /// - `None` otherwise
+            ///
+            /// # Examples
+            /// ```rust
+            /// # use address_v2::{PhysPage, PhysPageRange, PhysAddr};
+            /// let start = PhysPage::new_4k(PhysAddr::new(0x1000)).unwrap();
+            /// let range = PhysPageRange::from_start_len(start, 0x3000).unwrap();
+            /// assert_eq!(range.len(), 3);
+            ///
+            /// assert!(PhysPageRange::from_start_len(start, 0x1800).is_none());
+            /// ```
+            pub const fn from_start_len(start: $page_type, byte_len: usize) -> Option<Self> {
+                if !byte_len.is_multiple_of(start.size()) {
+                    return None;
+                }
+
+                Some(Self {
+                    start,
+                    page_count: byte_len / start.size(),
+                })
+            }
+
+            /// Creates the smallest range of 4K pages covering `[start, end)`.
+            ///
+            /// The start address is aligned down and the end address is aligned up.
+            ///
+            /// # Examples
+            /// ```rust
+            /// # use address_v2::{PhysAddr, PhysPageRange};
+            /// let range = PhysPageRange::covering(PhysAddr::new(0x1234), PhysAddr::new(0x3001));
+            /// assert_eq!(range.start().addr(), PhysAddr::new(0x1000));
+            /// assert_eq!(range.end().addr(), PhysAddr::new(0x4000));
+            /// ```
+            pub const fn covering(start: $addr_type, end: $addr_type) -> Self {
+                debug_assert!(*start <= *end);
+
+                Self::from_aligned(
+                    start.align_down($page_type::SIZE_4K),
+                    end.align_up($page_type::SIZE_4K),
+                )
+            }
+
+            /// Creates the largest range of 4K pages contained in `[start, end)`.
+            ///
+            /// The start address is aligned up and the end address is aligned down,
+            /// the range is empty if no whole page fits.
+            ///
+            /// # Examples
+            /// ```rust
+            /// # use address_v2::{PhysAddr, PhysPageRange};
+            /// let range = PhysPageRange::contained(PhysAddr::new(0x1234), PhysAddr::new(0x3001));
+            /// assert_eq!(range.start().addr(), PhysAddr::new(0x2000));
+            /// assert_eq!(range.end().addr(), PhysAddr::new(0x3000));
+            ///
+            /// assert!(PhysPageRange::contained(PhysAddr::new(0x1234), PhysAddr::new(0x1fff)).is_empty());
+            /// ```
+            pub const fn contained(start: $addr_type, end: $addr_type) -> Self {
+                debug_assert!(*start <= *end);
+
+                let start = start.align_up($page_type::SIZE_4K);
+                let end = end.align_down($page_type::SIZE_4K);
+
+                if *start >= *end {
+                    return Self::from_aligned(start, start);
+                }
+
+                Self::from_aligned(start, end)
+            }
+
+            #[inline(always)]
+            const fn from_aligned(start: $addr_type, end: $addr_type) -> Self {
+                Self {
+                    start: $page_type::new_custom_unchecked(start, $page_type::SIZE_4K),
+                    page_count: (*end - *start) / $page_type::SIZE_4K,
+                }
+            }
         }
 
         impl $page_range_type {
@@ -192,7 +273,8 @@ macro_rules! impl_page_range {
 
             /// Checks if this range contains a specific page.
             ///
-            /// The page must have the same size as the pages in this range.
+            /// The page may have a different size, in which case all of its bytes must be covered.
+            /// Probing with a mismatched size never panics, and an empty range contains no page.
             ///
             /// # Parameters
             /// - `page`: The page to check for containment
@@ -213,19 +295,20 @@ macro_rules! impl_page_range {
             /// assert!(!range.contains_page(outside_page));
             /// ```
             pub const fn contains_page(&self, page: $page_type) -> bool {
-                // Pages must have the same size to be comparable
-                debug_assert!(self.start.size() == page.size());
-
+                // Compare the covered bytes, so pages of a different size are handled as well
                 let start_addr = *self.start().addr();
                 let end_addr = *self.end().addr();
                 let page_addr = *page.addr();
 
-                start_addr <= page_addr && page_addr < end_addr
+                // Written without `page_addr + page.size()`, which overflows for the last page
+                start_addr <= page_addr
+                    && page_addr <= end_addr
+                    && end_addr - page_addr >= page.size()
             }
 
             /// Checks if this range completely contains another range.
             ///
-            /// Both ranges must have compatible page sizes for comparison.
+            /// The ranges are compared by the bytes they cover, regardless of their page sizes.
             ///
             /// # Parameters
             /// - `other`: The range to check for containment
@@ -246,9 +329,6 @@ macro_rules! impl_page_range {
             /// assert!(!range2.contains(range1));
             /// ```
             pub const fn contains(&self, other: Self) -> bool {
-                // Ranges must have compatible page sizes
-                debug_assert!(self.start.size() == other.start.size());
-
                 let self_start = *self.start().addr();
                 let self_end = *self.end().addr();
                 let other_start = *other.start().addr();
@@ -259,8 +339,9 @@ macro_rules! impl_page_range {
 
             /// Checks if this range intersects with another range.
             ///
-            /// Two ranges intersect if they have any overlapping pages.
-            /// Both ranges must have compatible page sizes.
+            /// Two ranges intersect if they have any overlapping bytes, regardless of their
+            /// page sizes.
+            /// An empty range never intersects with anything, even when placed inside the other range.
             ///
             /// # Parameters
             /// - `other`: The range to check for intersection
@@ -280,8 +361,9 @@ macro_rules! impl_page_range {
             /// assert!(range1.intersects(range2));
             /// ```
             pub const fn intersects(&self, other: Self) -> bool {
-                // Ranges must have compatible page sizes
-                debug_assert!(self.start.size() == other.start.size());
+                if self.is_empty() || other.is_empty() {
+                    return false;
+                }
 
                 let self_start = *self.start().addr();
                 let self_end = *self.end().addr();
@@ -301,7 +383,7 @@ macro_rules! impl_page_range {
             ///
             /// # Returns
             /// - `Some(range)` containing the overlapping pages if ranges intersect
-            /// - `None` if ranges don't intersect
+            /// - `None` if ranges don't intersect or have different page sizes
             ///
             /// # Examples
             /// ```rust
@@ -333,9 +415,8 @@ macro_rules! impl_page_range {
                     other.end()
                 };
 
-                // Since we've guaranteed the ranges are intersecting and have the same page size,
-                // the unwrap is safe here.
-                Some(Self::from_start_end(start, end).unwrap())
+                // Ranges of different page sizes have no common representation
+                Self::from_start_end(start, end)
             }
 
             /// Checks if this range is directly adjacent to another range.
@@ -354,8 +435,6 @@ macro_rules! impl_page_range {
             /// ```
             #[inline(always)]
             pub const fn is_adjacent(self, other: Self) -> bool {
-                debug_assert!(self.start.size() == other.start.size());
-
                 self.end().addr() == other.start().addr() || other.end().addr() == self.start().addr()
             }
 
@@ -407,9 +486,8 @@ macro_rules! impl_page_range {
                     other.end()
                 };
 
-                // The unwrap is intentional here since we have already validated the pages
-                // to ensure they are aligned and have the same size.
-                Some(Self::from_start_end(start, end).unwrap())
+                // Ranges of different page sizes have no common representation
+                Self::from_start_end(start, end)
             }
         }
 
@@ -452,6 +530,29 @@ macro_rules! impl_page_range {
                     end: self.end(),
                 }
             }
+
+            /// Creates an iterator over the pages in this range that are not contained in `other`.
+            ///
+            /// # Parameters
+            /// - `other`: The range whose pages are skipped
+            ///
+            /// # Returns
+            /// An iterator that yields each page of the range outside of `other` in order
+            ///
+            /// # Examples
+            /// ```rust
+            /// # use address_v2::{PhysPage, PhysPageRange, PhysAddr};
+            /// let range = PhysPageRange::new(PhysPage::new_4k(PhysAddr::new(0x1000)).unwrap(), 4);
+            /// let hole = PhysPageRange::new(PhysPage::new_4k(PhysAddr::new(0x2000)).unwrap(), 2);
+            ///
+            /// let pages: Vec<_> = range.pages_excluding(hole).collect();
+            /// assert_eq!(pages.len(), 2);
+            /// assert_eq!(pages[0].addr(), PhysAddr::new(0x1000));
+            /// assert_eq!(pages[1].addr(), PhysAddr::new(0x4000));
+            /// ```
+            pub fn pages_excluding(&self, other: Self) -> impl Iterator<Item = $page_type> {
+                self.iter().filter(move |page| !other.contains_page(*page))
+            }
         }
 
         impl IntoIterator for $page_range_type {
@@ -563,6 +664,72 @@ macro_rules! impl_page_range {
                 assert!(invalid_range.is_none());
             }
 
+            #[test]
+            fn test_page_range_from_start_len() {
+                let start = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
+
+                let range = $page_range_type::from_start_len(start, 0x3000).unwrap();
+                assert_eq!(range.start(), start);
+                assert_eq!(range.len(), 3);
+                assert_eq!(range.addr_len(), 0x3000);
+
+                let empty = $page_range_type::from_start_len(start, 0).unwrap();
+                assert!(empty.is_empty());
+
+                // Huge pages count in their own size
+                let huge = $page_type::new_2m(<$addr_type>::new(0x200000)).unwrap();
+                let range = $page_range_type::from_start_len(huge, 0x400000).unwrap();
+                assert_eq!(range.len(), 2);
+            }
+
+            #[test]
+            fn test_page_range_from_start_len_not_multiple() {
+                let start = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
+                assert!($page_range_type::from_start_len(start, 0x1800).is_none());
+                assert!($page_range_type::from_start_len(start, 1).is_none());
+
+                let huge = $page_type::new_2m(<$addr_type>::new(0x200000)).unwrap();
+                assert!($page_range_type::from_start_len(huge, 0x1000).is_none());
+            }
+
+            #[test]
+            fn test_page_range_covering() {
+                let range = $page_range_type::covering(<$addr_type>::new(0x1234), <$addr_type>::new(0x3001));
+
+                assert_eq!(range.start().addr(), <$addr_type>::new(0x1000));
+                assert_eq!(range.end().addr(), <$addr_type>::new(0x4000));
+                assert_eq!(range.len(), 3);
+
+                // Aligned bounds are kept as is
+                let aligned = $page_range_type::covering(<$addr_type>::new(0x1000), <$addr_type>::new(0x3000));
+                assert_eq!(aligned.start().addr(), <$addr_type>::new(0x1000));
+                assert_eq!(aligned.len(), 2);
+            }
+
+            #[test]
+            fn test_page_range_contained() {
+                let range = $page_range_type::contained(<$addr_type>::new(0x1234), <$addr_type>::new(0x3001));
+
+                assert_eq!(range.start().addr(), <$addr_type>::new(0x2000));
+                assert_eq!(range.end().addr(), <$addr_type>::new(0x3000));
+                assert_eq!(range.len(), 1);
+
+                let aligned = $page_range_type::contained(<$addr_type>::new(0x1000), <$addr_type>::new(0x3000));
+                assert_eq!(aligned.start().addr(), <$addr_type>::new(0x1000));
+                assert_eq!(aligned.len(), 2);
+            }
+
+            #[test]
+            fn test_page_range_contained_empty() {
+                // Within a single page
+                let range = $page_range_type::contained(<$addr_type>::new(0x1234), <$addr_type>::new(0x1fff));
+                assert!(range.is_empty());
+
+                // Straddles a page boundary without a whole page in between
+                let range = $page_range_type::contained(<$addr_type>::new(0x1800), <$addr_type>::new(0x2800));
+                assert!(range.is_empty());
+            }
+
             #[test]
             fn test_page_range_accessors() {
                 let start_page = $page_type::new_4k(<$addr_type>::new(0x2000)).unwrap();
@@ -607,6 +774,43 @@ macro_rules! impl_page_range {
                 assert!(!range.contains_page(outside_page));
             }
 
+            #[test]
+            fn test_page_range_contains_page_mismatched_size() {
+                let huge = $page_type::new_2m(<$addr_type>::new(0x200000)).unwrap();
+
+                // Only part of the huge page is covered
+                let small = $page_range_type::new($page_type::new_4k(<$addr_type>::new(0x200000)).unwrap(), 3);
+                assert!(!small.contains_page(huge));
+
+                let empty = $page_range_type::new($page_type::new_4k(<$addr_type>::new(0x200000)).unwrap(), 0);
+                assert!(!empty.contains_page(huge));
+                assert!(!empty.contains_page(empty.start()));
+
+                // Every byte of the huge page is covered by the small pages
+                let covering = $page_range_type::new($page_type::new_4k(<$addr_type>::new(0x200000)).unwrap(), 512);
+                assert!(covering.contains_page(huge));
+            }
+
+            #[test]
+            fn test_page_range_contains_last_page() {
+                let last = $page_type::new_4k(<$addr_type>::new(usize::MAX & !0xfff)).unwrap();
+
+                // Ends right below the last page of the address space
+                let below = $page_range_type::new(
+                    $page_type::new_4k(<$addr_type>::new((usize::MAX & !0xfff) - 0x2000)).unwrap(),
+                    2,
+                );
+                assert!(!below.contains_page(last));
+                assert!(below.contains_page(below.start()));
+
+                const CONTAINED: bool = $page_range_type::new(
+                    $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap(),
+                    1,
+                )
+                .contains_page($page_type::new_4k(<$addr_type>::new(usize::MAX & !0xfff)).unwrap());
+                assert!(!CONTAINED);
+            }
+
             #[test]
             fn test_page_range_contains() {
                 let start1 = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
@@ -651,6 +855,21 @@ macro_rules! impl_page_range {
                 assert!(!range1.intersects(range4));
             }
 
+            #[test]
+            fn test_page_range_intersects_empty() {
+                let range = $page_range_type::new($page_type::new_4k(<$addr_type>::new(0x1000)).unwrap(), 3); // 0x1000..0x4000
+
+                // At the shared boundaries and inside the range
+                for addr in [0x1000, 0x2000, 0x4000] {
+                    let empty = $page_range_type::new($page_type::new_4k(<$addr_type>::new(addr)).unwrap(), 0);
+
+                    assert!(!range.intersects(empty));
+                    assert!(!empty.intersects(range));
+                    assert!(!empty.intersects(empty));
+                    assert_eq!(range.intersection(empty), None);
+                }
+            }
+
             #[test]
             fn test_page_range_intersection() {
                 let start1 = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
@@ -677,6 +896,29 @@ macro_rules! impl_page_range {
                 assert!(no_intersection.is_none());
             }
 
+            #[test]
+            fn test_page_range_mixed_sizes() {
+                let small = $page_range_type::new($page_type::new_4k(<$addr_type>::new(0x200000)).unwrap(), 2);
+                let huge = $page_range_type::new($page_type::new_2m(<$addr_type>::new(0x200000)).unwrap(), 1);
+
+                // Compared by the covered bytes
+                assert!(huge.contains(small));
+                assert!(!small.contains(huge));
+                assert!(small.intersects(huge));
+                assert!(huge.contains_page(small.start()));
+                assert!(!small.contains_page(huge.start()));
+
+                // Ends up with the pages of the huge range only
+                assert_eq!(small.merge(huge), Some(huge));
+
+                // No common representation
+                assert_eq!(small.intersection(huge), None);
+
+                let adjacent = $page_range_type::new($page_type::new_4k(<$addr_type>::new(0x400000)).unwrap(), 1);
+                assert!(huge.is_adjacent(adjacent));
+                assert_eq!(huge.merge(adjacent), None);
+            }
+
             #[test]
             fn test_page_range_merge() {
                 // Try merge non-adjacent ranges (should fail)
@@ -732,6 +974,35 @@ macro_rules! impl_page_range {
                 }
             }
 
+            #[test]
+            fn test_page_range_pages_excluding() {
+                let start_page = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
+                let range = $page_range_type::new(start_page, 6);
+
+                let hole_page = $page_type::new_4k(<$addr_type>::new(0x3000)).unwrap();
+                let hole = $page_range_type::new(hole_page, 2);
+
+                let pages: Vec<_> = range.pages_excluding(hole).collect();
+                assert_eq!(pages.len(), 4);
+
+                assert_eq!(*pages[0].addr(), 0x1000);
+                assert_eq!(*pages[1].addr(), 0x2000);
+                assert_eq!(*pages[2].addr(), 0x5000);
+                assert_eq!(*pages[3].addr(), 0x6000);
+            }
+
+            #[test]
+            fn test_page_range_pages_excluding_disjoint_and_whole() {
+                let start_page = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
+                let range = $page_range_type::new(start_page, 3);
+
+                let far_page = $page_type::new_4k(<$addr_type>::new(0x10000)).unwrap();
+                let far = $page_range_type::new(far_page, 2);
+
+                assert!(range.pages_excluding(far).eq(range.iter()));
+                assert_eq!(range.pages_excluding(range).count(), 0);
+            }
+
             #[test]
             fn test_page_range_into_iterator() {
                 let start_page = $page_type::new_4k(<$addr_type>::new(0x1000)).unwrap();
@@ -1,7 +1,4 @@
-use crate::{PhysAddrRange, PhysPage};
-
-#[cfg(test)]
-use crate::PhysAddr;
+use crate::{PhysAddr, PhysAddrRange, PhysPage};
 
 impl_page_range!(PhysPageRange, PhysPage, PhysAddr, PhysAddrRange,
     /// Represents a range of physical memory pages.
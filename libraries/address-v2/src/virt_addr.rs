@@ -34,6 +34,54 @@ impl VirtAddr {
     pub unsafe fn as_mut_ptr<T>(self) -> *mut T {
         self.0 as *mut T
     }
+
+    /// Returns the address as a raw pointer of type `*const T`, or `None` if the address
+    /// is null.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`VirtAddr::as_ptr`], a non-null address is not necessarily valid.
+    #[inline(always)]
+    pub unsafe fn try_as_ptr<T>(self) -> Option<*const T> {
+        if self.is_null() {
+            None
+        } else {
+            Some(self.0 as *const T)
+        }
+    }
+
+    /// Returns the address as a raw pointer of type `*mut T`, or `None` if the address
+    /// is null.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`VirtAddr::as_mut_ptr`], a non-null address is not necessarily valid.
+    #[inline(always)]
+    pub unsafe fn try_as_mut_ptr<T>(self) -> Option<*mut T> {
+        if self.is_null() {
+            None
+        } else {
+            Some(self.0 as *mut T)
+        }
+    }
+
+    /// Returns the address of the referenced value itself.
+    ///
+    /// Unlike `From<&T>`, this never dereferences `value`, so it is the way to take the
+    /// address of a smart pointer or a reference, e.g. the `Box` rather than its content.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use address_v2::VirtAddr;
+    /// let boxed = Box::new(42);
+    ///
+    /// assert_eq!(*VirtAddr::from_ref(&boxed), &boxed as *const _ as usize);
+    /// assert_eq!(*VirtAddr::from(&boxed), boxed.as_ref() as *const _ as usize);
+    /// ```
+    #[inline(always)]
+    pub fn from_ref<T: ?Sized>(value: &T) -> Self {
+        VirtAddr::new(value as *const T as *const () as usize)
+    }
 }
 
 impl<T> From<*const T> for VirtAddr {
@@ -43,6 +91,13 @@ impl<T> From<*const T> for VirtAddr {
     }
 }
 
+// Conversions from references are resolved as follows:
+// 1. If `T: Deref`, the reference is dereferenced and the conversion applies again to the
+//    target, so `&Box<[i32]>`, `&Vec<i32>` or `&&i32` all yield the address of the data.
+// 2. Otherwise, the address of the referenced value itself is taken.
+//
+// This relies on `specialization`, use `VirtAddr::from_ref` when the address of a
+// `Deref` type itself is intended.
 impl<T: ?Sized> From<&T> for VirtAddr
 where
     T: Deref,
@@ -249,6 +304,23 @@ mod virt_addr_tests {
         });
     }
 
+    #[test]
+    fn test_try_as_ptr() {
+        let null_addr = VirtAddr::null;
+        assert_eq!(unsafe { null_addr.try_as_ptr::<i32>() }, None);
+        assert_eq!(unsafe { null_addr.try_as_mut_ptr::<i32>() }, None);
+
+        let addr = VirtAddr::new(0x1000);
+        assert_eq!(
+            unsafe { addr.try_as_ptr::<i32>() },
+            Some(0x1000 as *const i32)
+        );
+        assert_eq!(
+            unsafe { addr.try_as_mut_ptr::<i32>() },
+            Some(0x1000 as *mut i32)
+        );
+    }
+
     #[test]
     fn test_value_into() {
         let value: i32 = 42;
@@ -348,4 +420,67 @@ mod virt_addr_tests {
 
         assert_eq!(*addr, boxed.deref().as_ptr() as usize);
     }
+
+    #[test]
+    fn test_nested_ref_from() {
+        let value: i32 = 42;
+        let nested: &&&i32 = &&&value;
+
+        let addr: VirtAddr = From::from(nested);
+
+        assert_eq!(*addr, &value as *const _ as usize);
+    }
+
+    #[test]
+    fn test_vec_from() {
+        let vec = vec![0x12, 0x34, 0x56, 0x78];
+
+        let addr: VirtAddr = From::from(&vec);
+
+        assert_eq!(*addr, vec.as_ptr() as usize);
+    }
+
+    #[test]
+    fn test_string_from() {
+        let string = String::from("hello");
+
+        let addr: VirtAddr = From::from(&string);
+
+        assert_eq!(*addr, string.as_ptr() as usize);
+    }
+
+    #[test]
+    fn test_rc_from() {
+        let rc = std::rc::Rc::new(42);
+
+        let addr: VirtAddr = From::from(&rc);
+
+        assert_eq!(*addr, std::rc::Rc::as_ptr(&rc) as usize);
+    }
+
+    #[test]
+    fn test_from_ref_does_not_deref() {
+        let value: i32 = 42;
+        let reference: &i32 = &value;
+        let boxed = Box::new(42);
+
+        assert_eq!(
+            *VirtAddr::from_ref(&reference),
+            &reference as *const _ as usize
+        );
+        assert_eq!(*VirtAddr::from_ref(&boxed), &boxed as *const _ as usize);
+
+        // The target of a reference can still be addressed explicitly
+        assert_eq!(*VirtAddr::from_ref(reference), &value as *const _ as usize);
+        assert_eq!(*VirtAddr::from_ref(boxed.deref()), *VirtAddr::from(&boxed));
+    }
+
+    #[test]
+    fn test_from_ref_unsized() {
+        let slice: &[i32] = &[0x12, 0x34];
+        let str: &str = "hello";
+
+        assert_eq!(*VirtAddr::from_ref(slice), slice.as_ptr() as usize);
+        assert_eq!(*VirtAddr::from_ref(str), str.as_ptr() as usize);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::VirtAddr;
+use crate::{VirtAddr, VirtPage, VirtPageRange};
 
 impl_range!(VirtAddrRange, VirtAddr,
     /// Represents a range of virtual addresses.
@@ -9,6 +9,34 @@ impl_range!(VirtAddrRange, VirtAddr,
     /// and user space address ranges.
 );
 
+impl VirtAddrRange {
+    /// Converts this range into the range of pages of `page_size` it touches.
+    /// The start is aligned down and the end is aligned up.
+    ///
+    /// Returns `None` if `page_size` is zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use address_v2::{VirtAddr, VirtAddrRange, VirtPage};
+    /// let range = VirtAddrRange::new(VirtAddr::new(0x1234), VirtAddr::new(0x2345));
+    /// let pages = range.to_page_range(VirtPage::SIZE_4K).unwrap();
+    /// assert_eq!(pages.start().addr(), VirtAddr::new(0x1000));
+    /// assert_eq!(pages.len(), 2);
+    /// ```
+    pub const fn to_page_range(self, page_size: usize) -> Option<VirtPageRange> {
+        if page_size == 0 {
+            return None;
+        }
+
+        let aligned = self.align_to(page_size);
+
+        match VirtPage::new_custom(aligned.start(), page_size) {
+            Some(start) => Some(VirtPageRange::new(start, aligned.len() / page_size)),
+            None => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod virt_range_tests {
     use super::*;
@@ -125,4 +153,53 @@ mod virt_range_tests {
         let invalid_stack = VirtAddr::new(0x7FFF_8000_0000);
         assert!(!stack_range.contains_addr(invalid_stack));
     }
+
+    #[test]
+    fn test_virt_range_page_count_exact() {
+        let range = VirtAddrRange::from_start_len(VirtAddr::new(0x400000), 0x3000);
+
+        assert_eq!(range.page_count(0x1000), 3);
+        assert_eq!(range.aligned_page_count(0x1000), 3);
+
+        let pages = range.to_page_range(0x1000).unwrap();
+        assert_eq!(pages.start().addr(), VirtAddr::new(0x400000));
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages.as_addr_range(), range);
+    }
+
+    #[test]
+    fn test_virt_range_page_count_partial() {
+        // Shorter than two pages, but straddles three of them
+        let range = VirtAddrRange::new(VirtAddr::new(0x400800), VirtAddr::new(0x402001));
+
+        assert_eq!(range.page_count(0x1000), 2);
+        assert_eq!(range.aligned_page_count(0x1000), 3);
+
+        let pages = range.to_page_range(0x1000).unwrap();
+        assert_eq!(pages.start().addr(), VirtAddr::new(0x400000));
+        assert_eq!(pages.len(), 3);
+
+        let huge_pages = range.to_page_range(0x200000).unwrap();
+        assert_eq!(huge_pages.start().addr(), VirtAddr::new(0x400000));
+        assert_eq!(huge_pages.len(), 1);
+    }
+
+    #[test]
+    fn test_virt_range_page_count_zero_length() {
+        let range = VirtAddrRange::from_start_len(VirtAddr::new(0x400800), 0);
+
+        assert_eq!(range.page_count(0x1000), 0);
+        assert_eq!(range.aligned_page_count(0x1000), 1);
+
+        let aligned = VirtAddrRange::from_start_len(VirtAddr::new(0x400000), 0);
+        assert_eq!(aligned.aligned_page_count(0x1000), 0);
+        assert!(aligned.to_page_range(0x1000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_virt_range_to_page_range_zero_size() {
+        let range = VirtAddrRange::from_start_len(VirtAddr::new(0x400000), 0x1000);
+
+        assert!(range.to_page_range(0).is_none());
+    }
 }
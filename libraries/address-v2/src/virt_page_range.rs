@@ -1,7 +1,4 @@
-use crate::{VirtAddrRange, VirtPage};
-
-#[cfg(test)]
-use crate::VirtAddr;
+use crate::{VirtAddr, VirtAddrRange, VirtPage};
 
 impl_page_range!(VirtPageRange, VirtPage, VirtAddr, VirtAddrRange,
     /// Represents a range of virtual memory pages.
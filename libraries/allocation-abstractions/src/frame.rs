@@ -1,8 +1,11 @@
-use core::ops::{Deref, Drop, Range};
+use core::{
+    fmt::{Debug, Formatter},
+    ops::{Deref, Drop, Range},
+};
 
-use address::PhysicalAddress;
+use abstractions::IUsizeAlias;
+use address::{IToPageNum, PhysicalAddress, PhysicalPageNum, PhysicalPageNumRange};
 
-#[derive(Debug)]
 pub struct FrameDesc(pub PhysicalAddress);
 
 impl FrameDesc {
@@ -16,6 +19,16 @@ impl FrameDesc {
     pub unsafe fn new(addr: PhysicalAddress) -> Self {
         FrameDesc(addr)
     }
+
+    /// The physical address of the frame
+    pub fn addr(&self) -> PhysicalAddress {
+        self.0
+    }
+
+    /// The 4K page the frame occupies
+    pub fn page(&self) -> PhysicalPageNum {
+        self.0.to_floor_page_num()
+    }
 }
 
 impl Deref for FrameDesc {
@@ -26,6 +39,12 @@ impl Deref for FrameDesc {
     }
 }
 
+impl Debug for FrameDesc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "FrameDesc({:#x})", self.0.as_usize())
+    }
+}
+
 impl Drop for FrameDesc {
     fn drop(&mut self) {
         panic!("You must manually deallocate frames")
@@ -49,6 +68,29 @@ impl FrameRangeDesc {
             range: start..start + len,
         }
     }
+
+    /// The 4K pages covered by the range
+    pub fn pages(&self) -> PhysicalPageNumRange {
+        PhysicalPageNumRange::from_start_end(
+            self.range.start.to_floor_page_num(),
+            self.range.end.to_ceil_page_num(),
+        )
+    }
+
+    /// Iterates over the 4K pages of the range without consuming the descriptor
+    pub fn iter(&self) -> impl Iterator<Item = PhysicalPageNum> {
+        self.pages().iter()
+    }
+
+    /// The number of 4K pages in the range
+    pub fn len(&self) -> usize {
+        self.pages().page_count()
+    }
+
+    /// Whether the range covers no page
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 impl Deref for FrameRangeDesc {
@@ -59,6 +101,18 @@ impl Deref for FrameRangeDesc {
     }
 }
 
+impl Debug for FrameRangeDesc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "FrameRangeDesc({:#x}..{:#x}, {} pages)",
+            self.range.start.as_usize(),
+            self.range.end.as_usize(),
+            self.len()
+        )
+    }
+}
+
 impl Drop for FrameRangeDesc {
     fn drop(&mut self) {
         panic!("You must manually deallocate frames")
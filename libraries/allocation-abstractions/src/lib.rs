@@ -1,5 +1,7 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use abstractions::IUsizeAlias;
+use address::{PhysicalAddress, PhysicalAddressRange};
 use alloc::vec::Vec;
 
 #[cfg(feature = "std")]
@@ -11,6 +13,16 @@ mod frame;
 
 pub use frame::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReserveError {
+    /// The range is not aligned to page boundaries
+    NotAligned,
+    /// The range is outside of the memory managed by the allocator
+    OutOfRange,
+    /// Some frames in the range are already allocated
+    InUse,
+}
+
 pub trait IFrameAllocator {
     fn alloc_frame(&mut self) -> Option<FrameDesc>;
     // Allocates `count` frames and returns them as a vector, no guarantee that the frames are contiguous
@@ -18,7 +30,45 @@ pub trait IFrameAllocator {
     // Allocates `count` frames and returns them as a range, guaranteeing that the frames are contiguous
     fn alloc_contiguous(&mut self, count: usize) -> Option<FrameRangeDesc>;
 
+    /// Allocates `count` contiguous frames starting at a multiple of `align`, e.g. 2M for
+    /// huge pages. `align` must be a power of two, alignments up to a frame are always met.
+    ///
+    /// The default implementation over-allocates and deallocates the frames around the
+    /// aligned block one by one, so `dealloc` must accept any frame of a contiguous range.
+    fn alloc_contiguous_aligned(&mut self, count: usize, align: usize) -> Option<FrameRangeDesc> {
+        debug_assert!(align.is_power_of_two());
+
+        if align <= constants::PAGE_SIZE {
+            return self.alloc_contiguous(count);
+        }
+
+        let slack = align / constants::PAGE_SIZE - 1;
+        let outer = self.alloc_contiguous(count + slack)?;
+
+        let (outer_start, outer_end) = (outer.start, outer.end);
+        core::mem::forget(outer);
+
+        let start = PhysicalAddress::from_usize(outer_start.as_usize().next_multiple_of(align));
+        let end = start + count * constants::PAGE_SIZE;
+
+        let mut trim = |from: PhysicalAddress, to: PhysicalAddress| {
+            let mut cursor = from;
+            while cursor < to {
+                self.dealloc(unsafe { FrameDesc::new(cursor) });
+                cursor += constants::PAGE_SIZE;
+            }
+        };
+
+        trim(outer_start, start);
+        trim(end, outer_end);
+
+        Some(unsafe { FrameRangeDesc::new(start, count * constants::PAGE_SIZE) })
+    }
+
     fn dealloc(&mut self, frame: FrameDesc);
 
     fn dealloc_range(&mut self, range: FrameRangeDesc);
+
+    // Removes the range from the allocatable pool, e.g. the kernel image, the device tree or MMIO regions
+    fn reserve(&mut self, range: PhysicalAddressRange) -> Result<(), ReserveError>;
 }
@@ -0,0 +1,444 @@
+use abstractions::operations::IUsizeAlias;
+use address::{IAlignableAddress, PhysicalAddress, PhysicalAddressRange};
+use alloc::vec::Vec;
+use allocation_abstractions::{FrameDesc, FrameRangeDesc, IFrameAllocator, ReserveError};
+
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+/// A frame allocator that tracks every frame with one bit, so frames can be freed and
+/// reserved in any order without fragmenting the bookkeeping.
+///
+/// The bitmap is provided by the caller, use [`BitmapFrameAllocator::bitmap_len`] to get the
+/// number of words required for a given memory range.
+pub struct BitmapFrameAllocator<'a> {
+    top: PhysicalAddress,
+    bottom: PhysicalAddress,
+    // a set bit means the frame is allocated or reserved
+    bitmap: &'a mut [u64],
+    // no free frame exists below this index
+    hint: usize,
+}
+
+impl<'a> BitmapFrameAllocator<'a> {
+    pub fn new(top: PhysicalAddress, bottom: PhysicalAddress, bitmap: &'a mut [u64]) -> Self {
+        debug_assert!(bottom.is_page_aligned() && top.is_page_aligned());
+        debug_assert!(bottom <= top);
+
+        let frames = (top - bottom).as_usize() / constants::PAGE_SIZE;
+
+        assert!(
+            bitmap.len() >= Self::bitmap_len(frames),
+            "bitmap is too small for the given memory range"
+        );
+
+        bitmap.fill(0);
+
+        let mut allocator = Self {
+            top,
+            bottom,
+            bitmap,
+            hint: 0,
+        };
+
+        // Bits beyond the last frame must never be handed out
+        for idx in frames..allocator.bitmap.len() * BITS_PER_WORD {
+            allocator.set(idx);
+        }
+
+        allocator
+    }
+
+    /// The number of words the bitmap needs to track `frames` frames
+    pub const fn bitmap_len(frames: usize) -> usize {
+        frames.div_ceil(BITS_PER_WORD)
+    }
+
+    pub fn top(&self) -> PhysicalAddress {
+        self.top
+    }
+
+    pub fn bottom(&self) -> PhysicalAddress {
+        self.bottom
+    }
+
+    /// Checks if the given physical memory is managed by this allocator
+    pub fn check_paddr(&self, paddr: PhysicalAddress, len: usize) -> bool {
+        self.bottom <= paddr && paddr + len <= self.top
+    }
+
+    fn frame_count(&self) -> usize {
+        (self.top - self.bottom).as_usize() / constants::PAGE_SIZE
+    }
+
+    fn index_of(&self, paddr: PhysicalAddress) -> usize {
+        (paddr - self.bottom).as_usize() / constants::PAGE_SIZE
+    }
+
+    fn paddr_of(&self, idx: usize) -> PhysicalAddress {
+        self.bottom + idx * constants::PAGE_SIZE
+    }
+
+    fn is_set(&self, idx: usize) -> bool {
+        self.bitmap[idx / BITS_PER_WORD] & (1 << (idx % BITS_PER_WORD)) != 0
+    }
+
+    fn set(&mut self, idx: usize) {
+        self.bitmap[idx / BITS_PER_WORD] |= 1 << (idx % BITS_PER_WORD);
+    }
+
+    fn clear(&mut self, idx: usize) {
+        self.bitmap[idx / BITS_PER_WORD] &= !(1 << (idx % BITS_PER_WORD));
+
+        if idx < self.hint {
+            self.hint = idx;
+        }
+    }
+
+    fn free_count(&self) -> usize {
+        self.bitmap
+            .iter()
+            .map(|word| word.count_zeros() as usize)
+            .sum()
+    }
+
+    fn find_free(&self) -> Option<usize> {
+        let first_word = self.hint / BITS_PER_WORD;
+
+        self.bitmap[first_word..]
+            .iter()
+            .position(|word| *word != u64::MAX)
+            .map(|offset| {
+                let word = first_word + offset;
+                word * BITS_PER_WORD + self.bitmap[word].trailing_ones() as usize
+            })
+    }
+
+    /// Finds the first run of `count` free frames
+    fn find_free_run(&self, count: usize) -> Option<usize> {
+        let frames = self.frame_count();
+
+        let mut start = self.hint;
+        let mut len = 0;
+
+        for idx in self.hint..frames {
+            if len == count {
+                break;
+            }
+
+            if self.is_set(idx) {
+                start = idx + 1;
+                len = 0;
+            } else {
+                len += 1;
+            }
+        }
+
+        (len == count).then_some(start)
+    }
+}
+
+impl IFrameAllocator for BitmapFrameAllocator<'_> {
+    fn alloc_frame(&mut self) -> Option<FrameDesc> {
+        let idx = self.find_free()?;
+
+        self.set(idx);
+        self.hint = idx + 1;
+
+        Some(unsafe { FrameDesc::new(self.paddr_of(idx)) })
+    }
+
+    fn alloc_frames(&mut self, count: usize) -> Option<Vec<FrameDesc>> {
+        // Prevent dealloc if we don't have enough frames
+        if count > self.free_count() {
+            return None;
+        }
+
+        let mut frames = Vec::with_capacity(count);
+
+        for _ in 0..count {
+            frames.push(self.alloc_frame()?);
+        }
+
+        Some(frames)
+    }
+
+    fn alloc_contiguous(&mut self, count: usize) -> Option<FrameRangeDesc> {
+        let start = self.find_free_run(count)?;
+
+        for idx in start..start + count {
+            self.set(idx);
+        }
+
+        if start == self.hint {
+            self.hint = start + count;
+        }
+
+        Some(unsafe { FrameRangeDesc::new(self.paddr_of(start), count * constants::PAGE_SIZE) })
+    }
+
+    fn dealloc(&mut self, frame: FrameDesc) {
+        // is valid frame
+        debug_assert!(self.check_paddr(frame.addr(), constants::PAGE_SIZE));
+
+        let idx = self.index_of(frame.addr());
+        core::mem::forget(frame);
+
+        // is allocated frame
+        debug_assert!(self.is_set(idx));
+
+        self.clear(idx);
+    }
+
+    fn dealloc_range(&mut self, range: FrameRangeDesc) {
+        debug_assert!(self.check_paddr(range.start, (range.end - range.start).as_usize()));
+
+        let start = self.index_of(range.start);
+        let end = self.index_of(range.end);
+        core::mem::forget(range);
+
+        for idx in start..end {
+            debug_assert!(self.is_set(idx));
+
+            self.clear(idx);
+        }
+    }
+
+    fn reserve(&mut self, range: PhysicalAddressRange) -> Result<(), ReserveError> {
+        if !range.start().is_page_aligned() || !range.end().is_page_aligned() {
+            return Err(ReserveError::NotAligned);
+        }
+
+        if !self.check_paddr(range.start(), range.len()) {
+            return Err(ReserveError::OutOfRange);
+        }
+
+        let start = self.index_of(range.start());
+        let end = self.index_of(range.end());
+
+        if (start..end).any(|idx| self.is_set(idx)) {
+            return Err(ReserveError::InUse);
+        }
+
+        for idx in start..end {
+            self.set(idx);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: usize = 200;
+    const BOTTOM: usize = 0x8020_0000;
+
+    fn setup_bitmap() -> Vec<u64> {
+        alloc::vec![0; BitmapFrameAllocator::bitmap_len(FRAMES)]
+    }
+
+    fn setup_allocator(bitmap: &mut [u64]) -> BitmapFrameAllocator<'_> {
+        BitmapFrameAllocator::new(
+            PhysicalAddress::from_usize(BOTTOM + FRAMES * constants::PAGE_SIZE),
+            PhysicalAddress::from_usize(BOTTOM),
+            bitmap,
+        )
+    }
+
+    fn paddr(idx: usize) -> PhysicalAddress {
+        PhysicalAddress::from_usize(BOTTOM + idx * constants::PAGE_SIZE)
+    }
+
+    #[test]
+    fn test_alloc_all_frames() {
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        let frames = allocator.alloc_frames(FRAMES).unwrap();
+
+        for (idx, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.addr(), paddr(idx));
+        }
+
+        // Bits beyond the last frame are never handed out
+        assert!(allocator.alloc_frame().is_none());
+        assert!(allocator.alloc_frames(1).is_none());
+
+        for frame in frames {
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_fragmented_alloc_free() {
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        let frames = allocator.alloc_frames(FRAMES).unwrap();
+
+        // Free every other frame
+        let (even, odd): (Vec<_>, Vec<_>) = frames
+            .into_iter()
+            .partition(|frame| allocator.index_of(frame.addr()).is_multiple_of(2));
+
+        for frame in even {
+            allocator.dealloc(frame);
+        }
+
+        // Plenty of free frames, but no two of them are adjacent
+        assert!(allocator.alloc_contiguous(2).is_none());
+
+        let refilled = allocator.alloc_frames(FRAMES / 2).unwrap();
+        for frame in refilled.iter() {
+            assert_eq!(allocator.index_of(frame.addr()) % 2, 0);
+        }
+
+        assert!(allocator.alloc_frame().is_none());
+
+        for frame in refilled.into_iter().chain(odd) {
+            allocator.dealloc(frame);
+        }
+
+        assert_eq!(allocator.free_count(), FRAMES);
+    }
+
+    #[test]
+    fn test_contiguous_aligned_default() {
+        const ALIGN: usize = 16 * constants::PAGE_SIZE;
+
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        let frame = allocator.alloc_frame().unwrap();
+
+        let range = allocator.alloc_contiguous_aligned(2, ALIGN).unwrap();
+        assert!(range.start.is_aligned(ALIGN));
+        assert_eq!(range.start, paddr(16));
+        assert_eq!(range.len(), 2);
+
+        // Only the aligned block stays allocated
+        assert_eq!(allocator.free_count(), FRAMES - 3);
+
+        allocator.dealloc_range(range);
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_contiguous_across_freed_frames() {
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        let mut frames = allocator.alloc_frames(FRAMES).unwrap();
+
+        // Free frames 70..74
+        let freed: Vec<_> = frames.drain(70..74).collect();
+        for frame in freed {
+            allocator.dealloc(frame);
+        }
+
+        assert!(allocator.alloc_contiguous(5).is_none());
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+        assert_eq!(range.start, paddr(70));
+        assert_eq!(range.end, paddr(74));
+
+        allocator.dealloc_range(range);
+        for frame in frames {
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_contiguous_first_fit() {
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        let first = allocator.alloc_contiguous(10).unwrap();
+        let second = allocator.alloc_contiguous(10).unwrap();
+        let third = allocator.alloc_contiguous(10).unwrap();
+
+        allocator.dealloc_range(first);
+        allocator.dealloc_range(third);
+
+        // The hole left by `first` is too small, `third` is merged with the free tail
+        let large = allocator.alloc_contiguous(15).unwrap();
+        assert_eq!(large.start, paddr(20));
+
+        let small = allocator.alloc_contiguous(5).unwrap();
+        assert_eq!(small.start, paddr(0));
+
+        allocator.dealloc_range(small);
+        allocator.dealloc_range(large);
+        allocator.dealloc_range(second);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        let reserved = PhysicalAddressRange::from_start_end(paddr(10), paddr(100));
+        allocator.reserve(reserved).unwrap();
+
+        let frames = allocator.alloc_frames(FRAMES - 90).unwrap();
+        for frame in frames.iter() {
+            assert!(!reserved.contains(frame.addr()));
+        }
+
+        assert!(allocator.alloc_frame().is_none());
+
+        for frame in frames {
+            allocator.dealloc(frame);
+        }
+
+        // Only 10 frames below the reserved range
+        let range = allocator.alloc_contiguous(11).unwrap();
+        assert_eq!(range.start, paddr(100));
+
+        allocator.dealloc_range(range);
+    }
+
+    #[test]
+    fn test_reserve_rejects_invalid_ranges() {
+        let mut bitmap = setup_bitmap();
+        let mut allocator = setup_allocator(&mut bitmap);
+
+        assert_eq!(
+            allocator.reserve(PhysicalAddressRange::from_start_len(
+                paddr(0) + 0x800,
+                constants::PAGE_SIZE
+            )),
+            Err(ReserveError::NotAligned)
+        );
+        assert_eq!(
+            allocator.reserve(PhysicalAddressRange::from_start_end(
+                paddr(FRAMES - 1),
+                paddr(FRAMES + 1)
+            )),
+            Err(ReserveError::OutOfRange)
+        );
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(
+            allocator.reserve(PhysicalAddressRange::from_start_end(paddr(0), paddr(2))),
+            Err(ReserveError::InUse)
+        );
+
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_check_paddr() {
+        let mut bitmap = setup_bitmap();
+        let allocator = setup_allocator(&mut bitmap);
+
+        assert!(allocator.check_paddr(paddr(0), FRAMES * constants::PAGE_SIZE));
+        assert!(allocator.check_paddr(paddr(FRAMES - 1), constants::PAGE_SIZE));
+        assert!(!allocator.check_paddr(paddr(FRAMES - 1), 2 * constants::PAGE_SIZE));
+        assert!(!allocator.check_paddr(
+            PhysicalAddress::from_usize(BOTTOM - constants::PAGE_SIZE),
+            constants::PAGE_SIZE
+        ));
+    }
+}
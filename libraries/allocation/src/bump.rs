@@ -0,0 +1,757 @@
+use abstractions::operations::IUsizeAlias;
+use address::{IAddressBase, IAlignableAddress, IPageNum, PhysicalAddress, PhysicalAddressRange};
+use alloc::vec::Vec;
+use allocation_abstractions::{FrameDesc, FrameRangeDesc, IFrameAllocator, ReserveError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtendError {
+    /// The new top is not aligned to page boundaries
+    NotAligned,
+    /// The new top does not grow the allocator
+    NotGrowing,
+}
+
+pub struct FrameAllocator {
+    top: PhysicalAddress,
+    bottom: PhysicalAddress,
+    // current should always point to the last frame that can be allocated
+    current: PhysicalAddress,
+    recycled: Vec<PhysicalAddress>,
+    // ranges that are never handed out, sorted by start address
+    reserved: Vec<PhysicalAddressRange>,
+}
+
+impl FrameAllocator {
+    pub fn new(top: PhysicalAddress, bottom: PhysicalAddress) -> Self {
+        FrameAllocator {
+            top,
+            bottom,
+            current: bottom,
+            recycled: Vec::new(),
+            reserved: Vec::new(),
+        }
+    }
+
+    /// Same as [`FrameAllocator::new`], but never hands out the frame at address 0, so a null
+    /// physical address can not be mistaken for a valid frame.
+    ///
+    /// Prefer this one if the managed memory may start at 0.
+    pub fn new_reserving_zero(top: PhysicalAddress, bottom: PhysicalAddress) -> Self {
+        let mut allocator = Self::new(top, bottom);
+
+        if bottom.is_null() && top > bottom {
+            allocator
+                .reserve(PhysicalAddressRange::from_start_len(
+                    bottom,
+                    constants::PAGE_SIZE,
+                ))
+                .expect("the zero frame is free in a new allocator");
+        }
+
+        allocator
+    }
+
+    pub fn top(&self) -> PhysicalAddress {
+        self.top
+    }
+
+    pub fn bottom(&self) -> PhysicalAddress {
+        self.bottom
+    }
+
+    /// Raises the top bound of the allocator, the new frames are allocatable immediately
+    pub fn extend(&mut self, new_top: PhysicalAddress) -> Result<(), ExtendError> {
+        if !new_top.is_page_aligned() {
+            return Err(ExtendError::NotAligned);
+        }
+
+        if new_top <= self.top {
+            return Err(ExtendError::NotGrowing);
+        }
+
+        self.top = new_top;
+
+        Ok(())
+    }
+
+    /// The number of frames that can still be allocated, reserved frames excluded
+    pub fn available_frames(&self) -> usize {
+        let reserved = self.reserved_frames_in(PhysicalAddressRange::new(self.bottom..self.top));
+
+        (self.top - self.bottom).as_usize() / constants::PAGE_SIZE
+            - reserved
+            - self.outstanding_frames()
+    }
+
+    /// Returns the allocator to its pristine state, reserved ranges are kept.
+    ///
+    /// Every allocated frame must have been deallocated, as they would be handed out again.
+    pub fn reset(&mut self) {
+        debug_assert_eq!(
+            self.outstanding_frames(),
+            0,
+            "resetting an allocator with outstanding frames"
+        );
+
+        self.recycled.clear();
+        self.current = self.bottom;
+    }
+
+    /// The number of frames allocated and not yet deallocated
+    fn outstanding_frames(&self) -> usize {
+        let handed_out = PhysicalAddressRange::new(self.bottom..self.current);
+
+        handed_out.len() / constants::PAGE_SIZE
+            - self.reserved_frames_in(handed_out)
+            - self.recycled.len()
+    }
+
+    fn reserved_frames_in(&self, range: PhysicalAddressRange) -> usize {
+        self.reserved
+            .iter()
+            .filter_map(|reserved| reserved.intersection(range))
+            .map(|overlap| overlap.len() / constants::PAGE_SIZE)
+            .sum()
+    }
+
+    /// Moves `current` up to `target`, recycling the frames skipped over
+    fn recycle_until(&mut self, target: PhysicalAddress) {
+        while self.current < target {
+            if !self.reserved.iter().any(|r| r.contains(self.current)) {
+                self.recycled.push(self.current);
+            }
+
+            self.current += constants::PAGE_SIZE;
+        }
+
+        self.recycled.sort();
+    }
+
+    /// Moves `current` past the reserved range it points into, if any
+    fn skip_reserved(&mut self) {
+        for range in self.reserved.iter() {
+            if range.contains(self.current) {
+                self.current = range.end();
+            }
+        }
+    }
+
+    fn reserved_range_in(&self, range: PhysicalAddressRange) -> Option<PhysicalAddressRange> {
+        self.reserved
+            .iter()
+            .find(|reserved| reserved.intersects(range))
+            .copied()
+    }
+
+    /// The lowest address from `current` on where `len` bytes aligned to `align` fit below
+    /// `top` without crossing a reserved range. Nothing is changed, so a failed allocation
+    /// leaves the allocator untouched.
+    fn find_contiguous(&self, len: usize, align: usize) -> Option<PhysicalAddress> {
+        let mut start = self.current;
+
+        loop {
+            start = PhysicalAddress::from_usize(start.as_usize().next_multiple_of(align));
+
+            if start >= self.top || len > (self.top - start).as_usize() {
+                return None;
+            }
+
+            // Frames can not be contiguous across a reserved range
+            match self.reserved_range_in(PhysicalAddressRange::from_start_len(start, len)) {
+                Some(reserved) => start = reserved.end(),
+                None => return Some(start),
+            }
+        }
+    }
+}
+
+impl IFrameAllocator for FrameAllocator {
+    fn alloc_frame(&mut self) -> Option<FrameDesc> {
+        if let Some(pa) = self.recycled.pop() {
+            return Some(unsafe { FrameDesc::new(pa) });
+        }
+
+        self.skip_reserved();
+
+        match self.current {
+            pa if pa < self.top => {
+                self.current = pa + constants::PAGE_SIZE;
+                Some(unsafe { FrameDesc::new(pa) })
+            }
+            _ => None,
+        }
+    }
+
+    fn alloc_frames(&mut self, count: usize) -> Option<Vec<FrameDesc>> {
+        // Prevent dealloc if we don't have enough frames
+        if count > self.available_frames() {
+            return None;
+        }
+
+        let frames = (0..count)
+            .map(|_| self.alloc_frame().expect("counted as available"))
+            .collect();
+
+        Some(frames)
+    }
+
+    fn dealloc(&mut self, frame: FrameDesc) {
+        // is valid frame
+        debug_assert!(frame.addr() >= self.bottom && frame.addr() < self.top);
+        // is allocated frame
+        debug_assert!(
+            self.recycled.iter().all(|ppn| *ppn != frame.addr()) && self.current != frame.addr()
+        );
+
+        let pa = frame.addr();
+        core::mem::forget(frame);
+
+        debug_assert!(pa < self.current);
+
+        self.recycled.push(pa);
+        self.recycled.sort();
+
+        // try gc self.current before push to recycled
+        // Check if the recycled or ppn can be contiguous
+        match self.recycled.last() {
+            Some(last) if *last + constants::PAGE_SIZE == self.current => {
+                let mut new_current = self.current;
+
+                loop {
+                    match self.recycled.pop() {
+                        Some(pa) if pa + constants::PAGE_SIZE == new_current => {
+                            new_current = pa;
+                        }
+                        Some(pa) => {
+                            self.recycled.push(pa);
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+
+                self.current = new_current;
+            }
+            _ => (),
+        }
+    }
+
+    fn alloc_contiguous(&mut self, count: usize) -> Option<FrameRangeDesc> {
+        self.alloc_contiguous_aligned(count, constants::PAGE_SIZE)
+    }
+
+    /// Advances `current` to the start of the range first, recycling the frames skipped over,
+    /// so no more frames than requested are taken from the pool.
+    fn alloc_contiguous_aligned(&mut self, count: usize, align: usize) -> Option<FrameRangeDesc> {
+        debug_assert!(align.is_power_of_two());
+
+        let len = count * constants::PAGE_SIZE;
+        let start = self.find_contiguous(len, align)?;
+
+        self.recycle_until(start);
+        self.current += len;
+
+        Some(unsafe { FrameRangeDesc::new(start, len) })
+    }
+
+    /// Deallocates the range at 4K granularity, so ranges backing huge pages are accepted as
+    /// long as their bounds are page aligned.
+    fn dealloc_range(&mut self, range: FrameRangeDesc) {
+        debug_assert!(range.start.is_page_aligned() && range.end.is_page_aligned());
+
+        for page in range.iter() {
+            self.dealloc(unsafe { FrameDesc::new(page.start_addr()) });
+        }
+
+        core::mem::forget(range);
+    }
+
+    fn reserve(&mut self, range: PhysicalAddressRange) -> Result<(), ReserveError> {
+        if !range.start().is_page_aligned() || !range.end().is_page_aligned() {
+            return Err(ReserveError::NotAligned);
+        }
+
+        if range.start() < self.bottom || range.end() > self.top {
+            return Err(ReserveError::OutOfRange);
+        }
+
+        // Frames below current are allocated unless they have been recycled
+        let mut cursor = range.start();
+        while cursor < range.end() && cursor < self.current {
+            if !self.recycled.contains(&cursor) && !self.reserved.iter().any(|r| r.contains(cursor))
+            {
+                return Err(ReserveError::InUse);
+            }
+
+            cursor += constants::PAGE_SIZE;
+        }
+
+        self.recycled.retain(|pa| !range.contains(*pa));
+
+        self.reserved.push(range);
+        self.reserved.sort_by_key(|r| r.start());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use address::{IAlignableAddress, IPageNum};
+    use alloc::format;
+
+    use super::*;
+
+    fn setup_allocator() -> FrameAllocator {
+        FrameAllocator::new(
+            PhysicalAddress::from_usize(0x8040_0000),
+            PhysicalAddress::from_usize(0x8020_0000),
+        )
+    }
+
+    #[test]
+    fn test_frame_addr() {
+        let mut allocator = setup_allocator();
+
+        let frame = allocator.alloc_frame().unwrap();
+
+        assert_eq!(frame.addr(), frame.0);
+        assert_eq!(frame.addr(), allocator.bottom());
+
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_frame_page() {
+        let mut allocator = setup_allocator();
+
+        let frames = allocator.alloc_frames(4).unwrap();
+
+        for frame in frames {
+            let page = frame.page();
+
+            assert!(page.start_addr().is_page_aligned());
+            assert_eq!(page.start_addr(), frame.addr());
+
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_frame_debug() {
+        let mut allocator = setup_allocator();
+
+        let frame = allocator.alloc_frame().unwrap();
+
+        assert_eq!(format!("{frame:?}"), "FrameDesc(0x80200000)");
+
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_frame_range_debug() {
+        let mut allocator = setup_allocator();
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+
+        assert_eq!(
+            format!("{range:?}"),
+            "FrameRangeDesc(0x80200000..0x80204000, 4 pages)"
+        );
+
+        allocator.dealloc_range(range);
+    }
+
+    #[test]
+    fn test_frame_range_iter() {
+        let mut allocator = setup_allocator();
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+
+        assert_eq!(range.len(), 4);
+        assert!(!range.is_empty());
+
+        let first = range.iter().collect::<Vec<_>>();
+        let second = range.iter().collect::<Vec<_>>();
+
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 4);
+        assert_eq!(first[0].start_addr(), range.start);
+
+        for pages in first.windows(2) {
+            assert_eq!(pages[0].as_usize() + 1, pages[1].as_usize());
+        }
+
+        allocator.dealloc_range(range);
+    }
+
+    #[test]
+    fn test_dealloc_range_4k_pages() {
+        let mut allocator = setup_allocator();
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+        let start = range.start;
+
+        allocator.dealloc_range(range);
+
+        // The whole range is merged back into the untouched region
+        assert!(allocator.recycled.is_empty());
+        assert_eq!(allocator.current, allocator.bottom());
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+        assert_eq!(range.start, start);
+
+        allocator.dealloc_range(range);
+    }
+
+    #[test]
+    fn test_dealloc_range_below_allocated_frame() {
+        let mut allocator = setup_allocator();
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+        let frame = allocator.alloc_frame().unwrap();
+
+        allocator.dealloc_range(range);
+
+        // The frame above keeps the range from being merged
+        assert_eq!(allocator.recycled.len(), 4);
+
+        allocator.dealloc(frame);
+
+        assert!(allocator.recycled.is_empty());
+        assert_eq!(allocator.current, allocator.bottom());
+    }
+
+    #[test]
+    fn test_dealloc_range_huge_page() {
+        let mut allocator = setup_allocator();
+
+        // The whole allocator is a single 2M page
+        let range = allocator.alloc_contiguous(512).unwrap();
+        assert_eq!(range.start, allocator.bottom());
+        assert_eq!(range.end, allocator.top());
+        assert!(allocator.alloc_frame().is_none());
+
+        allocator.dealloc_range(range);
+
+        assert!(allocator.recycled.is_empty());
+        assert_eq!(allocator.current, allocator.bottom());
+
+        let frames = allocator.alloc_frames(512).unwrap();
+        assert_eq!(frames.len(), 512);
+
+        for frame in frames.into_iter().rev() {
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut allocator = setup_allocator();
+        let capacity = allocator.available_frames();
+
+        assert_eq!(capacity, 512);
+
+        let range = allocator.alloc_contiguous(8).unwrap();
+        let frames = allocator.alloc_frames(4).unwrap();
+        assert_eq!(allocator.available_frames(), capacity - 12);
+
+        allocator.dealloc_range(range);
+        for frame in frames {
+            allocator.dealloc(frame);
+        }
+
+        allocator.reset();
+
+        assert_eq!(allocator.available_frames(), capacity);
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(frame.addr(), allocator.bottom());
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_reset_keeps_reserved() {
+        let mut allocator = setup_allocator();
+        allocator.reserve(page_range(0, 4)).unwrap();
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(allocator.available_frames(), 512 - 4 - 1);
+        allocator.dealloc(frame);
+
+        allocator.reset();
+
+        assert_eq!(allocator.available_frames(), 512 - 4);
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(frame.addr(), page_range(0, 4).end());
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_new_reserving_zero() {
+        let top = PhysicalAddress::from_usize(16 * constants::PAGE_SIZE);
+        let mut allocator = FrameAllocator::new_reserving_zero(top, PhysicalAddress::null());
+
+        assert_eq!(allocator.available_frames(), 15);
+
+        let frames = allocator.alloc_frames(15).unwrap();
+        assert!(frames.iter().all(|frame| !frame.addr().is_null()));
+        assert!(allocator.alloc_frame().is_none());
+
+        for frame in frames {
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_new_reserving_zero_with_nonzero_bottom() {
+        let mut allocator = FrameAllocator::new_reserving_zero(
+            PhysicalAddress::from_usize(0x9000_0000),
+            PhysicalAddress::from_usize(0x8000_0000),
+        );
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(frame.addr(), allocator.bottom());
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "outstanding frames")]
+    fn test_reset_with_outstanding_frames() {
+        let mut allocator = setup_allocator();
+
+        let frame = allocator.alloc_frame().unwrap();
+        core::mem::forget(frame);
+
+        allocator.reset();
+    }
+
+    fn paddr(offset: usize) -> PhysicalAddress {
+        PhysicalAddress::from_usize(0x8020_0000 + offset)
+    }
+
+    fn page_range(start_page: usize, pages: usize) -> PhysicalAddressRange {
+        PhysicalAddressRange::from_start_len(
+            paddr(start_page * constants::PAGE_SIZE),
+            pages * constants::PAGE_SIZE,
+        )
+    }
+
+    #[test]
+    fn test_reserve_middle_region() {
+        let mut allocator = setup_allocator();
+        let reserved = page_range(16, 64);
+
+        allocator.reserve(reserved).unwrap();
+
+        let mut frames = Vec::new();
+        while let Some(frame) = allocator.alloc_frame() {
+            assert!(!reserved.contains(frame.addr()));
+            frames.push(frame);
+        }
+
+        // 512 frames in total
+        assert_eq!(frames.len(), 512 - 64);
+
+        for frame in frames.into_iter().rev() {
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_reserve_drops_recycled_frames() {
+        let mut allocator = setup_allocator();
+
+        let frames = allocator.alloc_frames(4).unwrap();
+        for frame in frames {
+            allocator.dealloc(frame);
+        }
+
+        let reserved = page_range(0, 2);
+        allocator.reserve(reserved).unwrap();
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert!(!reserved.contains(frame.addr()));
+
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_reserve_contiguous_skips_reserved() {
+        let mut allocator = setup_allocator();
+        let reserved = page_range(2, 2);
+
+        allocator.reserve(reserved).unwrap();
+
+        let range = allocator.alloc_contiguous(4).unwrap();
+        let allocated = PhysicalAddressRange::new(range.start..range.end);
+
+        assert!(!allocated.intersects(reserved));
+        assert_eq!(range.start, reserved.end());
+
+        allocator.dealloc_range(range);
+    }
+
+    #[test]
+    fn test_alloc_contiguous_past_reserved_does_not_overrun() {
+        let mut allocator = setup_allocator();
+        allocator.reserve(page_range(1, 1)).unwrap();
+
+        // Does not fit before the reserved frame, and only 510 frames are left after it
+        assert!(allocator.alloc_contiguous(511).is_none());
+
+        // The failed allocation took nothing, not even the frame before the reserved one
+        assert_eq!(allocator.available_frames(), 511);
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(frame.addr(), page_range(0, 1).start());
+
+        let range = allocator.alloc_contiguous(510).unwrap();
+        assert_eq!(range.start, page_range(2, 1).start());
+        assert_eq!(range.end, allocator.top());
+
+        allocator.dealloc_range(range);
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_alloc_frames_counts_reserved() {
+        let mut allocator = setup_allocator();
+        allocator.reserve(page_range(16, 64)).unwrap();
+
+        let frame = allocator.alloc_frame().unwrap();
+
+        // All or nothing
+        assert!(allocator.alloc_frames(512 - 64).is_none());
+        assert_eq!(allocator.available_frames(), 512 - 64 - 1);
+
+        let frames = allocator.alloc_frames(512 - 64 - 1).unwrap();
+        assert_eq!(frames.len(), 512 - 64 - 1);
+        assert_eq!(allocator.available_frames(), 0);
+
+        for frame in frames.into_iter().rev() {
+            allocator.dealloc(frame);
+        }
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_alloc_contiguous_aligned() {
+        const ALIGN_2M: usize = 0x20_0000;
+
+        let mut allocator = FrameAllocator::new(
+            PhysicalAddress::from_usize(0x8080_0000),
+            PhysicalAddress::from_usize(0x8020_0000),
+        );
+
+        // Move current off the 2M boundary
+        let frame = allocator.alloc_frame().unwrap();
+
+        let range = allocator.alloc_contiguous_aligned(4, ALIGN_2M).unwrap();
+        assert!(range.start.is_aligned(ALIGN_2M));
+        assert_eq!(range.start, PhysicalAddress::from_usize(0x8040_0000));
+        assert_eq!(range.len(), 4);
+
+        // The frames skipped over are handed out again
+        let skipped = allocator.alloc_frame().unwrap();
+        assert!(skipped.addr() > frame.addr() && skipped.addr() < range.start);
+
+        allocator.dealloc(skipped);
+        allocator.dealloc_range(range);
+        allocator.dealloc(frame);
+
+        assert_eq!(
+            allocator.available_frames(),
+            0x60_0000 / constants::PAGE_SIZE
+        );
+    }
+
+    #[test]
+    fn test_alloc_contiguous_aligned_skips_reserved() {
+        const ALIGN_2M: usize = 0x20_0000;
+
+        let mut allocator = FrameAllocator::new(
+            PhysicalAddress::from_usize(0x8080_0000),
+            PhysicalAddress::from_usize(0x8020_0000),
+        );
+
+        // Reserve the start of the first 2M block
+        allocator.reserve(page_range(0, 2)).unwrap();
+
+        let range = allocator.alloc_contiguous_aligned(1, ALIGN_2M).unwrap();
+        assert_eq!(range.start, PhysicalAddress::from_usize(0x8040_0000));
+        allocator.dealloc_range(range);
+
+        // Not enough room after the first 4M boundary
+        assert!(allocator
+            .alloc_contiguous_aligned(0x401, 0x40_0000)
+            .is_none());
+    }
+
+    #[test]
+    fn test_reserve_rejects_invalid_ranges() {
+        let mut allocator = setup_allocator();
+
+        assert_eq!(
+            allocator.reserve(PhysicalAddressRange::from_start_len(paddr(0x800), 0x1000)),
+            Err(ReserveError::NotAligned)
+        );
+        assert_eq!(
+            allocator.reserve(page_range(511, 2)),
+            Err(ReserveError::OutOfRange)
+        );
+
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(
+            allocator.reserve(page_range(0, 1)),
+            Err(ReserveError::InUse)
+        );
+
+        allocator.dealloc(frame);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut allocator = setup_allocator();
+
+        let mut frames = Vec::new();
+        while let Some(frame) = allocator.alloc_frame() {
+            frames.push(frame);
+        }
+
+        let old_top = allocator.top();
+        allocator
+            .extend(old_top + 2 * constants::PAGE_SIZE)
+            .unwrap();
+
+        let first = allocator.alloc_frame().unwrap();
+        let second = allocator.alloc_frame().unwrap();
+
+        assert_eq!(first.addr(), old_top);
+        assert_eq!(second.addr(), old_top + constants::PAGE_SIZE);
+        assert!(allocator.alloc_frame().is_none());
+
+        frames.push(first);
+        frames.push(second);
+        for frame in frames.into_iter().rev() {
+            allocator.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_extend_rejects_invalid_top() {
+        let mut allocator = setup_allocator();
+        let top = allocator.top();
+
+        assert_eq!(allocator.extend(top + 0x800), Err(ExtendError::NotAligned));
+        assert_eq!(allocator.extend(top), Err(ExtendError::NotGrowing));
+        assert_eq!(
+            allocator.extend(top - constants::PAGE_SIZE),
+            Err(ExtendError::NotGrowing)
+        );
+
+        assert_eq!(allocator.top(), top);
+    }
+}
@@ -2,142 +2,76 @@
 #![feature(allocator_api)]
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use abstractions::operations::IUsizeAlias;
-use address::PhysicalAddress;
-use alloc::vec::Vec;
-use allocation_abstractions::{FrameDesc, FrameRangeDesc, IFrameAllocator};
-
 #[cfg(feature = "std")]
 extern crate std;
 
 extern crate alloc;
 
-pub struct FrameAllocator {
-    top: PhysicalAddress,
-    bottom: PhysicalAddress,
-    // current should always point to the last frame that can be allocated
-    current: PhysicalAddress,
-    recycled: Vec<PhysicalAddress>,
-}
-
-impl FrameAllocator {
-    pub fn new(top: PhysicalAddress, bottom: PhysicalAddress) -> Self {
-        FrameAllocator {
-            top,
-            bottom,
-            current: bottom,
-            recycled: Vec::new(),
-        }
-    }
-
-    pub fn top(&self) -> PhysicalAddress {
-        self.top
-    }
-
-    pub fn bottom(&self) -> PhysicalAddress {
-        self.bottom
-    }
-}
-
-impl IFrameAllocator for FrameAllocator {
-    fn alloc_frame(&mut self) -> Option<FrameDesc> {
-        match self.recycled.pop() {
-            Some(pa) => Some(unsafe { FrameDesc::new(pa) }),
-            None => match self.current {
-                pa if pa < self.top => {
-                    self.current = pa + constants::PAGE_SIZE;
-                    Some(unsafe { FrameDesc::new(pa) })
-                }
-                _ => None,
-            },
-        }
+// Only the selected backends are built, tests cover all of them
+#[cfg(any(feature = "bitmap", test))]
+mod bitmap;
+#[cfg(any(feature = "bump", test))]
+mod bump;
+
+#[cfg(any(feature = "bitmap", test))]
+pub use bitmap::BitmapFrameAllocator;
+#[cfg(any(feature = "bump", test))]
+pub use bump::{ExtendError, FrameAllocator};
+
+#[cfg(not(any(feature = "bump", feature = "bitmap")))]
+compile_error!("At least one frame allocator backend must be enabled, `bump` or `bitmap`");
+
+/// The frame allocator selected by cargo features, `bump` takes precedence when both
+/// `bump` and `bitmap` are enabled.
+#[cfg(feature = "bump")]
+pub type DefaultFrameAllocator = FrameAllocator;
+
+/// The frame allocator selected by cargo features, `bump` takes precedence when both
+/// `bump` and `bitmap` are enabled.
+#[cfg(all(feature = "bitmap", not(feature = "bump")))]
+pub type DefaultFrameAllocator = BitmapFrameAllocator<'static>;
+
+#[cfg(test)]
+mod tests {
+    use abstractions::operations::IUsizeAlias;
+    use address::PhysicalAddress;
+    use allocation_abstractions::IFrameAllocator;
+
+    use super::*;
+
+    const BOTTOM: usize = 0x8020_0000;
+    const FRAMES: usize = 64;
+
+    #[cfg(feature = "bump")]
+    fn setup_default_allocator() -> DefaultFrameAllocator {
+        DefaultFrameAllocator::new(
+            PhysicalAddress::from_usize(BOTTOM + FRAMES * constants::PAGE_SIZE),
+            PhysicalAddress::from_usize(BOTTOM),
+        )
     }
 
-    fn alloc_frames(&mut self, count: usize) -> Option<Vec<FrameDesc>> {
-        let mut frames = Vec::with_capacity(count);
-
-        let avaliable =
-            self.recycled.len() + (self.top - self.bottom).as_usize() / (constants::PAGE_SIZE);
-
-        match count {
-            count if count <= avaliable => {
-                for _ in 0..count {
-                    match self.alloc_frame() {
-                        Some(frame) => frames.push(frame),
-                        None => break,
-                    }
-                }
-                Some(frames)
-            }
-            // Prevent dealloc if we don't have enough frames
-            _ => None,
-        }
-    }
+    #[cfg(all(feature = "bitmap", not(feature = "bump")))]
+    fn setup_default_allocator() -> DefaultFrameAllocator {
+        let bitmap = alloc::vec![0; BitmapFrameAllocator::bitmap_len(FRAMES)];
 
-    fn dealloc(&mut self, frame: FrameDesc) {
-        // is valid frame
-        debug_assert!(frame.0 >= self.bottom && frame.0 < self.top);
-        // is allocated frame
-        debug_assert!(self.recycled.iter().all(|ppn| *ppn != frame.0) && self.current != frame.0);
-
-        let pa = frame.0;
-        core::mem::forget(frame);
-
-        debug_assert!(pa < self.current);
-
-        self.recycled.push(pa);
-        self.recycled.sort();
-
-        // try gc self.current before push to recycled
-        // Check if the recycled or ppn can be contiguous
-        match self.recycled.last() {
-            Some(last) if *last + 1 == self.current => {
-                let mut new_current = self.current;
-
-                loop {
-                    match self.recycled.pop() {
-                        Some(pa) if pa + constants::PAGE_SIZE == new_current => {
-                            new_current = pa;
-                        }
-                        Some(pa) => {
-                            self.recycled.push(pa);
-                            break;
-                        }
-                        None => break,
-                    }
-                }
-
-                self.current = new_current;
-            }
-            _ => (),
-        }
+        DefaultFrameAllocator::new(
+            PhysicalAddress::from_usize(BOTTOM + FRAMES * constants::PAGE_SIZE),
+            PhysicalAddress::from_usize(BOTTOM),
+            bitmap.leak(),
+        )
     }
 
-    fn alloc_contiguous(&mut self, count: usize) -> Option<FrameRangeDesc> {
-        let avaliable = (self.top - self.current).as_usize();
-
-        match count {
-            count if count < avaliable => {
-                let start = self.current;
-                let len = count * constants::PAGE_SIZE;
-                self.current += len;
-
-                Some(unsafe { FrameRangeDesc::new(start, len) })
-            }
-            // Prevent dealloc if we don't have enough frames
-            _ => None,
-        }
-    }
+    fn assert_frame_allocator(_: &impl IFrameAllocator) {}
 
-    fn dealloc_range(&mut self, range: FrameRangeDesc) {
-        let mut cursor = range.start;
+    #[test]
+    fn test_default_allocator() {
+        let mut allocator = setup_default_allocator();
 
-        while cursor < range.end {
-            self.dealloc(unsafe { FrameDesc::new(cursor) });
+        assert_frame_allocator(&allocator);
 
-            cursor += constants::PAGE_SIZE;
-        }
+        let frame = allocator.alloc_frame().unwrap();
+        assert_eq!(frame.addr(), PhysicalAddress::from_usize(BOTTOM));
 
-        core::mem::forget(range);
+        allocator.dealloc(frame);
     }
 }
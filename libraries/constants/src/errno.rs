@@ -267,6 +267,159 @@ pub enum ErrNo {
     MemoryPageHasHardwareError = -133,
 }
 
+impl ErrNo {
+    /// The positive errno value, e.g. 14 for `EFAULT`.
+    pub const fn errno(&self) -> i32 {
+        -(*self as isize) as i32
+    }
+
+    /// The symbolic name of the error, e.g. `EFAULT`.
+    ///
+    /// Numbers without a name in Linux are shown as `E<number>`.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ErrNo::OperationNotPermitted => "EPERM",
+            ErrNo::NoSuchFileOrDirectory => "ENOENT",
+            ErrNo::NoSuchProcess => "ESRCH",
+            ErrNo::InterruptedSystemCall => "EINTR",
+            ErrNo::InputOutputError => "EIO",
+            ErrNo::NoSuchDeviceOrAddress => "ENXIO",
+            ErrNo::ArgumentListTooLong => "E2BIG",
+            ErrNo::ExecFormatError => "ENOEXEC",
+            ErrNo::BadFileDescriptor => "EBADF",
+            ErrNo::NoChildProcesses => "ECHILD",
+            ErrNo::ResourceTemporarilyUnavailable => "EAGAIN",
+            ErrNo::CannotAllocateMemory => "ENOMEM",
+            ErrNo::PermissionDenied => "EACCES",
+            ErrNo::BadAddress => "EFAULT",
+            ErrNo::BlockDeviceRequired => "ENOTBLK",
+            ErrNo::DeviceOrResourceBusy => "EBUSY",
+            ErrNo::FileExists => "EEXIST",
+            ErrNo::InvalidCrossDeviceLink => "EXDEV",
+            ErrNo::NoSuchDevice => "ENODEV",
+            ErrNo::NotADirectory => "ENOTDIR",
+            ErrNo::IsADirectory => "EISDIR",
+            ErrNo::InvalidArgument => "EINVAL",
+            ErrNo::TooManyOpenFilesInSystem => "ENFILE",
+            ErrNo::TooManyOpenFiles => "EMFILE",
+            ErrNo::InappropriateIoctlForDevice => "ENOTTY",
+            ErrNo::TextFileBusy => "ETXTBSY",
+            ErrNo::FileTooLarge => "EFBIG",
+            ErrNo::NoSpaceLeftOnDevice => "ENOSPC",
+            ErrNo::IllegalSeek => "ESPIPE",
+            ErrNo::ReadOnlyFileSystem => "EROFS",
+            ErrNo::TooManyLinks => "EMLINK",
+            ErrNo::BrokenPipe => "EPIPE",
+            ErrNo::NumericalArgumentOutOfDomain => "EDOM",
+            ErrNo::NumericalResultOutOfRange => "ERANGE",
+            ErrNo::ResourceDeadlockAvoided => "EDEADLK",
+            ErrNo::FileNameTooLong => "ENAMETOOLONG",
+            ErrNo::NoLocksAvailable => "ENOLCK",
+            ErrNo::FunctionNotImplemented => "ENOSYS",
+            ErrNo::DirectoryNotEmpty => "ENOTEMPTY",
+            ErrNo::TooManyLevelsOfSymbolicLinks => "ELOOP",
+            ErrNo::UnknownError41 => "E41",
+            ErrNo::NoMessageOfDesiredType => "ENOMSG",
+            ErrNo::IdentifierRemoved => "EIDRM",
+            ErrNo::ChannelNumberOutOfRange => "ECHRNG",
+            ErrNo::Level2NotSynchronized => "EL2NSYNC",
+            ErrNo::Level3Halted => "EL3HLT",
+            ErrNo::Level3Reset => "EL3RST",
+            ErrNo::LinkNumberOutOfRange => "ELNRNG",
+            ErrNo::ProtocolDriverNotAttached => "EUNATCH",
+            ErrNo::NoCsiStructureAvailable => "ENOCSI",
+            ErrNo::Level2Halted => "EL2HLT",
+            ErrNo::InvalidExchange => "EBADE",
+            ErrNo::InvalidRequestDescriptor => "EBADR",
+            ErrNo::ExchangeFull => "EXFULL",
+            ErrNo::NoAnode => "ENOANO",
+            ErrNo::InvalidRequestCode => "EBADRQC",
+            ErrNo::InvalidSlot => "EBADSLT",
+            ErrNo::BadFontFileFormat => "EBFONT",
+            ErrNo::DeviceNotAStream => "ENOSTR",
+            ErrNo::NoDataAvailable => "ENODATA",
+            ErrNo::TimerExpired => "ETIME",
+            ErrNo::OutOfStreamsResources => "ENOSR",
+            ErrNo::MachineIsNotOnTheNetwork => "ENONET",
+            ErrNo::PackageNotInstalled => "ENOPKG",
+            ErrNo::ObjectIsRemote => "EREMOTE",
+            ErrNo::LinkHasBeenSevered => "ENOLINK",
+            ErrNo::AdvertiseError => "EADV",
+            ErrNo::SrmountError => "ESRMNT",
+            ErrNo::CommunicationErrorOnSend => "ECOMM",
+            ErrNo::ProtocolError => "EPROTO",
+            ErrNo::MultihopAttempted => "EMULTIHOP",
+            ErrNo::RfsSpecificError => "EDOTDOT",
+            ErrNo::BadMessage => "EBADMSG",
+            ErrNo::ValueTooLargeForDefinedDataType => "EOVERFLOW",
+            ErrNo::NameNotUniqueOnNetwork => "ENOTUNIQ",
+            ErrNo::FileDescriptorInBadState => "EBADFD",
+            ErrNo::RemoteAddressChanged => "EREMCHG",
+            ErrNo::CannotAccessANeededSharedLibrary => "ELIBACC",
+            ErrNo::AccessingACorruptedSharedLibrary => "ELIBBAD",
+            ErrNo::LibSectionInAOutCorrupted => "ELIBSCN",
+            ErrNo::AttemptingToLinkInTooManySharedLibraries => "ELIBMAX",
+            ErrNo::CannotExecASharedLibraryDirectly => "ELIBEXEC",
+            ErrNo::InvalidOrIncompleteMultibyteOrWideCharacter => "EILSEQ",
+            ErrNo::InterruptedSystemCallShouldBeRestarted => "ERESTART",
+            ErrNo::StreamsPipeError => "ESTRPIPE",
+            ErrNo::TooManyUsers => "EUSERS",
+            ErrNo::SocketOperationOnNonSocket => "ENOTSOCK",
+            ErrNo::DestinationAddressRequired => "EDESTADDRREQ",
+            ErrNo::MessageTooLong => "EMSGSIZE",
+            ErrNo::ProtocolWrongTypeForSocket => "EPROTOTYPE",
+            ErrNo::ProtocolNotAvailable => "ENOPROTOOPT",
+            ErrNo::ProtocolNotSupported => "EPROTONOSUPPORT",
+            ErrNo::SocketTypeNotSupported => "ESOCKTNOSUPPORT",
+            ErrNo::OperationNotSupported => "EOPNOTSUPP",
+            ErrNo::ProtocolFamilyNotSupported => "EPFNOSUPPORT",
+            ErrNo::AddressFamilyNotSupportedByProtocol => "EAFNOSUPPORT",
+            ErrNo::AddressAlreadyInUse => "EADDRINUSE",
+            ErrNo::CannotAssignRequestedAddress => "EADDRNOTAVAIL",
+            ErrNo::NetworkIsDown => "ENETDOWN",
+            ErrNo::NetworkIsUnreachable => "ENETUNREACH",
+            ErrNo::NetworkDroppedConnectionOnReset => "ENETRESET",
+            ErrNo::SoftwareCausedConnectionAbort => "ECONNABORTED",
+            ErrNo::ConnectionResetByPeer => "ECONNRESET",
+            ErrNo::NoBufferSpaceAvailable => "ENOBUFS",
+            ErrNo::TransportEndpointIsAlreadyConnected => "EISCONN",
+            ErrNo::TransportEndpointIsNotConnected => "ENOTCONN",
+            ErrNo::CannotSendAfterTransportEndpointShutdown => "ESHUTDOWN",
+            ErrNo::TooManyReferencesCannotSplice => "ETOOMANYREFS",
+            ErrNo::ConnectionTimedOut => "ETIMEDOUT",
+            ErrNo::ConnectionRefused => "ECONNREFUSED",
+            ErrNo::HostIsDown => "EHOSTDOWN",
+            ErrNo::NoRouteToHost => "EHOSTUNREACH",
+            ErrNo::OperationAlreadyInProgress => "EALREADY",
+            ErrNo::OperationNowInProgress => "EINPROGRESS",
+            ErrNo::StaleFileHandle => "ESTALE",
+            ErrNo::StructureNeedsCleaning => "EUCLEAN",
+            ErrNo::NotAXenixNamedTypeFile => "ENOTNAM",
+            ErrNo::NoXenixSemaphoresAvailable => "ENAVAIL",
+            ErrNo::IsANamedTypeFile => "EISNAM",
+            ErrNo::RemoteIOError => "EREMOTEIO",
+            ErrNo::DiskQuotaExceeded => "EDQUOT",
+            ErrNo::NoMediumFound => "ENOMEDIUM",
+            ErrNo::WrongMediumType => "EMEDIUMTYPE",
+            ErrNo::OperationCanceled => "ECANCELED",
+            ErrNo::RequiredKeyNotAvailable => "ENOKEY",
+            ErrNo::KeyHasExpired => "EKEYEXPIRED",
+            ErrNo::KeyHasBeenRevoked => "EKEYREVOKED",
+            ErrNo::KeyWasRejectedByService => "EKEYREJECTED",
+            ErrNo::OwnerDied => "EOWNERDEAD",
+            ErrNo::StateNotRecoverable => "ENOTRECOVERABLE",
+            ErrNo::OperationNotPossibleDueToRfKill => "ERFKILL",
+            ErrNo::MemoryPageHasHardwareError => "EHWPOISON",
+        }
+    }
+}
+
+impl core::fmt::Display for ErrNo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 pub struct SyscallError;
 
 // Result<isize, ErrNo> is the actual type of SyscallResult, so we can return an instance directly
@@ -569,3 +722,36 @@ impl SyscallError {
     pub const MemoryPageHasHardwareError: Result<isize, ErrNo> =
         Err(ErrNo::MemoryPageHasHardwareError);
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use super::*;
+
+    #[test]
+    fn test_errno_values_and_names() {
+        let cases = [
+            (ErrNo::OperationNotPermitted, 1, "EPERM"),
+            (ErrNo::NoSuchFileOrDirectory, 2, "ENOENT"),
+            (ErrNo::BadFileDescriptor, 9, "EBADF"),
+            (ErrNo::ResourceTemporarilyUnavailable, 11, "EAGAIN"),
+            (ErrNo::CannotAllocateMemory, 12, "ENOMEM"),
+            (ErrNo::PermissionDenied, 13, "EACCES"),
+            (ErrNo::BadAddress, 14, "EFAULT"),
+            (ErrNo::FileExists, 17, "EEXIST"),
+            (ErrNo::InvalidArgument, 22, "EINVAL"),
+            (ErrNo::FunctionNotImplemented, 38, "ENOSYS"),
+            (ErrNo::UnknownError41, 41, "E41"),
+            (ErrNo::BadFontFileFormat, 59, "EBFONT"),
+            (ErrNo::OperationNotSupported, 95, "EOPNOTSUPP"),
+            (ErrNo::MemoryPageHasHardwareError, 133, "EHWPOISON"),
+        ];
+
+        for (err, errno, name) in cases {
+            assert_eq!(err.errno(), errno, "{err:?}");
+            assert_eq!(err.name(), name, "{err:?}");
+            assert_eq!(alloc::format!("{err}"), name);
+        }
+    }
+}
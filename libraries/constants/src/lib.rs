@@ -11,6 +11,9 @@ const _FORCE_REBUILD: &str = env!("FORCE_REBUILD_TS");
 mod errno;
 pub use errno::{ErrNo, SyscallError};
 
+mod page_size;
+pub use page_size::{page_size, PageSize, PAGE_SIZE_1G, PAGE_SIZE_2M, PAGE_SIZE_4K};
+
 pub const PROCESSOR_COUNT: usize = 2;
 
 pub const PAGE_SIZE: usize = 4096;
@@ -0,0 +1,98 @@
+/// The page sizes supported by the hardware page table.
+#[repr(usize)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PageSize {
+    /// Size of 4 kilobytes (2<sup>12</sup> bytes).
+    _4K = 0x1000,
+    /// Size of 2 megabytes (2<sup>21</sup> bytes).
+    _2M = 0x20_0000,
+    /// Size of 1 gigabytes (2<sup>30</sup> bytes).
+    _1G = 0x4000_0000,
+    Custom(usize),
+}
+
+impl From<usize> for PageSize {
+    fn from(value: usize) -> Self {
+        Self::from_usize(value)
+    }
+}
+
+impl PageSize {
+    /// Converts a size in bytes, sizes other than 4K, 2M and 1G become `Custom`.
+    pub const fn from_usize(value: usize) -> Self {
+        match value {
+            0x1000 => PageSize::_4K,
+            0x20_0000 => PageSize::_2M,
+            0x4000_0000 => PageSize::_1G,
+            _ => PageSize::Custom(value),
+        }
+    }
+
+    pub const fn as_usize(&self) -> usize {
+        match self {
+            PageSize::_4K => 0x1000,
+            PageSize::_2M => 0x20_0000,
+            PageSize::_1G => 0x4000_0000,
+            PageSize::Custom(v) => *v,
+        }
+    }
+
+    /// The unit string of a standard size, e.g. `"2M"`, or `"custom"` for `Custom`.
+    pub const fn human(&self) -> &'static str {
+        match self {
+            PageSize::_4K => "4K",
+            PageSize::_2M => "2M",
+            PageSize::_1G => "1G",
+            PageSize::Custom(_) => "custom",
+        }
+    }
+}
+
+/// Standard sizes are shown in units, e.g. `4K`, custom sizes as hexadecimal bytes.
+impl core::fmt::Display for PageSize {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PageSize::Custom(size) => write!(f, "{size:#x}"),
+            _ => f.write_str(self.human()),
+        }
+    }
+}
+
+pub const PAGE_SIZE_4K: PageSize = PageSize::_4K;
+pub const PAGE_SIZE_2M: PageSize = PageSize::_2M;
+pub const PAGE_SIZE_1G: PageSize = PageSize::_1G;
+
+/// The base page size, i.e. [`PAGE_SIZE`](crate::PAGE_SIZE) as a [`PageSize`].
+pub const fn page_size() -> PageSize {
+    PageSize::from_usize(crate::PAGE_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_typed_page_sizes() {
+        assert_eq!(PAGE_SIZE_4K.as_usize(), 0x1000);
+        assert_eq!(PAGE_SIZE_2M.as_usize(), 0x20_0000);
+        assert_eq!(PAGE_SIZE_1G.as_usize(), 0x4000_0000);
+
+        assert_eq!(page_size().as_usize(), crate::PAGE_SIZE);
+        assert_eq!(page_size(), PageSize::from(crate::PAGE_SIZE));
+    }
+
+    #[test]
+    fn test_page_size_display() {
+        assert_eq!(PageSize::_4K.to_string(), "4K");
+        assert_eq!(PageSize::_2M.to_string(), "2M");
+        assert_eq!(PageSize::_1G.to_string(), "1G");
+        assert_eq!(PageSize::Custom(0x3000).to_string(), "0x3000");
+
+        assert_eq!(PageSize::_2M.human(), "2M");
+        assert_eq!(PageSize::Custom(0x3000).human(), "custom");
+    }
+}
@@ -3,13 +3,17 @@ use address::{
     IAddressBase, IPageNum, IToPageNum, VirtualAddress, VirtualAddressRange, VirtualPageNum,
     VirtualPageNumRange,
 };
-use alloc::{string::String, sync::Arc, vec::Vec};
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
 use hermit_sync::SpinMutex;
 use log::trace;
 use memory_space::{AreaType, MapType, MappingArea, MemorySpace, MemorySpaceAttribute};
 use mmu_abstractions::{GenericMappingFlags, IMMU};
 use utilities::InvokeOnDrop;
-use xmas_elf::{program::ProgramHeader, ElfFile};
+use xmas_elf::{
+    header::{self, Class, Data, Machine},
+    program::{ProgramHeader, Type},
+    ElfFile,
+};
 
 use crate::{auxv::AuxVecKey, IExecSource, LinuxLoader, LoadError, ProcessContext, RawMemorySpace};
 
@@ -17,7 +21,8 @@ impl<'a> LinuxLoader<'a> {
     /// Load an ELF executable into a newly created MemorySpace and return a configured LinuxLoader.
     ///
     /// This:
-    /// - allocates contiguous physical frames, copies the ELF bytes into them, and parses the ELF;
+    /// - for sources residing in memory, allocates contiguous physical frames, copies the ELF bytes into them, and parses the ELF;
+    ///   other sources only have their headers read, and each segment is streamed straight into its destination frames;
     /// - maps PT_LOAD segments into the process address space (with permissions derived from segment flags),
     ///   tracking the loaded ELF area and PHDR location (or deriving it from the ELF header);
     /// - populates the process auxiliary vector (AT_PHDR, AT_PHENT, AT_PHNUM, AT_PAGESZ, AT_BASE, AT_FLAGS, AT_ENTRY);
@@ -34,7 +39,7 @@ impl<'a> LinuxLoader<'a> {
     /// - Returns Err(LoadError::UnableToReadExecutable) if reading the executable into memory fails.
     /// - Returns Err(LoadError::NotElf) if the ELF parser rejects the data.
     /// - Returns Err(LoadError::TooLarge) or Err(LoadError::IncompleteExecutable) for invalid segment sizes/offsets.
-    /// - Returns Err(LoadError::FailedToLoad) if writing segment bytes into the MMU fails.
+    /// - Returns Err(LoadError::FailedToLoad) if the frames of a segment can not be resolved through the MMU.
     ///
     /// # Examples
     ///
@@ -59,37 +64,62 @@ impl<'a> LinuxLoader<'a> {
 
         let mut attr = MemorySpaceAttribute::default();
 
+        let streamed_headers = match elf_data.as_slice() {
+            Some(_) => None,
+            None => Some(read_elf_headers(elf_data)?),
+        };
+
+        // Fail fast before allocating anything for the executable
+        let kind = probe_headers(streamed_headers.as_deref().or(elf_data.as_slice()).unwrap())?;
+
+        log::debug!("Loading {kind:?} ELF: {path}");
+
         // see https://github.com/caiyih/bakaos/issues/26
         let boxed_elf_holding;
 
-        let boxed_elf;
+        let headers;
+
+        // Where the segments are copied from
+        let segment_source: &dyn IExecSource;
 
-        let elf_info = {
-            let required_frames = elf_data.len().div_ceil(constants::PAGE_SIZE);
+        let elf_info = match elf_data.as_slice() {
+            // The source already resides in memory, stage the whole file into frames
+            Some(_) => {
+                let required_frames = elf_data.len().div_ceil(constants::PAGE_SIZE);
 
-            let frames = alloc
-                .lock()
-                .alloc_contiguous(required_frames)
-                .ok_or(LoadError::InsufficientMemory)?;
+                let frames = alloc
+                    .lock()
+                    .alloc_contiguous(required_frames)
+                    .ok_or(LoadError::InsufficientMemory)?;
 
-            boxed_elf_holding = InvokeOnDrop::transform(frames, |f| alloc.lock().dealloc_range(f));
+                boxed_elf_holding =
+                    InvokeOnDrop::transform(frames, |f| alloc.lock().dealloc_range(f));
 
-            let pt = mmu.lock();
+                let pt = mmu.lock();
 
-            let slice = pt
-                .translate_phys(
-                    boxed_elf_holding.start,
-                    boxed_elf_holding.end.as_usize() - boxed_elf_holding.start.as_usize(),
-                )
-                .unwrap();
+                let slice = pt
+                    .translate_phys(
+                        boxed_elf_holding.start,
+                        boxed_elf_holding.end.as_usize() - boxed_elf_holding.start.as_usize(),
+                    )
+                    .unwrap();
 
-            let len = elf_data
-                .read_at(0, slice)
-                .map_err(|_| LoadError::UnableToReadExecutable)?;
+                let len = elf_data
+                    .read_at(0, slice)
+                    .map_err(|_| LoadError::UnableToReadExecutable)?;
 
-            boxed_elf = &mut slice[..len];
+                headers = &slice[..len];
+                segment_source = &headers;
 
-            ElfFile::new(boxed_elf).map_err(|_| LoadError::NotElf)?
+                ElfFile::new(headers).map_err(|_| LoadError::NotElf)?
+            }
+            // Only read the headers, segments are streamed into their frames later
+            None => {
+                headers = streamed_headers.as_deref().unwrap();
+                segment_source = elf_data;
+
+                ElfFile::new(headers).map_err(|_| LoadError::NotElf)?
+            }
         };
 
         // No need to check the ELF magic number because it is already checked in `ElfFile::new`
@@ -107,10 +137,16 @@ impl<'a> LinuxLoader<'a> {
 
         let mut pie_offset = 0;
 
+        let mut segments = Vec::new();
+
         for ph in elf_info.program_iter() {
             trace!("Found program header: {ph:?}");
 
             match ph.get_type() {
+                Ok(xmas_elf::program::Type::Load) if ph.mem_size() == 0 => {
+                    trace!("Skipping empty segment");
+                    continue;
+                }
                 Ok(xmas_elf::program::Type::Load) => trace!("Loading"),
                 Ok(xmas_elf::program::Type::Interp) => {
                     interpreters.push(ph);
@@ -161,43 +197,22 @@ impl<'a> LinuxLoader<'a> {
                 segment_permissions |= GenericMappingFlags::Executable;
             }
 
-            let page_range = VirtualPageNumRange::from_start_end(
-                start.to_floor_page_num(),
-                end.to_ceil_page_num(), // end is exclusive
-            );
-
-            memory_space.alloc_and_map_area(MappingArea::new(
-                page_range,
-                AreaType::UserElf,
-                MapType::Framed,
-                segment_permissions,
-                None,
-            ));
-
-            fn copy_elf_segment(
-                elf: &[u8],
-                ph: &ProgramHeader,
-                vaddr: VirtualAddress,
-                mmu: &Arc<SpinMutex<dyn IMMU>>,
-            ) -> Result<(), LoadError> {
-                let file_sz = ph.file_size() as usize;
-
-                if file_sz > 0 {
-                    let off = ph.offset() as usize;
-                    let end = off.checked_add(file_sz).ok_or(LoadError::TooLarge)?;
-                    if end > elf.len() {
-                        return Err(LoadError::IncompleteExecutable);
-                    }
-                    let data = &elf[off..end];
-                    mmu.lock()
-                        .write_bytes(vaddr, data)
-                        .map_err(|_| LoadError::FailedToLoad)?;
-                }
+            segments.push(LoadSegment {
+                ph,
+                start,
+                end,
+                permissions: segment_permissions,
+            });
+        }
 
-                Ok(())
-            }
+        for area in merge_segment_areas(&mut segments)? {
+            memory_space
+                .alloc_and_map_area(area)
+                .map_err(|_| LoadError::InsufficientMemory)?;
+        }
 
-            copy_elf_segment(boxed_elf, &ph, start, mmu)?;
+        for segment in segments.iter() {
+            copy_elf_segment(segment_source, &segment.ph, segment.start, mmu)?;
         }
 
         for interp in interpreters {
@@ -241,27 +256,28 @@ impl<'a> LinuxLoader<'a> {
         attr.signal_trampoline = max_end_vpn;
 
         max_end_vpn += 1;
-        memory_space.alloc_and_map_area(MappingArea::new(
-            VirtualPageNumRange::from_single(max_end_vpn),
-            AreaType::UserStackGuardBase,
-            MapType::Framed,
-            GenericMappingFlags::empty(),
-            None,
-        ));
+        memory_space
+            .reserve_range(
+                VirtualPageNumRange::from_single(max_end_vpn),
+                AreaType::UserStackGuardBase,
+            )
+            .map_err(|_| LoadError::InsufficientMemory)?;
         attr.stack_guard_base =
             VirtualAddressRange::from_start_len(max_end_vpn.start_addr(), constants::PAGE_SIZE);
 
         let stack_page_count = constants::USER_STACK_SIZE / constants::PAGE_SIZE;
         max_end_vpn += 1;
-        memory_space.alloc_and_map_area(MappingArea::new(
-            VirtualPageNumRange::from_start_count(max_end_vpn, stack_page_count),
-            AreaType::UserStack,
-            MapType::Framed,
-            GenericMappingFlags::User
-                .union(GenericMappingFlags::Readable)
-                .union(GenericMappingFlags::Writable),
-            None,
-        ));
+        memory_space
+            .alloc_and_map_area(MappingArea::new(
+                VirtualPageNumRange::from_start_count(max_end_vpn, stack_page_count),
+                AreaType::Stack,
+                MapType::Framed,
+                GenericMappingFlags::User
+                    .union(GenericMappingFlags::Readable)
+                    .union(GenericMappingFlags::Writable),
+                None,
+            ))
+            .map_err(|_| LoadError::InsufficientMemory)?;
         attr.stack_range = VirtualAddressRange::from_start_len(
             max_end_vpn.start_addr(),
             constants::USER_STACK_SIZE,
@@ -269,39 +285,37 @@ impl<'a> LinuxLoader<'a> {
 
         max_end_vpn += stack_page_count;
         let stack_top = max_end_vpn.start_addr();
-        memory_space.alloc_and_map_area(MappingArea::new(
-            VirtualPageNumRange::from_single(max_end_vpn),
-            AreaType::UserStackGuardTop,
-            MapType::Framed,
-            GenericMappingFlags::empty(),
-            None,
-        ));
+        memory_space
+            .reserve_range(
+                VirtualPageNumRange::from_single(max_end_vpn),
+                AreaType::UserStackGuardTop,
+            )
+            .map_err(|_| LoadError::InsufficientMemory)?;
         attr.stack_guard_top =
             VirtualAddressRange::from_start_len(max_end_vpn.start_addr(), constants::PAGE_SIZE);
 
         max_end_vpn += 1;
-        memory_space.alloc_and_map_area(MappingArea::new(
-            VirtualPageNumRange::from_start_count(max_end_vpn, 0),
-            AreaType::UserBrk,
-            MapType::Framed,
-            GenericMappingFlags::User
-                .union(GenericMappingFlags::Readable)
-                .union(GenericMappingFlags::Writable),
-            None,
-        ));
-        attr.brk_area_idx = memory_space
-            .mappings()
-            .iter()
-            .enumerate()
-            .find(|(_, area)| area.area_type == AreaType::UserBrk)
-            .expect("UserBrk area not found")
-            .0;
+        memory_space
+            .alloc_and_map_area(MappingArea::new(
+                VirtualPageNumRange::from_start_count(max_end_vpn, 0),
+                AreaType::Heap,
+                MapType::Framed,
+                GenericMappingFlags::User
+                    .union(GenericMappingFlags::Readable)
+                    .union(GenericMappingFlags::Writable),
+                None,
+            ))
+            .map_err(|_| LoadError::InsufficientMemory)?;
         attr.brk_start = max_end_vpn.start_addr();
 
         // FIXME: handle cases where there is a interpreter
         let entry_pc =
             VirtualAddress::from_usize(elf_info.header.pt2.entry_point() as usize) + pie_offset;
 
+        attr.entry = entry_pc;
+        attr.phdr = phdr;
+        attr.pie = elf_info.header.pt2.type_().as_type() == header::Type::SharedObject;
+
         #[cfg(debug_assertions)]
         {
             for area in memory_space.mappings() {
@@ -336,3 +350,574 @@ impl<'a> LinuxLoader<'a> {
         })
     }
 }
+
+/// The kind of an ELF executable, see [`LinuxLoader::probe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElfKind {
+    /// A statically linked executable loaded at its fixed addresses.
+    Static,
+    /// A position independent executable that does not require an interpreter.
+    Pie,
+    /// An executable that requires an interpreter to be loaded.
+    Dynamic,
+}
+
+impl LinuxLoader<'_> {
+    /// Inspects the ELF header and the program headers of the source without allocating or mapping anything.
+    ///
+    /// Returns Err(LoadError::NotElf) if the source is not an ELF file,
+    /// Err(LoadError::ArchMismatch) if it can not run on the current architecture,
+    /// and Err(LoadError::NotExecutable) if it is an ELF file that can not be executed, e.g. an object file.
+    pub fn probe(source: &dyn IExecSource) -> Result<ElfKind, LoadError> {
+        match source.as_slice() {
+            Some(data) => probe_headers(data),
+            None => probe_headers(&read_elf_headers(source)?),
+        }
+    }
+}
+
+fn probe_headers(headers: &[u8]) -> Result<ElfKind, LoadError> {
+    let elf = ElfFile::new(headers).map_err(|_| LoadError::NotElf)?;
+
+    if elf.header.pt1.class() != Class::SixtyFour
+        || elf.header.pt1.data() != Data::LittleEndian
+        || !is_machine_supported(elf.header.pt2.machine().as_machine())
+    {
+        return Err(LoadError::ArchMismatch);
+    }
+
+    let has_interpreter = elf
+        .program_iter()
+        .any(|ph| matches!(ph.get_type(), Ok(Type::Interp)));
+
+    match (elf.header.pt2.type_().as_type(), has_interpreter) {
+        (_, true) => Ok(ElfKind::Dynamic),
+        (header::Type::Executable, false) if elf.header.pt2.entry_point() != 0 => {
+            Ok(ElfKind::Static)
+        }
+        (header::Type::SharedObject, false) => Ok(ElfKind::Pie),
+        _ => Err(LoadError::NotExecutable),
+    }
+}
+
+#[allow(unreachable_code)]
+const fn is_machine_supported(machine: Machine) -> bool {
+    #[cfg(target_arch = "riscv64")]
+    return matches!(machine, Machine::RISC_V);
+
+    #[cfg(target_arch = "loongarch64")]
+    return matches!(machine, Machine::Other(0x102));
+
+    // Executables for any architecture can be inspected on the host
+    let _ = machine;
+    true
+}
+
+/// Reads the ELF header and the program headers of the source.
+///
+/// The returned buffer starts at offset 0 of the file so that it can be parsed by `ElfFile`.
+fn read_elf_headers(source: &(impl IExecSource + ?Sized)) -> Result<Vec<u8>, LoadError> {
+    const EHDR_SIZE: usize = 64;
+
+    let mut ehdr = [0u8; EHDR_SIZE];
+
+    let len = source
+        .read_at(0, &mut ehdr)
+        .map_err(|_| LoadError::UnableToReadExecutable)?;
+
+    // Leave the detailed validation to `ElfFile::new`
+    if len < EHDR_SIZE || ehdr[0..4] != *b"\x7fELF" {
+        return Err(LoadError::NotElf);
+    }
+
+    let ph_offset = u64::from_le_bytes(ehdr[32..40].try_into().unwrap()) as usize;
+    let ph_entry_size = u16::from_le_bytes(ehdr[54..56].try_into().unwrap()) as usize;
+    let ph_count = u16::from_le_bytes(ehdr[56..58].try_into().unwrap()) as usize;
+
+    let headers_len = ph_entry_size
+        .checked_mul(ph_count)
+        .and_then(|len| len.checked_add(ph_offset))
+        .ok_or(LoadError::TooLarge)?
+        .max(EHDR_SIZE);
+
+    if headers_len > source.len() {
+        return Err(LoadError::IncompleteExecutable);
+    }
+
+    let mut headers = vec![0u8; headers_len];
+
+    let len = source
+        .read_at(0, &mut headers)
+        .map_err(|_| LoadError::UnableToReadExecutable)?;
+
+    if len != headers_len {
+        return Err(LoadError::IncompleteExecutable);
+    }
+
+    Ok(headers)
+}
+
+struct LoadSegment<'a> {
+    ph: ProgramHeader<'a>,
+    start: VirtualAddress,
+    end: VirtualAddress,
+    permissions: GenericMappingFlags,
+}
+
+/// Builds the areas backing the loadable segments, sorting the segments by address.
+///
+/// Segments sharing a page are backed by a single area with the union of their permissions,
+/// while segments whose memory actually overlaps are rejected.
+fn merge_segment_areas(segments: &mut [LoadSegment]) -> Result<Vec<MappingArea>, LoadError> {
+    segments.sort_by_key(|segment| segment.start);
+
+    let mut areas: Vec<MappingArea> = Vec::new();
+    let mut last_end = VirtualAddress::null();
+
+    for segment in segments.iter() {
+        if segment.start < last_end {
+            return Err(LoadError::OverlappingSegments);
+        }
+
+        last_end = segment.end;
+
+        let page_range = VirtualPageNumRange::from_start_end(
+            segment.start.to_floor_page_num(),
+            segment.end.to_ceil_page_num(), // end is exclusive
+        );
+
+        match areas.last_mut() {
+            Some(last) if last.range().end() > page_range.start() => {
+                let permissions = last.permissions() | segment.permissions;
+
+                *last = MappingArea::new(
+                    VirtualPageNumRange::from_start_end(last.range().start(), page_range.end()),
+                    area_type_of(permissions),
+                    MapType::Framed,
+                    permissions,
+                    None,
+                );
+            }
+            _ => areas.push(MappingArea::new(
+                page_range,
+                area_type_of(segment.permissions),
+                MapType::Framed,
+                segment.permissions,
+                None,
+            )),
+        }
+    }
+
+    Ok(areas)
+}
+
+fn area_type_of(permissions: GenericMappingFlags) -> AreaType {
+    match permissions.contains(GenericMappingFlags::Executable) {
+        true => AreaType::Text,
+        false => AreaType::Data,
+    }
+}
+
+/// Reads the file content of the segment directly into the frames backing `vaddr`.
+fn copy_elf_segment(
+    source: &dyn IExecSource,
+    ph: &ProgramHeader,
+    vaddr: VirtualAddress,
+    mmu: &Arc<SpinMutex<dyn IMMU>>,
+) -> Result<(), LoadError> {
+    let file_sz = ph.file_size() as usize;
+    let off = ph.offset() as usize;
+
+    // Nothing to read for a pure .bss segment, whose offset may well lie past the file
+    if file_sz == 0 {
+        return Ok(());
+    }
+
+    let end = off.checked_add(file_sz).ok_or(LoadError::TooLarge)?;
+    if end > source.len() {
+        return Err(LoadError::IncompleteExecutable);
+    }
+
+    let mmu = mmu.lock();
+
+    let mut copied = 0;
+    while copied < file_sz {
+        let (paddr, _, _) = mmu
+            .query_virtual(vaddr + copied)
+            .map_err(|_| LoadError::FailedToLoad)?;
+
+        let len =
+            (constants::PAGE_SIZE - paddr.as_usize() % constants::PAGE_SIZE).min(file_sz - copied);
+
+        let frame = mmu
+            .translate_phys(paddr, len)
+            .map_err(|_| LoadError::FailedToLoad)?;
+
+        let read = source
+            .read_at(off + copied, frame)
+            .map_err(|_| LoadError::UnableToReadExecutable)?;
+
+        if read != len {
+            return Err(LoadError::IncompleteExecutable);
+        }
+
+        copied += len;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use test_utilities::{
+        allocation::contiguous::TestFrameAllocator,
+        elf::{TestElfBuilder, ET_DYN, PF_R, PF_W, PF_X},
+    };
+
+    use super::*;
+
+    fn load(elf: &[u8]) -> Result<LinuxLoader<'static>, LoadError> {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(64 * 1024 * 1024);
+
+        LinuxLoader::from_elf(&elf, "/test", ProcessContext::new(), &(mmu, alloc))
+    }
+
+    fn area_type_at(loader: &LinuxLoader<'_>, vaddr: usize) -> Option<AreaType> {
+        let vpn = VirtualAddress::from_usize(vaddr).to_floor_page_num();
+
+        loader
+            .memory_space
+            .mappings()
+            .iter()
+            .find(|area| area.contains(vpn))
+            .map(|area| area.area_type)
+    }
+
+    #[test]
+    fn test_segments_are_classified() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load(0x20000, PF_R, &[0x42; 0x100])
+            .with_load_sized(0x30000, PF_R | PF_W, &[0x42; 0x100], 0x2000)
+            .build();
+
+        let loader = load(&elf).unwrap();
+
+        assert_eq!(area_type_at(&loader, 0x10000), Some(AreaType::Text));
+        assert_eq!(area_type_at(&loader, 0x20000), Some(AreaType::Data));
+        assert_eq!(area_type_at(&loader, 0x31000), Some(AreaType::Data));
+
+        let memory_space = &loader.memory_space;
+        let brk = &memory_space.mappings()[memory_space.brk_area_idx()];
+        assert_eq!(brk.area_type, AreaType::Heap);
+
+        let stack = memory_space.attr().stack_range.start();
+        assert_eq!(
+            area_type_at(&loader, stack.as_usize()),
+            Some(AreaType::Stack)
+        );
+    }
+
+    #[test]
+    fn test_attributes_of_static_executable() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10080)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .build();
+
+        let loader = load(&elf).unwrap();
+        let memory_space = &loader.memory_space;
+
+        #[cfg(debug_assertions)]
+        memory_space.verify_invariants().unwrap();
+
+        assert_eq!(memory_space.entry().as_usize(), 0x10080);
+        assert_eq!(memory_space.entry(), loader.entry_pc);
+        assert!(!memory_space.is_pie());
+
+        assert_eq!(memory_space.user_stack_top(), loader.stack_top);
+        assert_eq!(memory_space.brk_base(), memory_space.brk_start());
+        assert!(memory_space.brk_base() > memory_space.user_stack_top());
+
+        // No PT_PHDR, the headers are implied to follow the first segment
+        assert_eq!(memory_space.phdr().as_usize(), 0x10000 + 64);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_stack_guards_are_unbacked() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load_sized(0x20000, PF_R | PF_W, &[0x42; 0x100], 0x2000)
+            .build();
+
+        let loader = load(&elf).unwrap();
+        let memory_space = &loader.memory_space;
+
+        memory_space.verify_invariants().unwrap();
+
+        let attr = memory_space.attr();
+        let mmu = memory_space.mmu().lock();
+        assert!(!mmu.is_mapped(attr.stack_guard_base.start()));
+        assert!(!mmu.is_mapped(attr.stack_guard_top.start()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_mapped_stack_guard_is_a_violation() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .build();
+
+        let loader = load(&elf).unwrap();
+        let memory_space = &loader.memory_space;
+        let guard = memory_space.attr().stack_guard_top.start();
+
+        let frame = memory_space.allocator().lock().alloc_frame().unwrap();
+        memory_space
+            .mmu()
+            .lock()
+            .map_single(
+                guard,
+                frame.addr(),
+                mmu_abstractions::PageSize::_4K,
+                GenericMappingFlags::User | GenericMappingFlags::Readable,
+            )
+            .unwrap();
+
+        let violation = memory_space.verify_invariants().unwrap_err();
+        assert!(violation.contains("UserStackGuardTop"), "{violation}");
+
+        memory_space.mmu().lock().unmap_single(guard).unwrap();
+        memory_space.allocator().lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_attributes_of_pie_executable() {
+        let elf = TestElfBuilder::new()
+            .with_type(ET_DYN)
+            .with_entry(0x80)
+            .with_load(0, PF_R | PF_X, &[0x13; 0x100])
+            .build();
+
+        let loader = load(&elf).unwrap();
+        let memory_space = &loader.memory_space;
+
+        // Segments at page 0 are biased by one page
+        assert_eq!(memory_space.entry().as_usize(), 0x80 + constants::PAGE_SIZE);
+        assert!(memory_space.is_pie());
+    }
+
+    fn area_at<'a>(loader: &'a LinuxLoader<'_>, vaddr: usize) -> Option<&'a MappingArea> {
+        let vpn = VirtualAddress::from_usize(vaddr).to_floor_page_num();
+
+        loader
+            .memory_space
+            .mappings()
+            .iter()
+            .find(|area| area.contains(vpn))
+    }
+
+    #[test]
+    fn test_zero_length_segment_is_skipped() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load(0x4000_0000, PF_R | PF_W, &[])
+            .build();
+
+        let loader = load(&elf).unwrap();
+
+        assert!(area_at(&loader, 0x4000_0000).is_none());
+        assert_eq!(area_type_at(&loader, 0x10000), Some(AreaType::Text));
+
+        // The empty segment doesn't move the areas placed after the executable
+        assert_eq!(
+            loader.memory_space.attr().signal_trampoline.start_addr(),
+            VirtualAddress::from_usize(0x11000)
+        );
+    }
+
+    #[test]
+    fn test_bss_segment_past_end_of_file() {
+        let mut elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load_sized(0x20000, PF_R | PF_W, &[], 0x2000)
+            .build();
+
+        // Point p_offset of the .bss segment beyond the end of the file
+        let p_offset = 64 + 56 + 8;
+        let past_end = elf.len() as u64 + 0x1000;
+        elf[p_offset..p_offset + 8].copy_from_slice(&past_end.to_le_bytes());
+
+        let loader = load(&elf).unwrap();
+
+        assert_eq!(area_type_at(&loader, 0x20000), Some(AreaType::Data));
+
+        let mut buf = [0xffu8; 0x2000];
+        loader
+            .memory_space
+            .mmu()
+            .lock()
+            .read_bytes(VirtualAddress::from_usize(0x20000), &mut buf)
+            .unwrap();
+        assert!(buf.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_segments_sharing_page_are_merged() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load(0x10800, PF_R | PF_W, &[0x42; 0x100])
+            .build();
+
+        let loader = load(&elf).unwrap();
+
+        let area = area_at(&loader, 0x10000).unwrap();
+        assert_eq!(area.range().page_count(), 1);
+        assert_eq!(area.area_type, AreaType::Text);
+        assert!(area.permissions().contains(
+            GenericMappingFlags::Readable
+                | GenericMappingFlags::Writable
+                | GenericMappingFlags::Executable
+        ));
+
+        // Both segments are copied into the shared page
+        let mmu = loader.memory_space.mmu().lock();
+        assert_eq!(
+            mmu.import::<u8>(VirtualAddress::from_usize(0x10000)),
+            Ok(0x13)
+        );
+        assert_eq!(
+            mmu.import::<u8>(VirtualAddress::from_usize(0x10800)),
+            Ok(0x42)
+        );
+    }
+
+    #[test]
+    fn test_overlapping_segments_are_rejected() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x200])
+            .with_load(0x10100, PF_R | PF_W, &[0x42; 0x100])
+            .build();
+
+        assert_eq!(load(&elf).err(), Some(LoadError::OverlappingSegments));
+    }
+
+    struct CountingSource {
+        data: Vec<u8>,
+        bytes_read: Cell<usize>,
+    }
+
+    impl IExecSource for CountingSource {
+        fn read_at(&self, offset: usize, buf: &mut [u8]) -> Result<usize, &'static str> {
+            let len = (&self.data[..]).read_at(offset, buf)?;
+            self.bytes_read.set(self.bytes_read.get() + len);
+            Ok(len)
+        }
+
+        fn len(&self) -> usize {
+            self.data.len()
+        }
+    }
+
+    #[test]
+    fn test_streaming_source_reads_only_loadable_segments() {
+        let text = [0x13; 0x100];
+        let data = [0x42; 0x1800];
+
+        let source = CountingSource {
+            data: TestElfBuilder::new()
+                .with_entry(0x10000)
+                .with_load(0x10000, PF_R | PF_X, &text)
+                .with_load(0x20000, PF_R | PF_W, &data)
+                .build(),
+            bytes_read: Cell::new(0),
+        };
+
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(64 * 1024 * 1024);
+        let _loader = LinuxLoader::from_elf(
+            &source,
+            "/test",
+            ProcessContext::new(),
+            &(mmu.clone(), alloc),
+        )
+        .unwrap();
+
+        // The ELF header is read first to locate the program headers, then again along with them
+        const HEADERS_LEN: usize = 64 + 2 * 56;
+        let expected = 64 + HEADERS_LEN + text.len() + data.len();
+
+        assert_eq!(source.bytes_read.get(), expected);
+        assert!(source.bytes_read.get() < source.len());
+
+        let mut buf = vec![0u8; data.len()];
+        mmu.lock()
+            .read_bytes(VirtualAddress::from_usize(0x20000), &mut buf)
+            .unwrap();
+        assert_eq!(buf, data);
+    }
+
+    #[test]
+    fn test_probe_static() {
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .build();
+
+        assert_eq!(LinuxLoader::probe(&elf.as_slice()), Ok(ElfKind::Static));
+    }
+
+    #[test]
+    fn test_probe_pie() {
+        let elf = TestElfBuilder::new()
+            .with_type(ET_DYN)
+            .with_entry(0x100)
+            .with_load(0, PF_R | PF_X, &[0x13; 0x200])
+            .build();
+
+        assert_eq!(LinuxLoader::probe(&elf.as_slice()), Ok(ElfKind::Pie));
+    }
+
+    #[test]
+    fn test_probe_dynamic() {
+        let elf = TestElfBuilder::new()
+            .with_type(ET_DYN)
+            .with_entry(0x100)
+            .with_interp("/lib/ld-musl-riscv64.so.1")
+            .with_load(0, PF_R | PF_X, &[0x13; 0x200])
+            .build();
+
+        assert_eq!(LinuxLoader::probe(&elf.as_slice()), Ok(ElfKind::Dynamic));
+    }
+
+    #[test]
+    fn test_probe_not_elf() {
+        let data: &[u8] = b"#!/bin/sh\necho hello\n";
+
+        assert_eq!(LinuxLoader::probe(&data), Err(LoadError::NotElf));
+    }
+
+    #[test]
+    fn test_probe_streaming_source() {
+        let source = CountingSource {
+            data: TestElfBuilder::new()
+                .with_entry(0x10000)
+                .with_load(0x10000, PF_R | PF_X, &[0x13; 0x2000])
+                .build(),
+            bytes_read: Cell::new(0),
+        };
+
+        assert_eq!(LinuxLoader::probe(&source), Ok(ElfKind::Static));
+        // Only the headers are read
+        assert_eq!(source.bytes_read.get(), 64 + 64 + 56);
+    }
+}
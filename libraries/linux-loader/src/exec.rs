@@ -0,0 +1,156 @@
+use address::VirtualAddress;
+use memory_space::{MemorySpace, MemorySpaceAttribute};
+
+use crate::{auxv::AuxVecValues, IExecSource, LinuxLoader, LoadError, ProcessContext};
+
+/// Replaces the image of an existing memory space, the core of `execve`.
+pub trait IExecMemorySpace {
+    /// Tears down every mapping of the memory space and loads the ELF executable into it,
+    /// keeping its MMU and frame allocator.
+    ///
+    /// The stack is initialized with `ctx` like [`LinuxLoader::from_raw`] does. Returns the
+    /// attribute of the new image and the initial stack pointer.
+    ///
+    /// The old image is torn down before loading, as the new one may use the same addresses,
+    /// so it is lost even if loading fails. The signal trampoline has to be registered again.
+    fn replace_with_elf<'a>(
+        &mut self,
+        source: &impl IExecSource,
+        path: &str,
+        ctx: &ProcessContext<'a>,
+        auxv_values: &AuxVecValues<'a>,
+    ) -> Result<(MemorySpaceAttribute, VirtualAddress), LoadError>;
+}
+
+impl IExecMemorySpace for MemorySpace {
+    fn replace_with_elf<'a>(
+        &mut self,
+        source: &impl IExecSource,
+        path: &str,
+        ctx: &ProcessContext<'a>,
+        auxv_values: &AuxVecValues<'a>,
+    ) -> Result<(MemorySpaceAttribute, VirtualAddress), LoadError> {
+        let raw = (self.mmu().clone(), self.allocator().clone());
+
+        self.unmap_all_areas_that(|_| true);
+
+        let mut loader = LinuxLoader::from_elf(source, path, ProcessContext::default(), &raw)?;
+        loader.init_stack(None, ctx, auxv_values)?;
+
+        let attr = *loader.memory_space.attr();
+
+        // The emptied memory space is dropped along with the loader
+        core::mem::swap(self, &mut loader.memory_space);
+
+        Ok((attr, loader.stack_top))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abstractions::IUsizeAlias;
+    use address::IToPageNum;
+    use alloc::{borrow::Cow, sync::Arc};
+    use hermit_sync::SpinMutex;
+    use mmu_abstractions::IMMU;
+    use test_utilities::{
+        allocation::contiguous::TestFrameAllocator,
+        elf::{TestElfBuilder, PF_R, PF_W, PF_X},
+    };
+
+    use super::*;
+
+    fn image_a() -> alloc::vec::Vec<u8> {
+        TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load(0x20000, PF_R | PF_W, &[0x42; 0x100])
+            .build()
+    }
+
+    fn image_b() -> alloc::vec::Vec<u8> {
+        TestElfBuilder::new()
+            .with_entry(0x40080)
+            .with_load(0x40000, PF_R | PF_X, &[0x17; 0x100])
+            .build()
+    }
+
+    fn is_mapped(mmu: &Arc<SpinMutex<dyn IMMU>>, vaddr: usize) -> bool {
+        mmu.lock()
+            .query_virtual(VirtualAddress::from_usize(vaddr))
+            .is_ok()
+    }
+
+    #[test]
+    fn test_replace_with_elf() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(64 * 1024 * 1024);
+
+        let a = image_a();
+        let mut memory_space =
+            LinuxLoader::from_elf(&a.as_slice(), "/a", ProcessContext::new(), &(mmu, alloc))
+                .unwrap()
+                .memory_space;
+
+        let mmu = memory_space.mmu().clone();
+        let allocator = memory_space.allocator().clone();
+
+        assert!(is_mapped(&mmu, 0x10000));
+        assert!(is_mapped(&mmu, 0x20000));
+
+        let mut ctx = ProcessContext::new();
+        ctx.extend_argv(&[Cow::Borrowed("/b")]).unwrap();
+
+        let b = image_b();
+        let (attr, stack_top) = memory_space
+            .replace_with_elf(&b.as_slice(), "/b", &ctx, &AuxVecValues::default())
+            .unwrap();
+
+        // The mmu and the allocator are kept
+        assert!(Arc::ptr_eq(memory_space.mmu(), &mmu));
+        assert!(Arc::ptr_eq(memory_space.allocator(), &allocator));
+
+        // A is gone
+        assert!(!is_mapped(&mmu, 0x10000));
+        assert!(!is_mapped(&mmu, 0x20000));
+
+        // B is loaded
+        assert!(is_mapped(&mmu, 0x40000));
+        assert_eq!(attr.entry().as_usize(), 0x40080);
+        assert_eq!(memory_space.entry().as_usize(), 0x40080);
+        assert!(memory_space
+            .mappings()
+            .iter()
+            .all(|area| area.range().start()
+                >= VirtualAddress::from_usize(0x40000).to_floor_page_num()));
+
+        // The arguments are pushed below the top of the stack
+        assert!(stack_top < attr.user_stack_top());
+        assert!(stack_top >= attr.stack_range.start());
+    }
+
+    #[test]
+    fn test_replace_with_same_elf() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(64 * 1024 * 1024);
+
+        let a = image_a();
+        let mut memory_space =
+            LinuxLoader::from_elf(&a.as_slice(), "/a", ProcessContext::new(), &(mmu, alloc))
+                .unwrap()
+                .memory_space;
+
+        let area_count = memory_space.area_count();
+
+        // The new image occupies the same addresses as the old one
+        memory_space
+            .replace_with_elf(
+                &a.as_slice(),
+                "/a",
+                &ProcessContext::new(),
+                &AuxVecValues::default(),
+            )
+            .unwrap();
+
+        assert_eq!(memory_space.area_count(), area_count);
+        assert!(is_mapped(memory_space.mmu(), 0x10000));
+    }
+}
@@ -7,11 +7,14 @@ extern crate alloc;
 
 pub mod auxv;
 mod elf;
+mod exec;
 mod loader;
 mod process;
 mod shebang;
 mod stack;
 
+pub use elf::ElfKind;
+pub use exec::IExecMemorySpace;
 pub use loader::*;
 pub use process::*;
 
@@ -51,6 +51,13 @@ pub trait IExecSource {
         // clippy requirement
         self.len() == 0
     }
+
+    /// Returns the whole content if the source already resides in memory.
+    ///
+    /// Loaders stage such sources at once instead of streaming them piece by piece.
+    fn as_slice(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
 impl IExecSource for &[u8] {
@@ -92,6 +99,10 @@ impl IExecSource for &[u8] {
     fn len(&self) -> usize {
         (self as &[u8]).len()
     }
+
+    fn as_slice(&self) -> Option<&[u8]> {
+        Some(self)
+    }
 }
 
 impl IExecSource for dyn IInode {
@@ -261,6 +272,8 @@ pub enum LoadError {
     ArgumentCountExceeded,
     /// The required environment variable count is exceeded.
     EnvironmentCountExceeded,
+    /// Two loadable segments of the executable overlap in memory.
+    OverlappingSegments,
 }
 
 impl LoadError {
@@ -290,7 +303,8 @@ impl LoadError {
             | LoadError::CanNotFindInterpreter
             | LoadError::InvalidShebangString
             | LoadError::ArgumentCountExceeded
-            | LoadError::EnvironmentCountExceeded => true,
+            | LoadError::EnvironmentCountExceeded
+            | LoadError::OverlappingSegments => true,
             LoadError::UnableToReadExecutable | LoadError::NotElf | LoadError::NotShebang => false,
         }
     }
@@ -205,19 +205,21 @@ mod tests {
         let stack_base = VirtualAddress::from_usize(0x80000000);
         let stack_size = PageSize::_2M.as_usize();
 
-        memory_space.alloc_and_map_area(MappingArea {
-            range: VirtualPageNumRange::from_start_count(
-                stack_base.to_floor_page_num(),
-                stack_size / PageSize::_4K.as_usize(),
-            ),
-            area_type: memory_space::AreaType::UserStack,
-            map_type: memory_space::MapType::Framed,
-            permissions: GenericMappingFlags::User
-                | GenericMappingFlags::Kernel
-                | GenericMappingFlags::Readable
-                | GenericMappingFlags::Writable,
-            allocation: None,
-        });
+        memory_space
+            .alloc_and_map_area(MappingArea {
+                range: VirtualPageNumRange::from_start_count(
+                    stack_base.to_floor_page_num(),
+                    stack_size / PageSize::_4K.as_usize(),
+                ),
+                area_type: memory_space::AreaType::Stack,
+                map_type: memory_space::MapType::Framed,
+                permissions: GenericMappingFlags::User
+                    | GenericMappingFlags::Kernel
+                    | GenericMappingFlags::Readable
+                    | GenericMappingFlags::Writable,
+                allocation: None,
+            })
+            .unwrap();
 
         let loader = LinuxLoader {
             memory_space,
@@ -94,7 +94,10 @@ impl LinuxProcess {
         let _pt = space.mmu().lock();
 
         #[cfg_accessible(platform_specific::register_kernel_area_for_pt)]
-        platform_specific::register_kernel_area_for_pt(_pt.platform_payload());
+        platform_specific::register_kernel_area_for_pt(
+            _pt.platform_payload()
+                .expect("The memory space can not be activated on this platform"),
+        );
     }
 }
 
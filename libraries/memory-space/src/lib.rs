@@ -17,6 +17,9 @@ pub enum MapType {
     Framed,
     Direct,
     Linear,
+    /// Only occupies the virtual address space, without any frame or page table entry.
+    /// Accessing the area faults.
+    Reserved,
 }
 
 /// Layout of a typical user memory space
@@ -58,12 +61,16 @@ pub enum MapType {
 /// +------------------+ <- 0x0000_0000_0000_0000
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AreaType {
-    UserElf,
+    /// Executable segments of the ELF.
+    Text,
+    /// Non-executable segments of the ELF, e.g. `.rodata`, `.data` and `.bss`.
+    Data,
     SignalTrampoline,
     UserStackGuardBase,
-    UserStack,
+    Stack,
     UserStackGuardTop,
-    UserBrk,
+    /// The brk area.
+    Heap,
     VMA,
     Kernel,
 }
@@ -1,4 +1,4 @@
-use address::{VirtualPageNum, VirtualPageNumRange};
+use address::{IToPageNum, PhysicalPageNum, VirtualPageNum, VirtualPageNumRange};
 use alloc::{collections::btree_map::BTreeMap, sync::Arc};
 use allocation_abstractions::{FrameDesc, IFrameAllocator};
 use hermit_sync::SpinMutex;
@@ -56,6 +56,42 @@ impl MappingArea {
     pub fn contains(&self, vpn: VirtualPageNum) -> bool {
         self.range.contains(vpn)
     }
+
+    /// Splits the area at `at`, keeping `[start, at)` in this area and returning `[at, end)`
+    /// together with the frames backing it.
+    pub fn split_off(&mut self, at: VirtualPageNum) -> MappingArea {
+        debug_assert!(self.range.start() < at && at < self.range.end());
+
+        let tail_range = VirtualPageNumRange::from_start_end(at, self.range.end());
+        self.range = VirtualPageNumRange::from_start_end(self.range.start(), at);
+
+        let allocation = self
+            .allocation
+            .as_mut()
+            .map(|allocation| MappingAreaAllocation {
+                allocator: allocation.allocator.clone(),
+                frames: allocation.frames.split_off(&at),
+            });
+
+        Self {
+            range: tail_range,
+            area_type: self.area_type,
+            map_type: self.map_type,
+            permissions: self.permissions,
+            allocation,
+        }
+    }
+
+    /// Iterates over the frames currently owned by this area, in ascending order of the
+    /// virtual pages they back.
+    ///
+    /// Areas without an allocation, e.g. reserved areas, yield nothing.
+    pub fn backing_frames(&self) -> impl Iterator<Item = PhysicalPageNum> + '_ {
+        self.allocation
+            .iter()
+            .flat_map(|allocation| allocation.frames.values())
+            .map(|frame| frame.addr().to_floor_page_num())
+    }
 }
 
 impl alloc::fmt::Debug for MappingArea {
@@ -82,6 +118,13 @@ impl MappingAreaAllocation {
             frames: BTreeMap::new(),
         }
     }
+
+    /// The frame backing the given virtual page, if this allocation owns one for it.
+    pub fn frame_for(&self, vpn: VirtualPageNum) -> Option<PhysicalPageNum> {
+        self.frames
+            .get(&vpn)
+            .map(|frame| frame.addr().to_floor_page_num())
+    }
 }
 
 impl Drop for MappingAreaAllocation {
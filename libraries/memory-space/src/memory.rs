@@ -2,6 +2,8 @@ use core::cell::OnceCell;
 
 use abstractions::IUsizeAlias;
 use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+#[cfg(debug_assertions)]
+use alloc::{format, string::String};
 
 use crate::{AreaType, MapType, MappingArea, MappingAreaAllocation};
 use address::{
@@ -10,7 +12,7 @@ use address::{
 };
 use allocation_abstractions::IFrameAllocator;
 use hermit_sync::SpinMutex;
-use mmu_abstractions::{GenericMappingFlags, PageSize, IMMU};
+use mmu_abstractions::{GenericMappingFlags, PageSize, PagingError, PagingResult, IMMU};
 
 pub struct MemorySpace {
     mmu: Arc<SpinMutex<dyn IMMU>>,
@@ -21,22 +23,24 @@ pub struct MemorySpace {
 
 #[derive(Debug, Clone, Copy)]
 pub struct MemorySpaceAttribute {
-    pub brk_area_idx: usize,
     pub brk_start: VirtualAddress,
     pub stack_guard_base: VirtualAddressRange,
     pub stack_range: VirtualAddressRange,
     pub stack_guard_top: VirtualAddressRange,
     pub elf_area: VirtualAddressRange,
     pub signal_trampoline: VirtualPageNum,
+    pub entry: VirtualAddress,
+    pub phdr: VirtualAddress,
+    pub pie: bool,
 }
 
 impl Default for MemorySpaceAttribute {
     /// Creates a default MemorySpaceAttribute with all address ranges set to null and numeric fields set to sentinel values.
     ///
     /// The returned value is suitable as an uninitialized placeholder:
-    /// - `brk_area_idx` is `usize::MAX` (indicating no brk area assigned),
     /// - `brk_start`, `stack_guard_base`, `stack_range`, `stack_guard_top`, and `elf_area` are all empty/null ranges,
-    /// - `signal_trampoline` is `0`.
+    /// - `signal_trampoline` is `0`,
+    /// - `entry` and `phdr` are null and `pie` is `false`.
     ///
     /// # Examples
     ///
@@ -46,13 +50,11 @@ impl Default for MemorySpaceAttribute {
     /// use memory_space::MemorySpaceAttribute;
     ///
     /// let attr = MemorySpaceAttribute::default();
-    /// assert_eq!(attr.brk_area_idx, usize::MAX);
     /// assert!(attr.brk_start.is_null());
     /// assert_eq!(attr.signal_trampoline.as_usize(), 0);
     /// ```
     fn default() -> Self {
         Self {
-            brk_area_idx: usize::MAX,
             brk_start: VirtualAddress::null(),
             stack_guard_base: VirtualAddressRange::from_start_end(
                 VirtualAddress::null(),
@@ -71,36 +73,188 @@ impl Default for MemorySpaceAttribute {
                 VirtualAddress::null(),
             ),
             signal_trampoline: VirtualPageNum::from_usize(0),
+            entry: VirtualAddress::null(),
+            phdr: VirtualAddress::null(),
+            pie: false,
         }
     }
 }
 
+impl MemorySpaceAttribute {
+    /// The entry point of the executable, including the load bias of PIE executables.
+    pub fn entry(&self) -> VirtualAddress {
+        self.entry
+    }
+
+    /// The exclusive top of the user stack, where the initial stack pointer starts.
+    pub fn user_stack_top(&self) -> VirtualAddress {
+        self.stack_range.end()
+    }
+
+    /// The address where the program break starts.
+    pub fn brk_base(&self) -> VirtualAddress {
+        self.brk_start
+    }
+
+    /// The address of the program headers, as reported by `AT_PHDR`.
+    pub fn phdr(&self) -> VirtualAddress {
+        self.phdr
+    }
+
+    /// Whether the executable is position independent.
+    pub fn is_pie(&self) -> bool {
+        self.pie
+    }
+}
+
+/// The layout of a [`MemorySpace`] at some point, i.e. the metadata of its areas but not
+/// their contents, see [`MemorySpace::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySnapshot {
+    areas: Vec<AreaSnapshot>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AreaSnapshot {
+    range: VirtualPageNumRange,
+    area_type: AreaType,
+    map_type: MapType,
+    permissions: GenericMappingFlags,
+}
+
+impl AreaSnapshot {
+    fn of(area: &MappingArea) -> Self {
+        Self {
+            range: area.range(),
+            area_type: area.area_type,
+            map_type: area.map_type,
+            permissions: area.permissions(),
+        }
+    }
+}
+
+impl MemorySnapshot {
+    /// The number of areas recorded in the snapshot.
+    pub fn area_count(&self) -> usize {
+        self.areas.len()
+    }
+}
+
 impl MemorySpace {
     pub fn mappings(&self) -> &[MappingArea] {
         &self.mapping_areas
     }
 
-    pub fn alloc_and_map_area(&mut self, mut area: MappingArea) {
+    /// Iterates over the mapped areas in ascending order of their start page.
+    pub fn iter_areas(&self) -> impl Iterator<Item = &MappingArea> {
+        let mut areas = self.mapping_areas.iter().collect::<Vec<_>>();
+        areas.sort_by_key(|area| area.range().start());
+        areas.into_iter()
+    }
+
+    /// The number of areas currently mapped in this memory space.
+    pub fn area_count(&self) -> usize {
+        self.mapping_areas.len()
+    }
+
+    /// The resident set size in bytes, i.e. the size of the frames backing the areas.
+    ///
+    /// Pages that are reserved or not populated yet do not count.
+    pub fn rss_bytes(&self) -> usize {
+        self.mapping_areas
+            .iter()
+            .map(|area| area.backing_frames().count())
+            .sum::<usize>()
+            * constants::PAGE_SIZE
+    }
+
+    /// The total size in bytes of the virtual ranges covered by the areas, whether they
+    /// are backed by frames or not.
+    pub fn virtual_bytes(&self) -> usize {
+        self.mapping_areas
+            .iter()
+            .map(|area| area.range().page_count())
+            .sum::<usize>()
+            * constants::PAGE_SIZE
+    }
+
+    /// Allocates frames for every page of the area and maps them.
+    ///
+    /// If the allocator runs out of frames or the mmu fails partway through, every page
+    /// mapped so far is unmapped, the allocated frames are released, and the area is not
+    /// recorded.
+    pub fn alloc_and_map_area(&mut self, mut area: MappingArea) -> PagingResult<()> {
         debug_assert!(area.allocation.is_none());
 
         let mut alloc = self.create_empty_area_allocation();
 
-        {
+        let result = (|| {
             for vpn in area.range().iter() {
-                let frame = alloc.allocator.lock().alloc_frame().unwrap();
-                let paddr = frame.0;
+                let frame = alloc
+                    .allocator
+                    .lock()
+                    .alloc_frame()
+                    .ok_or(PagingError::OutOfMemory)?;
+                let paddr = frame.addr();
 
                 alloc.frames.insert(vpn, frame);
 
-                self.mmu
-                    .lock()
-                    .map_single(vpn.start_addr(), paddr, PageSize::_4K, area.permissions())
-                    .unwrap();
+                self.mmu.lock().map_single(
+                    vpn.start_addr(),
+                    paddr,
+                    PageSize::_4K,
+                    area.permissions(),
+                )?;
+            }
+
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            let mut mmu = self.mmu.lock();
+
+            for vpn in alloc.frames.keys() {
+                // the last page may fail to map, ignore it
+                let _ = mmu.unmap_single(vpn.start_addr());
             }
+
+            // Drop alloc to release allocated frames
+            return Err(e);
         }
 
         area.allocation = Some(alloc);
         self.mapping_areas.push(area);
+
+        Ok(())
+    }
+
+    /// Records an area without allocating frames or mapping it, so it only occupies the
+    /// virtual address space, e.g. for guard pages.
+    ///
+    /// Fails with `AlreadyMapped` if the range overlaps with an existing area.
+    pub fn reserve_range(
+        &mut self,
+        range: VirtualPageNumRange,
+        area_type: AreaType,
+    ) -> PagingResult<()> {
+        let overlaps = self
+            .mapping_areas
+            .iter()
+            .any(|area| area.range().start() < range.end() && range.start() < area.range().end());
+
+        if overlaps {
+            return Err(PagingError::AlreadyMapped);
+        }
+
+        self.mapping_areas.push(MappingArea::new(
+            range,
+            area_type,
+            MapType::Reserved,
+            GenericMappingFlags::empty(),
+            None,
+        ));
+
+        Ok(())
     }
 
     pub fn map_area(&mut self, area: MappingArea) {
@@ -117,6 +271,11 @@ impl MemorySpace {
         match self.mapping_areas.iter().position(predicate) {
             Some(index) => {
                 let area = self.mapping_areas.remove(index);
+
+                if area.map_type == MapType::Reserved {
+                    return true;
+                }
+
                 for vpn in area.range.iter() {
                     self.mmu.lock().unmap_single(vpn.start_addr()).unwrap();
                 }
@@ -136,6 +295,192 @@ impl MemorySpace {
     pub fn unmap_area_starts_with(&mut self, vpn: VirtualPageNum) -> bool {
         self.unmap_first_area_that(&|area| area.range.start() == vpn)
     }
+
+    /// Unmaps every page in `range` and releases the frames backing them.
+    ///
+    /// Areas partially covered by `range` are trimmed, or split in two if `range` lies in
+    /// their middle.
+    pub fn unmap_range(&mut self, range: VirtualPageNumRange) {
+        let mut idx = 0;
+
+        while idx < self.mapping_areas.len() {
+            let area_range = self.mapping_areas[idx].range();
+
+            let start = area_range.start().max(range.start());
+            let end = area_range.end().min(range.end());
+
+            if start >= end {
+                idx += 1;
+                continue;
+            }
+
+            let area = &mut self.mapping_areas[idx];
+
+            if area.map_type != MapType::Reserved {
+                let mut mmu = self.mmu.lock();
+
+                for vpn in VirtualPageNumRange::from_start_end(start, end).iter() {
+                    mmu.unmap_single(vpn.start_addr()).unwrap();
+                }
+            }
+
+            let tail = (end < area_range.end()).then(|| area.split_off(end));
+
+            // Drop the unmapped part to release its frames
+            match (start > area_range.start(), tail) {
+                (true, tail) => {
+                    drop(area.split_off(start));
+
+                    if let Some(tail) = tail {
+                        self.mapping_areas.push(tail);
+                    }
+                }
+                (false, Some(tail)) => *area = tail,
+                // Keep the brk area so that the program break can still be looked up
+                (false, None) if area.area_type == AreaType::Heap => {
+                    area.range = VirtualPageNumRange::from_start_count(start, 0);
+
+                    if let Some(allocation) = area.allocation.as_mut() {
+                        let empty = MappingAreaAllocation::empty(allocation.allocator.clone());
+                        drop(core::mem::replace(allocation, empty));
+                    }
+                }
+                (false, None) => {
+                    self.mapping_areas.remove(idx);
+                    continue;
+                }
+            }
+
+            idx += 1;
+        }
+    }
+
+    /// Records the current set of areas, to check later that an operation left the space
+    /// unchanged or to revert it with [`restore`](Self::restore).
+    ///
+    /// Only the metadata is recorded, not the contents of the areas.
+    pub fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            areas: self.iter_areas().map(AreaSnapshot::of).collect(),
+        }
+    }
+
+    /// Reverts the space to the set of areas recorded in `snapshot`.
+    ///
+    /// Areas that differ from the snapshot, e.g. added or trimmed since, are unmapped and
+    /// areas missing from the space are mapped again with fresh frames, so their previous
+    /// contents are lost. This is a test and debugging aid, not a way to roll back user
+    /// visible state.
+    ///
+    /// The order of the areas may change, the brk area is still found by its type.
+    pub fn restore(&mut self, snapshot: &MemorySnapshot) -> PagingResult<()> {
+        self.unmap_all_areas_that(|area| !snapshot.areas.contains(&AreaSnapshot::of(area)));
+
+        for recorded in snapshot.areas.iter() {
+            if self
+                .mapping_areas
+                .iter()
+                .any(|area| AreaSnapshot::of(area) == *recorded)
+            {
+                continue;
+            }
+
+            match recorded.map_type {
+                MapType::Reserved => self.reserve_range(recorded.range, recorded.area_type)?,
+                _ => self.alloc_and_map_area(MappingArea::new(
+                    recorded.range,
+                    recorded.area_type,
+                    recorded.map_type,
+                    recorded.permissions,
+                    None,
+                ))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks the layout invariants for debugging, returning a description of the first
+    /// violation found:
+    /// - no two areas overlap,
+    /// - the stack guards are neither backed by frames nor mapped,
+    /// - the brk area does not collide with the stack or its guards.
+    #[cfg(debug_assertions)]
+    pub fn verify_invariants(&self) -> Result<(), String> {
+        let areas = self
+            .iter_areas()
+            .filter(|area| !area.range().is_empty())
+            .collect::<Vec<_>>();
+
+        for pair in areas.windows(2) {
+            if pair[0].range().end() > pair[1].range().start() {
+                return Err(format!(
+                    "{:?} area {:?} overlaps with {:?} area {:?}",
+                    pair[0].area_type,
+                    pair[0].range(),
+                    pair[1].area_type,
+                    pair[1].range()
+                ));
+            }
+        }
+
+        let mmu = self.mmu.lock();
+
+        for guard in self.mapping_areas.iter().filter(|area| {
+            matches!(
+                area.area_type,
+                AreaType::UserStackGuardBase | AreaType::UserStackGuardTop
+            )
+        }) {
+            if guard.backing_frames().next().is_some() {
+                return Err(format!(
+                    "{:?} area {:?} is backed by frames",
+                    guard.area_type,
+                    guard.range()
+                ));
+            }
+
+            if let Some(vpn) = guard
+                .range()
+                .iter()
+                .find(|vpn| mmu.is_mapped(vpn.start_addr()))
+            {
+                return Err(format!(
+                    "{:?} area {:?} is mapped at {:?}",
+                    guard.area_type,
+                    guard.range(),
+                    vpn.start_addr()
+                ));
+            }
+        }
+
+        let Some(attr) = self.attr.get() else {
+            return Ok(());
+        };
+
+        if let Some(brk) = self
+            .mapping_areas
+            .iter()
+            .find(|area| area.area_type == AreaType::Heap)
+        {
+            let brk_range = VirtualAddressRange::from_start_end(
+                brk.range().start().start_addr(),
+                brk.range().end().start_addr(),
+            );
+
+            for (name, range) in [
+                ("stack guard base", attr.stack_guard_base),
+                ("stack", attr.stack_range),
+                ("stack guard top", attr.stack_guard_top),
+            ] {
+                if brk_range.intersects(range) {
+                    return Err(format!("brk {brk_range:?} collides with {name} {range:?}"));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl MemorySpace {
@@ -147,12 +492,38 @@ impl MemorySpace {
         self.attr().brk_start
     }
 
+    pub fn entry(&self) -> VirtualAddress {
+        self.attr().entry()
+    }
+
+    pub fn user_stack_top(&self) -> VirtualAddress {
+        self.attr().user_stack_top()
+    }
+
+    pub fn brk_base(&self) -> VirtualAddress {
+        self.attr().brk_base()
+    }
+
+    pub fn phdr(&self) -> VirtualAddress {
+        self.attr().phdr()
+    }
+
+    pub fn is_pie(&self) -> bool {
+        self.attr().is_pie()
+    }
+
     pub fn brk_page_range(&self) -> VirtualPageNumRange {
         self.mapping_areas[self.brk_area_idx()].range()
     }
 
+    /// The index of the brk area in [`mappings`](Self::mappings).
+    ///
+    /// Looked up by its type, as removing other areas shifts the indices.
     pub fn brk_area_idx(&self) -> usize {
-        self.attr().brk_area_idx
+        self.mapping_areas
+            .iter()
+            .position(|area| area.area_type == AreaType::Heap)
+            .expect("Heap area not found")
     }
 
     pub fn increase_brk(&mut self, new_end_vpn: VirtualPageNum) -> Result<(), &str> {
@@ -170,6 +541,14 @@ impl MemorySpace {
             old_end_vpn = brk_area.range.end();
         }
 
+        let collides = self.mapping_areas.iter().enumerate().any(|(idx, area)| {
+            idx != brk_idx && area.range.start() < new_end_vpn && old_end_vpn < area.range.end()
+        });
+
+        if collides {
+            return Err("New end collides with another area");
+        }
+
         let page_count = new_end_vpn.diff_page_count(old_end_vpn);
 
         if page_count == 0 {
@@ -181,7 +560,7 @@ impl MemorySpace {
 
         for vpn in increased_range.iter() {
             let frame = self.allocator.lock().alloc_frame().unwrap();
-            let paddr = frame.0;
+            let paddr = frame.addr();
 
             let area = &mut self.mapping_areas[brk_idx];
 
@@ -202,10 +581,21 @@ impl MemorySpace {
 }
 
 impl MemorySpace {
+    /// Creates an empty memory space.
+    ///
+    /// The MMU must either be bound to `allocator` or not allocate at all, otherwise frames
+    /// would be allocated from one pool and freed into another.
     pub fn new(
         mmu: Arc<SpinMutex<dyn IMMU>>,
         allocator: Arc<SpinMutex<dyn IFrameAllocator>>,
     ) -> Self {
+        debug_assert!(
+            mmu.lock()
+                .bound_alloc()
+                .is_none_or(|bound| Arc::ptr_eq(&bound, &allocator)),
+            "The MMU is bound to another allocator"
+        );
+
         Self {
             mmu,
             mapping_areas: Vec::new(),
@@ -252,7 +642,13 @@ impl MemorySpace {
 
         for area in them.mapping_areas.iter() {
             let my_area = MappingArea::clone_from(area);
-            this.alloc_and_map_area(my_area);
+
+            if area.map_type == MapType::Reserved {
+                this.mapping_areas.push(my_area);
+                continue;
+            }
+
+            this.alloc_and_map_area(my_area).unwrap();
 
             // Copy datas through high half address
             for src_page in area.range.iter() {
@@ -269,7 +665,8 @@ impl MemorySpace {
             }
         }
 
-        *this.attr.get_mut().unwrap() = *them.attr();
+        // Spaces that are not initialized yet have no attributes to copy
+        this.attr = them.attr.clone();
 
         this
     }
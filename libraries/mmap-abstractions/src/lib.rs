@@ -18,5 +18,8 @@ bitflags! {
         const ANONYMOUS = 0x00;
         const SHARED = 0x01;
         const PRIVATE = 0x02;
+        const FIXED = 0x10;
+        const NORESERVE = 0x4000;
+        const STACK = 0x20000;
     }
 }
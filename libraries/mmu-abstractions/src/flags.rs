@@ -10,3 +10,72 @@ bitflags::bitflags! {
         const Uncached = 1 << 6;
     }
 }
+
+impl GenericMappingFlags {
+    /// The most an entry pointing to a next level page table reports: it is only walked by
+    /// the hardware, so it is never readable, writable or executable by itself.
+    pub const TABLE_POINTER: Self = Self::Kernel;
+
+    /// Checks whether every flag in `other` is set. An empty `other` is always contained.
+    pub const fn contains_all(&self, other: Self) -> bool {
+        self.bits() & other.bits() == other.bits()
+    }
+
+    /// Checks whether at least one flag in `other` is set. An empty `other` is never contained.
+    pub const fn contains_any(&self, other: Self) -> bool {
+        self.bits() & other.bits() != 0
+    }
+}
+
+bitflags::bitflags! {
+    /// The optional operations an [`IMMU`](crate::IMMU) implementation supports, so callers can
+    /// branch on them instead of downcasting or hitting a panic.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MmuCapabilities: usize {
+        /// Physical memory can be accessed through `translate_phys`.
+        const LINEAR_MAP = 1 << 0;
+        /// Memory of another MMU can be mapped with `map_cross`.
+        const CROSS_MAP = 1 << 1;
+        /// `platform_payload` returns a value the platform can activate.
+        const PLATFORM_PAYLOAD = 1 << 2;
+        /// Pages larger than 4K can be mapped.
+        const HUGE_PAGES = 1 << 3;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flags_contains_all() {
+        let flags = GenericMappingFlags::User | GenericMappingFlags::Readable;
+
+        // Subsets
+        assert!(flags.contains_all(GenericMappingFlags::User));
+        assert!(flags.contains_all(flags));
+        assert!(flags.contains_all(GenericMappingFlags::empty()));
+
+        // Supersets
+        assert!(!flags.contains_all(flags | GenericMappingFlags::Writable));
+
+        // Disjoint sets
+        assert!(!flags.contains_all(GenericMappingFlags::Writable | GenericMappingFlags::Kernel));
+    }
+
+    #[test]
+    fn test_flags_contains_any() {
+        let flags = GenericMappingFlags::User | GenericMappingFlags::Readable;
+
+        // Subsets
+        assert!(flags.contains_any(GenericMappingFlags::User));
+        assert!(flags.contains_any(flags));
+        assert!(!flags.contains_any(GenericMappingFlags::empty()));
+
+        // Supersets
+        assert!(flags.contains_any(flags | GenericMappingFlags::Writable));
+
+        // Disjoint sets
+        assert!(!flags.contains_any(GenericMappingFlags::Writable | GenericMappingFlags::Kernel));
+    }
+}
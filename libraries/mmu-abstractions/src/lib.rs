@@ -1,8 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use core::ops::{Deref, DerefMut};
-
-use address::{PhysicalAddress, VirtualAddress};
+use core::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut, Range},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use abstractions::IUsizeAlias;
+use address::{IAlignableAddress, PhysicalAddress, VirtualAddress};
+use alloc::sync::Arc;
+use allocation_abstractions::IFrameAllocator;
+use constants::ErrNo;
+use hermit_sync::SpinMutex;
 
 #[cfg(feature = "std")]
 extern crate std;
@@ -11,8 +20,9 @@ extern crate alloc;
 
 mod flags;
 
+pub use constants::PageSize;
 use downcast_rs::{impl_downcast, Downcast};
-pub use flags::GenericMappingFlags;
+pub use flags::{GenericMappingFlags, MmuCapabilities};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MMUError {
@@ -24,6 +34,35 @@ pub enum MMUError {
     CanNotModify,
     PageNotReadable { vaddr: VirtualAddress },
     PageNotWritable { vaddr: VirtualAddress },
+    AlreadyMapped,
+    OutOfMemory,
+}
+
+/// How an `inspect_framed` scan ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InspectOutcome {
+    /// Every byte of the range was passed to the callback.
+    Completed,
+    /// The callback returned `false`, `bytes_processed` counts the bytes passed to it,
+    /// including the chunk it stopped at.
+    Stopped { bytes_processed: usize },
+}
+
+impl From<MMUError> for ErrNo {
+    fn from(value: MMUError) -> Self {
+        match value {
+            MMUError::InvalidAddress
+            | MMUError::AccessFault
+            | MMUError::PageNotReadable { .. }
+            | MMUError::PageNotWritable { .. } => ErrNo::BadAddress,
+            MMUError::PrivilegeError => ErrNo::PermissionDenied,
+            MMUError::MisalignedAddress => ErrNo::InvalidArgument,
+            MMUError::Borrowed => ErrNo::ResourceTemporarilyUnavailable,
+            MMUError::CanNotModify => ErrNo::OperationNotPermitted,
+            MMUError::AlreadyMapped => ErrNo::FileExists,
+            MMUError::OutOfMemory => ErrNo::CannotAllocateMemory,
+        }
+    }
 }
 
 impl dyn IMMU {
@@ -32,7 +71,7 @@ impl dyn IMMU {
         vaddr: VirtualAddress,
         len: usize,
         mut callback: impl FnMut(&[u8], usize) -> bool,
-    ) -> Result<(), MMUError> {
+    ) -> Result<InspectOutcome, MMUError> {
         self.inspect_framed_internal(vaddr, len, &mut callback)
     }
 
@@ -41,11 +80,16 @@ impl dyn IMMU {
         vaddr: VirtualAddress,
         len: usize,
         mut callback: impl FnMut(&mut [u8], usize) -> bool,
-    ) -> Result<(), MMUError> {
+    ) -> Result<InspectOutcome, MMUError> {
         self.inspect_framed_mut_internal(vaddr, len, &mut callback)
     }
 
     pub fn import<T: Copy>(&self, vaddr: VirtualAddress) -> Result<T, MMUError> {
+        // Never hand out a partially read value
+        if !self.is_range_mapped(vaddr, core::mem::size_of::<T>()) {
+            return Err(MMUError::AccessFault);
+        }
+
         let mut value: T = unsafe { core::mem::zeroed() };
         let value_bytes = unsafe {
             core::slice::from_raw_parts_mut(
@@ -58,6 +102,14 @@ impl dyn IMMU {
     }
 
     pub fn export<T: Copy>(&self, vaddr: VirtualAddress, value: T) -> Result<(), MMUError> {
+        if !self.is_range_mapped(vaddr, core::mem::size_of::<T>()) {
+            return Err(MMUError::AccessFault);
+        }
+
+        // Validate the permissions of the whole range before writing anything, so a fault
+        // in a later page can not leave a partially written value behind
+        self.inspect_framed_mut(vaddr, core::mem::size_of::<T>(), |_, _| true)?;
+
         let value_bytes = unsafe {
             core::slice::from_raw_parts(&value as *const T as *const u8, core::mem::size_of::<T>())
         };
@@ -65,10 +117,41 @@ impl dyn IMMU {
         self.write_bytes(vaddr, value_bytes)
     }
 
+    /// Atomically replaces the `u32` at `vaddr` with `new` if it equals `expected`, e.g. for
+    /// futexes.
+    ///
+    /// Returns the value observed before the operation, the swap happened if it equals
+    /// `expected`. `vaddr` must be aligned to 4 bytes and writable, even if the swap fails.
+    pub fn cas_u32(&self, vaddr: VirtualAddress, expected: u32, new: u32) -> Result<u32, MMUError> {
+        if !vaddr.is_aligned(core::mem::align_of::<u32>()) {
+            return Err(MMUError::MisalignedAddress);
+        }
+
+        let mut observed = None;
+
+        self.inspect_framed_mut(vaddr, core::mem::size_of::<u32>(), |slice, _| {
+            // An aligned u32 never crosses a page boundary
+            debug_assert_eq!(slice.len(), core::mem::size_of::<u32>());
+
+            let atomic = unsafe { AtomicU32::from_ptr(slice.as_mut_ptr().cast()) };
+
+            observed = Some(
+                match atomic.compare_exchange(expected, new, Ordering::SeqCst, Ordering::SeqCst) {
+                    Ok(value) | Err(value) => value,
+                },
+            );
+
+            false
+        })?;
+
+        observed.ok_or(MMUError::AccessFault)
+    }
+
     pub fn map_buffer(&self, vaddr: VirtualAddress, len: usize) -> Result<Memory<'_>, MMUError> {
         #[allow(deprecated)]
         self.map_buffer_internal(vaddr, len).map(|buf| Memory {
             mmu: self,
+            vaddr,
             slice: buf,
         })
     }
@@ -83,24 +166,84 @@ impl dyn IMMU {
         self.map_buffer_mut_internal(vaddr, len, force_mut)
             .map(|buf| MemoryMut {
                 mmu: self,
+                vaddr,
                 slice: buf,
             })
     }
 
+    /// Runs `f` with the physical memory `[paddr, paddr + len)` accessible, e.g. to initialize a
+    /// frame that is not mapped to any virtual address yet.
+    ///
+    /// The memory is only accessible within `f`, so no reference to it can outlive the call.
+    pub fn with_temp_phys<R>(
+        &self,
+        paddr: PhysicalAddress,
+        len: usize,
+        f: impl FnOnce(&mut [u8]) -> R,
+    ) -> Result<R, MMUError> {
+        let slice = self.translate_phys(paddr, len)?;
+
+        Ok(f(slice))
+    }
+
+    /// Checks whether `vaddr` is mapped, regardless of its permissions.
+    pub fn is_mapped(&self, vaddr: VirtualAddress) -> bool {
+        self.query_virtual(vaddr).is_ok()
+    }
+
+    /// Checks whether every byte in `[vaddr, vaddr + len)` is mapped, regardless of
+    /// permissions. An empty range is always considered mapped.
+    pub fn is_range_mapped(&self, vaddr: VirtualAddress, len: usize) -> bool {
+        if len == 0 {
+            return true;
+        }
+
+        // The range can not wrap around the end of the address space
+        let Some(last) = vaddr.as_usize().checked_add(len - 1) else {
+            return false;
+        };
+        let last = VirtualAddress::from_usize(last);
+        let mut current = vaddr;
+
+        while current <= last {
+            let Ok((_, _, size)) = self.query_virtual(current) else {
+                return false;
+            };
+
+            // Skip the rest of the page, custom sizes are not necessarily aligned
+            let step = match size.as_usize() {
+                size if size.is_power_of_two() => size,
+                _ => PageSize::_4K.as_usize(),
+            };
+
+            // The last page of the address space has been checked
+            match current.align_down(step).as_usize().checked_add(step) {
+                Some(next) => current = VirtualAddress::from_usize(next),
+                None => break,
+            }
+        }
+
+        // The last page may be a custom sized mapping that ends before `last`
+        self.is_mapped(last)
+    }
+
+    /// Makes host memory accessible through this MMU at its own address, for tests.
+    ///
+    /// Fails if the MMU can not access host memory, e.g. a native page table.
     #[cfg(not(target_os = "none"))]
-    pub fn register<T>(&mut self, val: &T, mutable: bool) -> VirtualAddress {
+    pub fn register<T>(&mut self, val: &T, mutable: bool) -> Result<VirtualAddress, MMUError> {
         self.register_internal(
             VirtualAddress::from_ref(val),
             core::mem::size_of_val(val),
             mutable,
-        );
+        )?;
 
-        VirtualAddress::from_ref(val)
+        Ok(VirtualAddress::from_ref(val))
     }
 
     #[cfg(not(target_os = "none"))]
-    pub fn unregister<T>(&mut self, val: &T) {
-        self.unregister_internal(VirtualAddress::from_ref(val));
+    pub fn unregister<T>(&mut self, val: &T) -> Result<(), MMUError> {
+        self.unregister_internal(VirtualAddress::from_ref(val))
     }
 }
 
@@ -141,7 +284,7 @@ pub trait IMMU: Downcast {
         vaddr: VirtualAddress,
         len: usize,
         callback: &mut dyn FnMut(&[u8], usize) -> bool,
-    ) -> Result<(), MMUError>;
+    ) -> Result<InspectOutcome, MMUError>;
 
     #[doc(hidden)]
     fn inspect_framed_mut_internal(
@@ -149,7 +292,7 @@ pub trait IMMU: Downcast {
         vaddr: VirtualAddress,
         len: usize,
         callback: &mut dyn FnMut(&mut [u8], usize) -> bool,
-    ) -> Result<(), MMUError>;
+    ) -> Result<InspectOutcome, MMUError>;
 
     fn translate_phys(
         &self,
@@ -157,8 +300,14 @@ pub trait IMMU: Downcast {
         len: usize,
     ) -> Result<&'static mut [u8], MMUError>;
 
+    /// Reads `buf.len()` bytes starting at `vaddr`.
+    ///
+    /// A zero-length read always succeeds, regardless of `vaddr`.
     fn read_bytes(&self, vaddr: VirtualAddress, buf: &mut [u8]) -> Result<(), MMUError>;
 
+    /// Writes `buf` starting at `vaddr`.
+    ///
+    /// A zero-length write always succeeds, regardless of `vaddr`.
     fn write_bytes(&self, vaddr: VirtualAddress, buf: &[u8]) -> Result<(), MMUError>;
 
     /// Maps a memory area from another MMU.
@@ -195,17 +344,39 @@ pub trait IMMU: Downcast {
 
     fn unmap_buffer(&self, vaddr: VirtualAddress);
 
+    /// Drops every outstanding reference to the buffer containing `vaddr`, as if all its
+    /// guards were dropped.
+    ///
+    /// Only meant for tests and debugging, to recover from a leaked [`Memory`] or
+    /// [`MemoryMut`] guard that keeps returning [`MMUError::Borrowed`]. Any slice still
+    /// handed out for the buffer must not be used afterwards.
+    fn force_unmap_buffer(&self, vaddr: VirtualAddress);
+
     fn unmap_cross(&mut self, source: &dyn IMMU, vaddr: VirtualAddress);
 
-    fn platform_payload(&self) -> usize;
+    /// The value the platform needs to activate this MMU, e.g. the physical address of the
+    /// root page table, or `None` if the MMU can not be activated, like in test environments.
+    fn platform_payload(&self) -> Option<usize>;
+
+    /// The optional operations supported by this MMU.
+    fn capabilities(&self) -> MmuCapabilities;
+
+    /// The allocator the MMU allocates and frees its frames with, or `None` if it never does,
+    /// e.g. a borrowed page table.
+    fn bound_alloc(&self) -> Option<Arc<SpinMutex<dyn IFrameAllocator>>>;
 
     #[doc(hidden)]
     #[cfg(not(target_os = "none"))]
-    fn register_internal(&mut self, vaddr: VirtualAddress, len: usize, mutable: bool);
+    fn register_internal(
+        &mut self,
+        vaddr: VirtualAddress,
+        len: usize,
+        mutable: bool,
+    ) -> Result<(), MMUError>;
 
     #[doc(hidden)]
     #[cfg(not(target_os = "none"))]
-    fn unregister_internal(&mut self, vaddr: VirtualAddress);
+    fn unregister_internal(&mut self, vaddr: VirtualAddress) -> Result<(), MMUError>;
 }
 
 impl_downcast!(IMMU);
@@ -213,8 +384,12 @@ impl_downcast!(IMMU);
 /// The error type for page table operation failures.
 #[derive(Debug, PartialEq, Eq)]
 pub enum PagingError {
-    /// The address is not aligned to the page size.
-    NotAligned,
+    /// The virtual address is not aligned to the page size.
+    NotAlignedVaddr,
+    /// The physical address is not aligned to the page size.
+    NotAlignedPaddr,
+    /// The page size is not supported, e.g. not a multiple of the base page size.
+    NotAlignedSize,
     /// The mapping is not present.
     NotMapped,
     /// The mapping is already present.
@@ -222,55 +397,31 @@ pub enum PagingError {
     /// The page table entry represents a huge page, but the target physical
     /// frame is 4K in size.
     MappedToHugePage,
+    /// The page can not be a leaf at the level of its size, e.g. the architecture has no
+    /// pages of that size or the flags would turn the entry into a table.
+    NotLeafCapable,
     CanNotModify,
     OutOfMemory,
 }
 
-// cargo clippy keeps complaining about use <XXXX as Into<T>>::into(e) if From is implemented
-// so we use Into over From
-#[allow(clippy::from_over_into)]
-impl Into<MMUError> for PagingError {
-    fn into(self) -> MMUError {
-        match self {
-            PagingError::NotAligned => MMUError::MisalignedAddress,
-            PagingError::NotMapped => MMUError::InvalidAddress,
-            _ => unimplemented!("Should never happen: {:?}", self),
-        }
-    }
-}
-
-/// The page sizes supported by the hardware page table.
-#[repr(usize)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum PageSize {
-    /// Size of 4 kilobytes (2<sup>12</sup> bytes).
-    _4K = 0x1000,
-    /// Size of 2 megabytes (2<sup>21</sup> bytes).
-    _2M = 0x20_0000,
-    /// Size of 1 gigabytes (2<sup>30</sup> bytes).
-    _1G = 0x4000_0000,
-    Custom(usize),
-}
-
-impl From<usize> for PageSize {
-    fn from(value: usize) -> Self {
+impl From<PagingError> for MMUError {
+    fn from(value: PagingError) -> Self {
         match value {
-            0x1000 => PageSize::_4K,
-            0x20_0000 => PageSize::_2M,
-            0x4000_0000 => PageSize::_1G,
-            _ => PageSize::Custom(value),
+            PagingError::NotAlignedVaddr
+            | PagingError::NotAlignedPaddr
+            | PagingError::NotAlignedSize
+            | PagingError::NotLeafCapable => MMUError::MisalignedAddress,
+            PagingError::NotMapped | PagingError::MappedToHugePage => MMUError::InvalidAddress,
+            PagingError::AlreadyMapped => MMUError::AlreadyMapped,
+            PagingError::CanNotModify => MMUError::CanNotModify,
+            PagingError::OutOfMemory => MMUError::OutOfMemory,
         }
     }
 }
 
-impl PageSize {
-    pub const fn as_usize(&self) -> usize {
-        match self {
-            PageSize::_4K => 0x1000,
-            PageSize::_2M => 0x20_0000,
-            PageSize::_1G => 0x4000_0000,
-            PageSize::Custom(v) => *v,
-        }
+impl From<PagingError> for ErrNo {
+    fn from(value: PagingError) -> Self {
+        MMUError::from(value).into()
     }
 }
 
@@ -278,6 +429,8 @@ pub type PagingResult<TValue> = Result<TValue, PagingError>;
 
 pub struct Memory<'a> {
     mmu: &'a dyn IMMU,
+    // The slice may not point to vaddr, e.g. a bounce buffer, so we keep it for unmapping
+    vaddr: VirtualAddress,
     slice: &'a [u8],
 }
 
@@ -289,15 +442,40 @@ impl Deref for Memory<'_> {
     }
 }
 
+impl<'a> Memory<'a> {
+    /// Narrows the guard to the given range of the buffer.
+    ///
+    /// Unlike slicing through `Deref`, the returned guard keeps the buffer mapped
+    /// and unmaps it when dropped.
+    pub fn subslice(self, range: Range<usize>) -> Memory<'a> {
+        let this = ManuallyDrop::new(self);
+
+        // An empty guard owns no buffer, see `Drop`
+        if range.is_empty() && !this.slice.is_empty() {
+            this.mmu.unmap_buffer(this.vaddr);
+        }
+
+        Memory {
+            mmu: this.mmu,
+            vaddr: this.vaddr + range.start,
+            slice: &this.slice[range],
+        }
+    }
+}
+
 impl Drop for Memory<'_> {
     fn drop(&mut self) {
-        self.mmu
-            .unmap_buffer(VirtualAddress::from_ptr(self.slice.as_ptr()));
+        // Empty buffers are never tracked, their address may belong to another buffer
+        if !self.slice.is_empty() {
+            self.mmu.unmap_buffer(self.vaddr);
+        }
     }
 }
 
 pub struct MemoryMut<'a> {
     mmu: &'a dyn IMMU,
+    // The slice may not point to vaddr, e.g. a bounce buffer, so we keep it for unmapping
+    vaddr: VirtualAddress,
     slice: &'a mut [u8],
 }
 
@@ -315,9 +493,69 @@ impl DerefMut for MemoryMut<'_> {
     }
 }
 
+impl<'a> MemoryMut<'a> {
+    /// Narrows the guard to the given range of the buffer.
+    ///
+    /// Unlike slicing through `DerefMut`, the returned guard keeps the buffer mapped
+    /// and unmaps it when dropped.
+    pub fn subslice(self, range: Range<usize>) -> MemoryMut<'a> {
+        let mut this = ManuallyDrop::new(self);
+        let slice = core::mem::take(&mut this.slice);
+
+        // An empty guard owns no buffer, see `Drop`
+        if range.is_empty() && !slice.is_empty() {
+            this.mmu.unmap_buffer(this.vaddr);
+        }
+
+        MemoryMut {
+            mmu: this.mmu,
+            vaddr: this.vaddr + range.start,
+            slice: &mut slice[range],
+        }
+    }
+}
+
 impl Drop for MemoryMut<'_> {
     fn drop(&mut self) {
-        self.mmu
-            .unmap_buffer(VirtualAddress::from_ptr(self.slice.as_ptr()));
+        // Empty buffers are never tracked, their address may belong to another buffer
+        if !self.slice.is_empty() {
+            self.mmu.unmap_buffer(self.vaddr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_already_mapped_error_chain() {
+        let mmu_error = MMUError::from(PagingError::AlreadyMapped);
+
+        assert_eq!(mmu_error, MMUError::AlreadyMapped);
+        assert_eq!(ErrNo::from(mmu_error), ErrNo::FileExists);
+    }
+
+    #[test]
+    fn test_paging_errors_convert_without_panic() {
+        for error in [
+            PagingError::NotAlignedVaddr,
+            PagingError::NotAlignedPaddr,
+            PagingError::NotAlignedSize,
+            PagingError::NotMapped,
+            PagingError::AlreadyMapped,
+            PagingError::MappedToHugePage,
+            PagingError::NotLeafCapable,
+            PagingError::CanNotModify,
+            PagingError::OutOfMemory,
+        ] {
+            let _: ErrNo = error.into();
+        }
+
+        assert_eq!(
+            ErrNo::from(PagingError::OutOfMemory),
+            ErrNo::CannotAllocateMemory
+        );
+        assert_eq!(ErrNo::from(PagingError::NotMapped), ErrNo::BadAddress);
     }
 }
@@ -1,8 +1,6 @@
 mod pte;
 pub use pte::*;
 
-#[cfg(target_os = "none")]
 mod pt;
 
-#[cfg(target_os = "none")]
 pub use pt::*;
@@ -194,6 +194,18 @@ impl Debug for LA64PageTableEntry {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_new_table() {
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+        let pte = LA64PageTableEntry::new_table(paddr);
+
+        // Directory entries only hold the address of the next level
+        assert!(!pte.is_empty());
+        assert!(!pte.is_huge());
+        assert_eq!(pte.paddr(), paddr);
+        assert!(GenericMappingFlags::TABLE_POINTER.contains(pte.flags()));
+    }
+
     #[test]
     fn test_to_arch_empty() {
         let flags = GenericMappingFlags::empty();
@@ -1,7 +1,7 @@
-#[cfg(any(target_arch = "riscv64", all(test, feature = "riscv64")))]
+#[cfg(any(target_arch = "riscv64", all(test, not(feature = "loongarch64"))))]
 pub mod riscv64;
 
-#[cfg(any(target_arch = "riscv64", all(test, feature = "riscv64")))]
+#[cfg(any(target_arch = "riscv64", all(test, not(feature = "loongarch64"))))]
 pub use riscv64::*;
 
 #[cfg(any(target_arch = "loongarch64", all(test, feature = "loongarch64")))]
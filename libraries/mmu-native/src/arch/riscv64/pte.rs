@@ -18,9 +18,18 @@ bitflags::bitflags! {
         const Accessed = 1 << 6;
         const Dirty = 1 << 7;
         const _Reserved8 = 1 << 8;
+        /// Svpbmt: non-cacheable, idempotent, weakly-ordered main memory
+        const NonCacheable = 1 << 61;
+        /// Svpbmt: non-cacheable, non-idempotent, strongly-ordered I/O memory
+        const IO = 1 << 62;
     }
 }
 
+const RV64_NC_MASK: usize = RV64PageTableEntryFlags::NonCacheable.bits();
+const RV64_IO_MASK: usize = RV64PageTableEntryFlags::IO.bits();
+const GENERIC_DEVICE_MASK: usize = GenericMappingFlags::Device.bits();
+const GENERIC_UNCACHED_MASK: usize = GenericMappingFlags::Uncached.bits();
+
 const RV64_USER_MASK: usize = RV64PageTableEntryFlags::User.bits();
 const RV64_USER_OFFSET: usize = 4;
 const GENERIC_USER_MASK: usize = GenericMappingFlags::User.bits();
@@ -53,7 +62,8 @@ impl const IGenericMappingFlags for GenericMappingFlags {
                 | ((bits & RV64_USER_MASK) >> (RV64_USER_OFFSET - GENERIC_USER_OFFSET))
                 | ((bits & RV64_READABLE_MASK) >> (RV64_READABLE_OFFSET - GENERIC_READABLE_OFFSET))
                 | ((bits & RV64_WRITABLE_MASK) >> (RV64_WRITABLE_OFFSET - GENERIC_WRITABLE_OFFSET))
-                | ((bits & RV64_EXECUTABLE_MASK) >> (RV64_EXECUTABLE_OFFSET - GENERIC_EXECUTABLE_OFFSET)))
+                | ((bits & RV64_EXECUTABLE_MASK) >> (RV64_EXECUTABLE_OFFSET - GENERIC_EXECUTABLE_OFFSET))
+                | memory_type_from_arch(bits))
                 & ((bits & 0b1) * GenericMappingFlags::all().bits()),
         )
     }
@@ -70,26 +80,67 @@ impl const IGenericMappingFlags for GenericMappingFlags {
                 | ((bits & GENERIC_WRITABLE_MASK)
                     << (RV64_WRITABLE_OFFSET - GENERIC_WRITABLE_OFFSET))
                 | ((bits & GENERIC_EXECUTABLE_MASK)
-                    << (RV64_EXECUTABLE_OFFSET - GENERIC_EXECUTABLE_OFFSET)),
+                    << (RV64_EXECUTABLE_OFFSET - GENERIC_EXECUTABLE_OFFSET))
+                | memory_type_to_arch(bits),
         )
     }
 }
 
-const PTE_FLAGS_MASK: u64 = 0x1FF;
+/// Encodes `Device` as the IO memory type, which also disables caching, and `Uncached` as NC.
+#[inline(always)]
+const fn memory_type_to_arch(bits: usize) -> usize {
+    if bits & GENERIC_DEVICE_MASK != 0 {
+        RV64_IO_MASK
+    } else if bits & GENERIC_UNCACHED_MASK != 0 {
+        RV64_NC_MASK
+    } else {
+        0
+    }
+}
+
+#[inline(always)]
+const fn memory_type_from_arch(bits: usize) -> usize {
+    if bits & RV64_IO_MASK != 0 {
+        GENERIC_DEVICE_MASK | GENERIC_UNCACHED_MASK
+    } else if bits & RV64_NC_MASK != 0 {
+        GENERIC_UNCACHED_MASK
+    } else {
+        0
+    }
+}
+
 const PTE_PHYS_MASK: u64 = (1 << 54) - (1 << 10); // bits 10..54
 
+/// A page table entry of Sv39 and Sv48.
+///
+/// With `SVPBMT`, `Device` and `Uncached` are encoded as the memory types of the Svpbmt
+/// extension. Without it, bits 61-62 are reserved and the PMAs alone decide the memory type,
+/// so both flags are dropped instead of faulting on hardware lacking the extension. Defaults
+/// to whether the `svpbmt` feature is enabled.
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct RV64PageTableEntry(u64);
+pub struct RV64PageTableEntry<const SVPBMT: bool = { cfg!(feature = "svpbmt") }>(u64);
+
+impl<const SVPBMT: bool> RV64PageTableEntry<SVPBMT> {
+    const PTE_FLAGS_MASK: u64 = match SVPBMT {
+        true => 0x1FF | (0b11 << 61),
+        false => 0x1FF,
+    };
 
-impl RV64PageTableEntry {
     #[inline(always)]
     const fn flags_internal(&self) -> RV64PageTableEntryFlags {
-        RV64PageTableEntryFlags::from_bits_truncate((self.bits() & PTE_FLAGS_MASK) as usize)
+        RV64PageTableEntryFlags::from_bits_truncate((self.bits() & Self::PTE_FLAGS_MASK) as usize)
+    }
+
+    #[inline(always)]
+    const fn arch_flags(flags: GenericMappingFlags) -> RV64PageTableEntryFlags {
+        RV64PageTableEntryFlags::from_bits_truncate(
+            flags.to_arch().bits() & Self::PTE_FLAGS_MASK as usize,
+        )
     }
 }
 
-impl const IArchPageTableEntryBase for RV64PageTableEntry {
+impl<const SVPBMT: bool> const IArchPageTableEntryBase for RV64PageTableEntry<SVPBMT> {
     type RawType = u64;
 
     #[inline(always)]
@@ -147,8 +198,7 @@ impl const IArchPageTableEntryBase for RV64PageTableEntry {
 
     #[inline(always)]
     fn new_page(paddr: PhysicalAddress, flags: GenericMappingFlags, _huge: bool) -> Self {
-        let flags = flags
-            .to_arch()
+        let flags = Self::arch_flags(flags)
             .union(RV64PageTableEntryFlags::Accessed)
             .union(RV64PageTableEntryFlags::Dirty);
 
@@ -156,15 +206,16 @@ impl const IArchPageTableEntryBase for RV64PageTableEntry {
     }
 }
 
-impl IArchPageTableEntry for RV64PageTableEntry {
+impl<const SVPBMT: bool> IArchPageTableEntry for RV64PageTableEntry<SVPBMT> {
     fn set_paddr(&mut self, paddr: PhysicalAddress) {
         self.0 = (self.0 & !(PTE_PHYS_MASK)) // keep flags
             | ((paddr.as_usize() as u64 >> 2) & PTE_PHYS_MASK); // new paddr
     }
 
     fn set_flags(&mut self, flags: GenericMappingFlags, _huge: bool) {
-        let flags =
-            flags.to_arch() | RV64PageTableEntryFlags::Accessed | RV64PageTableEntryFlags::Dirty;
+        let flags = Self::arch_flags(flags)
+            | RV64PageTableEntryFlags::Accessed
+            | RV64PageTableEntryFlags::Dirty;
         self.0 = (self.0 & PTE_PHYS_MASK) | flags.bits() as u64;
     }
 
@@ -178,12 +229,12 @@ impl IArchPageTableEntry for RV64PageTableEntry {
     }
 
     fn add_flags(&mut self, flags: GenericMappingFlags) {
-        let to_add = flags.to_arch().bits() as u64;
+        let to_add = Self::arch_flags(flags).bits() as u64;
         self.0 |= to_add;
     }
 }
 
-impl Debug for RV64PageTableEntry {
+impl<const SVPBMT: bool> Debug for RV64PageTableEntry<SVPBMT> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("RV64PageTableEntry")
             .field("paddr", &self.paddr())
@@ -199,6 +250,9 @@ mod tests {
     use super::*;
     use mmu_abstractions::GenericMappingFlags;
 
+    /// Entries with the encoding selected by the `svpbmt` feature
+    type Entry = RV64PageTableEntry;
+
     #[test]
     fn test_flag_conversions() {
         let gm_flags = GenericMappingFlags::Readable;
@@ -260,11 +314,97 @@ mod tests {
         assert!(gm_flags.contains(GenericMappingFlags::Kernel));
     }
 
+    #[test]
+    fn test_memory_type_conversions() {
+        let rv_flags = GenericMappingFlags::Device.to_arch();
+        assert!(rv_flags.contains(RV64PageTableEntryFlags::IO));
+        assert!(!rv_flags.contains(RV64PageTableEntryFlags::NonCacheable));
+
+        let rv_flags = GenericMappingFlags::Uncached.to_arch();
+        assert!(rv_flags.contains(RV64PageTableEntryFlags::NonCacheable));
+        assert!(!rv_flags.contains(RV64PageTableEntryFlags::IO));
+
+        // Device memory is never cached, the encoding must not combine both types
+        let rv_flags = (GenericMappingFlags::Device | GenericMappingFlags::Uncached).to_arch();
+        assert!(rv_flags.contains(RV64PageTableEntryFlags::IO));
+        assert!(!rv_flags.contains(RV64PageTableEntryFlags::NonCacheable));
+
+        let gm_flags = GenericMappingFlags::from_arch(
+            RV64PageTableEntryFlags::Valid | RV64PageTableEntryFlags::IO,
+        );
+        assert!(gm_flags.contains(GenericMappingFlags::Device | GenericMappingFlags::Uncached));
+
+        let gm_flags = GenericMappingFlags::from_arch(
+            RV64PageTableEntryFlags::Valid | RV64PageTableEntryFlags::NonCacheable,
+        );
+        assert!(gm_flags.contains(GenericMappingFlags::Uncached));
+        assert!(!gm_flags.contains(GenericMappingFlags::Device));
+
+        let gm_flags = GenericMappingFlags::from_arch(RV64PageTableEntryFlags::Valid);
+        assert!(!gm_flags.contains_any(GenericMappingFlags::Device | GenericMappingFlags::Uncached));
+    }
+
+    #[test]
+    fn test_device_pte_keeps_paddr() {
+        let paddr = PhysicalAddress::from_usize(0x1000_0000);
+        let mut pte = RV64PageTableEntry::<true>::new_page(
+            paddr,
+            GenericMappingFlags::Readable
+                | GenericMappingFlags::Writable
+                | GenericMappingFlags::Device,
+            false,
+        );
+
+        assert_eq!(pte.paddr(), paddr);
+        assert!(pte.flags().contains(GenericMappingFlags::Device));
+
+        pte.set_paddr(PhysicalAddress::from_usize(0x2000_0000));
+        assert!(pte.flags().contains(GenericMappingFlags::Device));
+
+        pte.set_flags(GenericMappingFlags::Readable, false);
+        assert!(!pte.flags().contains(GenericMappingFlags::Device));
+        assert_eq!(pte.paddr(), PhysicalAddress::from_usize(0x2000_0000));
+    }
+
+    #[test]
+    fn test_memory_type_without_svpbmt() {
+        let paddr = PhysicalAddress::from_usize(0x1000_0000);
+        let rw = GenericMappingFlags::Readable | GenericMappingFlags::Writable;
+
+        for memory_type in [
+            GenericMappingFlags::Device,
+            GenericMappingFlags::Uncached,
+            GenericMappingFlags::Device | GenericMappingFlags::Uncached,
+        ] {
+            let mut pte = RV64PageTableEntry::<false>::new_page(paddr, rw | memory_type, false);
+
+            // The reserved bits 61-62 are never set
+            assert_eq!(pte.bits() >> 61, 0);
+            assert_eq!(pte.paddr(), paddr);
+            assert_eq!(pte.flags(), rw | GenericMappingFlags::Kernel);
+
+            pte.set_flags(rw | memory_type, false);
+            assert_eq!(pte.bits() >> 61, 0);
+
+            pte.add_flags(memory_type);
+            assert_eq!(pte.bits() >> 61, 0);
+            assert!(!pte.flags().contains_any(memory_type));
+        }
+
+        // Stray reserved bits are not taken as a memory type either
+        let pte = RV64PageTableEntry::<false>::from_bits(
+            (RV64PageTableEntryFlags::Valid | RV64PageTableEntryFlags::IO).bits() as u64,
+        );
+        assert!(!pte
+            .flags()
+            .contains_any(GenericMappingFlags::Device | GenericMappingFlags::Uncached));
+    }
+
     #[test]
     fn test_pte_construction() {
         let paddr = PhysicalAddress::from_usize(0x4000);
         let flags = GenericMappingFlags::Readable | GenericMappingFlags::Writable;
-        let pte = RV64PageTableEntry::new_page(paddr, flags, false);
+        let pte = Entry::new_page(paddr, flags, false);
 
         assert_eq!(pte.paddr(), paddr);
 
@@ -276,7 +416,7 @@ mod tests {
         assert!(!rv_flags.contains(RV64PageTableEntryFlags::Executable));
         assert!(!rv_flags.contains(RV64PageTableEntryFlags::User));
 
-        let table_pte = RV64PageTableEntry::new_table(paddr);
+        let table_pte = Entry::new_table(paddr);
         assert_eq!(table_pte.paddr(), paddr);
         assert!(table_pte
             .flags_internal()
@@ -288,7 +428,7 @@ mod tests {
 
     #[test]
     fn test_set_paddr() {
-        let mut pte = RV64PageTableEntry::new_page(
+        let mut pte = Entry::new_page(
             PhysicalAddress::from_usize(0x1000),
             GenericMappingFlags::empty(),
             false,
@@ -304,7 +444,7 @@ mod tests {
 
     #[test]
     fn test_set_flags() {
-        let mut pte = RV64PageTableEntry::new_page(
+        let mut pte = Entry::new_page(
             PhysicalAddress::from_usize(0x1000),
             GenericMappingFlags::Readable,
             false,
@@ -331,7 +471,7 @@ mod tests {
 
     #[test]
     fn test_add_remove_flags() {
-        let mut pte = RV64PageTableEntry::new_page(
+        let mut pte = Entry::new_page(
             PhysicalAddress::from_usize(0x1000),
             GenericMappingFlags::Readable,
             false,
@@ -358,9 +498,27 @@ mod tests {
             .contains(RV64PageTableEntryFlags::Dirty));
     }
 
+    #[test]
+    fn test_new_table() {
+        let paddr = PhysicalAddress::from_usize(0x8020_0000);
+        let pte = Entry::new_table(paddr);
+
+        assert!(pte.is_present());
+        assert!(!pte.is_empty());
+        assert!(!pte.is_huge());
+        assert_eq!(pte.paddr(), paddr);
+
+        assert!(!pte.flags().contains_any(
+            GenericMappingFlags::Readable
+                | GenericMappingFlags::Writable
+                | GenericMappingFlags::Executable
+        ));
+        assert_eq!(pte.flags(), GenericMappingFlags::TABLE_POINTER);
+    }
+
     #[test]
     fn test_clear_pte() {
-        let mut pte = RV64PageTableEntry::new_page(
+        let mut pte = Entry::new_page(
             PhysicalAddress::from_usize(0x1000),
             GenericMappingFlags::Readable,
             false,
@@ -371,7 +529,7 @@ mod tests {
 
     #[test]
     fn test_debug_output() {
-        let pte = RV64PageTableEntry::new_page(
+        let pte = Entry::new_page(
             PhysicalAddress::from_usize(0x1000),
             GenericMappingFlags::Executable,
             false,
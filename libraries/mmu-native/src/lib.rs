@@ -8,14 +8,14 @@ pub use arch::*;
 #[allow(unused_imports)]
 pub use pte::{IArchPageTableEntry, IArchPageTableEntryBase};
 
-mod arch;
-mod pte;
+pub use linear::*;
+pub use pt::*;
 
-#[cfg(target_os = "none")]
+mod arch;
+mod linear;
+mod lock_order;
 mod pt;
-
-#[cfg(target_os = "none")]
-pub use pt::*;
+mod pte;
 
 #[cfg(all(target_arch = "riscv64", target_os = "none"))]
 pub type PageTable = PageTableNative<SV39PageTableAttribute, RV64PageTableEntry>;
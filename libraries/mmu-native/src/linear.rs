@@ -0,0 +1,80 @@
+use abstractions::IUsizeAlias;
+use address::{PhysicalAddress, VirtualAddress};
+use alloc::sync::Arc;
+
+/// Translates physical addresses to the virtual addresses the page table accesses them
+/// through, e.g. the page table frames themselves.
+pub trait ILinearMapper: Send + Sync {
+    fn linear_vaddr(&self, paddr: PhysicalAddress) -> VirtualAddress;
+}
+
+/// The linear mapping set up by the platform.
+#[cfg(target_os = "none")]
+pub struct PlatformLinearMapper;
+
+#[cfg(target_os = "none")]
+impl ILinearMapper for PlatformLinearMapper {
+    fn linear_vaddr(&self, paddr: PhysicalAddress) -> VirtualAddress {
+        use address::IConvertablePhysicalAddress;
+
+        paddr.to_high_virtual()
+    }
+}
+
+/// A linear mapping starting at a fixed offset.
+///
+/// The offset wraps around, so mappings placed below the physical memory work as well.
+pub struct OffsetLinearMapper {
+    offset: usize,
+}
+
+impl OffsetLinearMapper {
+    pub const fn new(offset: usize) -> Self {
+        Self { offset }
+    }
+}
+
+impl ILinearMapper for OffsetLinearMapper {
+    fn linear_vaddr(&self, paddr: PhysicalAddress) -> VirtualAddress {
+        VirtualAddress::from_usize(paddr.as_usize().wrapping_add(self.offset))
+    }
+}
+
+/// The platform's linear mapping, or the identity mapping on hosts where physical memory is
+/// just host memory.
+pub(crate) fn default_linear_mapper() -> Arc<dyn ILinearMapper> {
+    #[cfg(target_os = "none")]
+    return Arc::new(PlatformLinearMapper);
+
+    #[cfg(not(target_os = "none"))]
+    return Arc::new(OffsetLinearMapper::new(0));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_high_half_offset() {
+        let mapper = OffsetLinearMapper::new(0xffff_ffc0_0000_0000);
+        let paddr = PhysicalAddress::from_usize(0x8020_1234);
+
+        assert_eq!(mapper.linear_vaddr(paddr).as_usize(), 0xffff_ffc0_8020_1234);
+    }
+
+    #[test]
+    fn test_zero_offset_is_identity() {
+        let mapper = OffsetLinearMapper::new(0);
+        let paddr = PhysicalAddress::from_usize(0x8020_1234);
+
+        assert_eq!(mapper.linear_vaddr(paddr).as_usize(), 0x8020_1234);
+    }
+
+    #[test]
+    fn test_offset_below_physical_memory() {
+        let mapper = OffsetLinearMapper::new(0usize.wrapping_sub(0x8000_0000));
+        let paddr = PhysicalAddress::from_usize(0x8020_1234);
+
+        assert_eq!(mapper.linear_vaddr(paddr).as_usize(), 0x20_1234);
+    }
+}
@@ -0,0 +1,170 @@
+//! Debug-only lock ordering checks for the locks taken by page tables.
+//!
+//! Locks must be acquired in the order of their [`LockClass`], so the frame allocator is
+//! always the innermost lock. Acquiring a lock while holding one of a later class panics
+//! instead of deadlocking some time later. The checks are enabled by the `lock-order`
+//! feature in debug builds, and the held locks are tracked per processor.
+
+use core::ops::{Deref, DerefMut};
+
+use hermit_sync::{SpinMutex, SpinMutexGuard};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum LockClass {
+    CrossMappings,
+    FrameAllocator,
+}
+
+/// A lock guard whose lock is tracked by the lock ordering checks.
+pub(crate) struct Ordered<G> {
+    guard: G,
+    class: LockClass,
+}
+
+impl<G> Drop for Ordered<G> {
+    fn drop(&mut self) {
+        tracker::release(self.class);
+    }
+}
+
+impl<G: Deref> Deref for Ordered<G> {
+    type Target = G::Target;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<G: DerefMut> DerefMut for Ordered<G> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+/// Locks `mutex` of the given class, checking the order against the locks already held.
+pub(crate) fn lock_ordered<T: ?Sized>(
+    mutex: &SpinMutex<T>,
+    class: LockClass,
+) -> Ordered<SpinMutexGuard<'_, T>> {
+    tracker::acquire(class);
+
+    Ordered {
+        guard: mutex.lock(),
+        class,
+    }
+}
+
+#[cfg(all(feature = "lock-order", debug_assertions))]
+mod tracker {
+    use super::LockClass;
+
+    const LOCK_CLASSES: usize = 2;
+
+    #[cfg(target_os = "none")]
+    fn with_held<R>(f: impl FnOnce(&mut [usize; LOCK_CLASSES]) -> R) -> R {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        const MAX_PROCESSORS: usize = 64;
+
+        static HELD: [[AtomicUsize; LOCK_CLASSES]; MAX_PROCESSORS] =
+            [const { [const { AtomicUsize::new(0) }; LOCK_CLASSES] }; MAX_PROCESSORS];
+
+        let processor = platform_specific::current_processor_index();
+        debug_assert!(processor < MAX_PROCESSORS);
+
+        let slots = &HELD[processor];
+
+        let mut held = [0; LOCK_CLASSES];
+        for (count, slot) in held.iter_mut().zip(slots) {
+            *count = slot.load(Ordering::Relaxed);
+        }
+
+        let ret = f(&mut held);
+
+        for (count, slot) in held.iter().zip(slots) {
+            slot.store(*count, Ordering::Relaxed);
+        }
+
+        ret
+    }
+
+    // Threads play the role of processors on host
+    #[cfg(not(target_os = "none"))]
+    fn with_held<R>(f: impl FnOnce(&mut [usize; LOCK_CLASSES]) -> R) -> R {
+        extern crate std;
+
+        std::thread_local! {
+            static HELD: core::cell::RefCell<[usize; LOCK_CLASSES]> =
+                const { core::cell::RefCell::new([0; LOCK_CLASSES]) };
+        }
+
+        HELD.with_borrow_mut(f)
+    }
+
+    pub(super) fn acquire(class: LockClass) {
+        let inverted = with_held(|held| {
+            let inverted = held[class as usize + 1..].iter().any(|count| *count != 0);
+
+            held[class as usize] += 1;
+
+            inverted
+        });
+
+        if inverted {
+            panic!("Lock order inversion: acquiring {class:?} while holding a later lock");
+        }
+    }
+
+    pub(super) fn release(class: LockClass) {
+        with_held(|held| held[class as usize] -= 1);
+    }
+}
+
+#[cfg(not(all(feature = "lock-order", debug_assertions)))]
+mod tracker {
+    use super::LockClass;
+
+    #[inline(always)]
+    pub(super) fn acquire(_class: LockClass) {}
+
+    #[inline(always)]
+    pub(super) fn release(_class: LockClass) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_in_order() {
+        let cross = SpinMutex::new(0);
+        let allocator = SpinMutex::new(0);
+
+        let mut cross = lock_ordered(&cross, LockClass::CrossMappings);
+        let mut allocator = lock_ordered(&allocator, LockClass::FrameAllocator);
+
+        *cross += 1;
+        *allocator += 1;
+    }
+
+    #[test]
+    fn test_lock_after_release() {
+        let cross = SpinMutex::new(());
+        let allocator = SpinMutex::new(());
+
+        drop(lock_ordered(&allocator, LockClass::FrameAllocator));
+
+        let _cross = lock_ordered(&cross, LockClass::CrossMappings);
+    }
+
+    #[test]
+    #[cfg(all(feature = "lock-order", debug_assertions))]
+    #[should_panic(expected = "Lock order inversion")]
+    fn test_lock_inversion() {
+        let cross = SpinMutex::new(());
+        let allocator = SpinMutex::new(());
+
+        let _allocator = lock_ordered(&allocator, LockClass::FrameAllocator);
+        let _cross = lock_ordered(&cross, LockClass::CrossMappings);
+    }
+}
@@ -1,19 +1,35 @@
 use core::{marker::PhantomData, ops::Deref};
 
-use crate::IArchPageTableEntry;
+use crate::{
+    linear::default_linear_mapper,
+    lock_order::{lock_ordered, LockClass},
+    IArchPageTableEntry, ILinearMapper, OffsetLinearMapper,
+};
 use abstractions::IUsizeAlias;
 use address::{
-    IAddressBase, IAlignableAddress, IConvertablePhysicalAddress, PhysicalAddress, VirtualAddress,
-    VirtualAddressRange,
+    IAddressBase, IAlignableAddress, PhysicalAddress, VirtualAddress, VirtualAddressRange,
+};
+use alloc::{
+    collections::{
+        btree_map::{BTreeMap, Entry},
+        btree_set::BTreeSet,
+    },
+    sync::Arc,
+    vec,
+    vec::Vec,
 };
-use alloc::{collections::btree_set::BTreeSet, sync::Arc, vec, vec::Vec};
 use allocation_abstractions::{FrameDesc, IFrameAllocator};
 use hermit_sync::SpinMutex;
-use mmu_abstractions::{GenericMappingFlags, MMUError, PageSize, PagingError, PagingResult, IMMU};
-use utilities::InvokeOnDrop;
+use mmu_abstractions::{
+    GenericMappingFlags, InspectOutcome, MMUError, MmuCapabilities, PageSize, PagingError,
+    PagingResult, IMMU,
+};
+use utilities::{extract_bits, InvokeOnDrop};
 
 pub trait IPageTableArchAttribute {
     const LEVELS: usize;
+    /// The highest level that can hold a leaf, 1 for 4K pages, 2 for 2M and 3 for 1G.
+    const MAX_LEAF_LEVEL: usize;
     const PA_MAX_BITS: usize;
     const VA_MAX_BITS: usize;
     const PA_MAX_ADDR: usize = (1 << Self::PA_MAX_BITS) - 1;
@@ -26,6 +42,8 @@ where
 {
     root: PhysicalAddress,
     allocation: Option<PageTableAllocation>,
+    buffers: SpinMutex<BTreeMap<VirtualAddress, MappedBuffer>>,
+    linear: Arc<dyn ILinearMapper>,
     _marker: PhantomData<(Arch, PTE)>,
 }
 
@@ -111,10 +129,34 @@ impl Ord for CrossMappingWindow {
     }
 }
 
+/// Bookkeeping of a buffer handed out by `map_buffer`/`map_buffer_mut`.
+///
+/// The native backend returns the user memory directly, so this only keeps
+/// overlapping requests consistent with other backends.
+struct MappedBuffer {
+    len: usize,
+    mutable: bool,
+    rc: usize,
+}
+
+impl MappedBuffer {
+    fn vaddr_range(&self, vaddr: VirtualAddress) -> VirtualAddressRange {
+        VirtualAddressRange::from_start_len(vaddr, self.len)
+    }
+}
+
 impl Drop for PageTableAllocation {
     fn drop(&mut self) {
+        // Live cross mappings may still reference the frames, so leak them instead of handing
+        // them out again
+        if cfg!(debug_assertions) && !self.cross_mappings.get_mut().windows.is_empty() {
+            core::mem::forget(core::mem::take(&mut self.frames));
+
+            panic!("Page table dropped with live cross mappings, call unmap_cross first");
+        }
+
         while let Some(frame) = self.frames.pop() {
-            self.allocator.lock().dealloc(frame);
+            lock_ordered(&self.allocator, LockClass::FrameAllocator).dealloc(frame);
         }
     }
 }
@@ -129,8 +171,35 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         size: PageSize,
         flags: GenericMappingFlags,
     ) -> PagingResult<()> {
-        if !target.is_page_aligned() {
-            return Err(PagingError::NotAligned);
+        // Only the sizes of the hardware page table levels can be mapped
+        if let PageSize::Custom(_) = size {
+            return Err(PagingError::NotAlignedSize);
+        }
+
+        if !vaddr.is_aligned(size.as_usize()) {
+            return Err(PagingError::NotAlignedVaddr);
+        }
+
+        if !target.is_aligned(size.as_usize()) {
+            return Err(PagingError::NotAlignedPaddr);
+        }
+
+        let level = match size {
+            PageSize::_4K => 1,
+            PageSize::_2M => 2,
+            _ => 3,
+        };
+
+        if level > Arch::MAX_LEAF_LEVEL {
+            return Err(PagingError::NotLeafCapable);
+        }
+
+        let huge = size != PageSize::_4K;
+        let page = PTE::new_page(target.page_down(), flags, huge);
+
+        // The walker would follow a huge entry that is not recognized as a leaf as a table
+        if huge && page.is_present() && !page.is_huge() {
+            return Err(PagingError::NotLeafCapable);
         }
 
         let entry = self.get_create_entry(vaddr, size)?;
@@ -138,7 +207,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             return Err(PagingError::AlreadyMapped);
         }
 
-        *entry = PTE::new_page(target.page_down(), flags, size != PageSize::_4K);
+        *entry = page;
         Ok(())
     }
 
@@ -149,7 +218,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         flags: GenericMappingFlags,
     ) -> PagingResult<PageSize> {
         if !new_target.is_page_aligned() {
-            return Err(PagingError::NotAligned);
+            return Err(PagingError::NotAlignedPaddr);
         }
 
         let (entry, size) = self.get_entry_mut(vaddr)?;
@@ -176,14 +245,15 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         &self,
         vaddr: VirtualAddress,
     ) -> PagingResult<(PhysicalAddress, GenericMappingFlags, PageSize)> {
-        let (entry, size) = self.get_entry(vaddr.page_down())?;
+        let (entry, size) = self.get_entry(vaddr)?;
 
         if entry.is_empty() {
             return Err(PagingError::NotMapped);
         }
 
-        let offset = vaddr.as_usize() & (size.as_usize() - 1);
-        Ok((entry.paddr() | offset, entry.flags(), size))
+        // The offset within the resolved page, which may be a huge page
+        let offset = vaddr.as_usize() - vaddr.align_down(size.as_usize()).as_usize();
+        Ok((entry.paddr() + offset, entry.flags(), size))
     }
 
     fn create_or_update_single(
@@ -206,11 +276,25 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         Ok(())
     }
 
-    fn platform_payload(&self) -> usize {
-        self.root.as_usize()
+    fn platform_payload(&self) -> Option<usize> {
+        Some(self.root.as_usize())
+    }
+
+    fn capabilities(&self) -> MmuCapabilities {
+        MmuCapabilities::all()
+    }
+
+    fn bound_alloc(&self) -> Option<Arc<SpinMutex<dyn IFrameAllocator>>> {
+        self.allocation
+            .as_ref()
+            .map(|allocation| allocation.allocator.clone())
     }
 
     fn read_bytes(&self, vaddr: VirtualAddress, buf: &mut [u8]) -> Result<(), MMUError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
         let mut bytes_read = 0;
         self.inspect_bytes_through_linear(vaddr, buf.len(), |src| {
             buf[bytes_read..bytes_read + src.len()].copy_from_slice(src);
@@ -221,6 +305,10 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
     }
 
     fn write_bytes(&self, vaddr: VirtualAddress, buf: &[u8]) -> Result<(), MMUError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
         let mut bytes_written = 0;
         self.inspect_bytes_through_linear(vaddr, buf.len(), |dst| {
             dst.copy_from_slice(&buf[bytes_written..bytes_written + dst.len()]);
@@ -235,14 +323,15 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         vaddr: VirtualAddress,
         len: usize,
         callback: &mut dyn FnMut(&[u8], usize) -> bool,
-    ) -> Result<(), MMUError> {
+    ) -> Result<InspectOutcome, MMUError> {
         ensure_vaddr_valid(vaddr)?;
 
         let mut checking_vaddr = vaddr;
         let mut remaining_len = len;
 
         loop {
-            let (paddr, flags, size) = self.query_virtual(checking_vaddr).map_err(|e| e.into())?;
+            let (paddr, flags, size) =
+                self.query_virtual(checking_vaddr).map_err(MMUError::from)?;
 
             ensure_permission(vaddr, flags, false)?;
 
@@ -255,13 +344,15 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             let slice = unsafe {
                 core::slice::from_raw_parts(
                     // query_virtual adds offset internally
-                    paddr.to_high_virtual().as_mut::<u8>(),
+                    self.linear_vaddr(paddr).as_mut::<u8>(),
                     avaliable_len,
                 )
             };
 
             if !callback(slice, avaliable_len) {
-                break;
+                return Ok(InspectOutcome::Stopped {
+                    bytes_processed: len - remaining_len + avaliable_len,
+                });
             }
 
             checking_vaddr += frame_remain_len;
@@ -272,7 +363,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             }
         }
 
-        Ok(())
+        Ok(InspectOutcome::Completed)
     }
 
     fn inspect_framed_mut_internal(
@@ -280,14 +371,15 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         vaddr: VirtualAddress,
         len: usize,
         callback: &mut dyn FnMut(&mut [u8], usize) -> bool,
-    ) -> Result<(), MMUError> {
+    ) -> Result<InspectOutcome, MMUError> {
         ensure_vaddr_valid(vaddr)?;
 
         let mut checking_vaddr = vaddr;
         let mut remaining_len = len;
 
         loop {
-            let (paddr, flags, size) = self.query_virtual(checking_vaddr).map_err(|e| e.into())?;
+            let (paddr, flags, size) =
+                self.query_virtual(checking_vaddr).map_err(MMUError::from)?;
 
             ensure_permission(vaddr, flags, true)?;
 
@@ -300,13 +392,15 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             let slice = unsafe {
                 core::slice::from_raw_parts_mut(
                     // query_virtual adds offset internally
-                    paddr.to_high_virtual().as_mut::<u8>(),
+                    self.linear_vaddr(paddr).as_mut::<u8>(),
                     avaliable_len,
                 )
             };
 
             if !callback(slice, avaliable_len) {
-                break;
+                return Ok(InspectOutcome::Stopped {
+                    bytes_processed: len - remaining_len + avaliable_len,
+                });
             }
 
             checking_vaddr += frame_remain_len;
@@ -317,7 +411,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             }
         }
 
-        Ok(())
+        Ok(InspectOutcome::Completed)
     }
 
     fn translate_phys(
@@ -325,13 +419,14 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         paddr: PhysicalAddress,
         len: usize,
     ) -> Result<&'static mut [u8], MMUError> {
-        let virt = paddr.to_high_virtual();
+        let virt = self.linear_vaddr(paddr);
 
         Ok(unsafe { core::slice::from_raw_parts_mut(virt.as_mut::<u8>(), len) })
     }
 
     fn map_buffer_internal(&self, vaddr: VirtualAddress, len: usize) -> Result<&'_ [u8], MMUError> {
         self.inspect_permission(vaddr, len, false)?;
+        self.track_buffer(vaddr, len, false)?;
 
         Ok(unsafe { core::slice::from_raw_parts(vaddr.as_ptr(), len) })
     }
@@ -343,11 +438,39 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         _force_mut: bool,
     ) -> Result<&'_ mut [u8], MMUError> {
         self.inspect_permission(vaddr, len, true)?;
+        self.track_buffer(vaddr, len, true)?;
 
         Ok(unsafe { core::slice::from_raw_parts_mut(vaddr.as_mut_ptr(), len) })
     }
 
-    fn unmap_buffer(&self, _vaddr: VirtualAddress) {}
+    fn unmap_buffer(&self, vaddr: VirtualAddress) {
+        let mut buffers = self.buffers.lock();
+
+        let base = buffers
+            .iter_mut()
+            .find(|(base, buffer)| buffer.vaddr_range(**base).contains(vaddr))
+            .and_then(|(base, buffer)| {
+                buffer.rc -= 1;
+                (buffer.rc == 0).then_some(*base)
+            });
+
+        if let Some(base) = base {
+            buffers.remove(&base);
+        }
+    }
+
+    fn force_unmap_buffer(&self, vaddr: VirtualAddress) {
+        let mut buffers = self.buffers.lock();
+
+        let base = buffers
+            .iter()
+            .find(|(base, buffer)| buffer.vaddr_range(**base).contains(vaddr))
+            .map(|(base, _)| *base);
+
+        if let Some(base) = base {
+            buffers.remove(&base);
+        }
+    }
 
     fn map_cross_internal<'a>(
         &'a mut self,
@@ -358,12 +481,8 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         const PERMISSION: GenericMappingFlags =
             GenericMappingFlags::Readable.union(GenericMappingFlags::Kernel);
 
-        let mut cross = self
-            .allocation
-            .as_ref()
-            .ok_or(MMUError::CanNotModify)?
-            .cross_mappings
-            .lock();
+        let allocation = self.allocation.as_ref().ok_or(MMUError::CanNotModify)?;
+        let mut cross = lock_ordered(&allocation.cross_mappings, LockClass::CrossMappings);
 
         let window = cross.alloc(len, false); // placeholder
         let window = InvokeOnDrop::transform(window, |w| {
@@ -378,7 +497,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         let mut phys = Vec::new();
 
         loop {
-            let (phy, permission, sz) = source.query_virtual(checking).map_err(|e| e.into())?;
+            let (phy, permission, sz) = source.query_virtual(checking).map_err(MMUError::from)?;
 
             ensure_permission(vaddr, permission, false)?;
             phys.push((phy, sz));
@@ -428,12 +547,8 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             .union(GenericMappingFlags::Writable)
             .union(GenericMappingFlags::Kernel);
 
-        let mut cross = self
-            .allocation
-            .as_ref()
-            .ok_or(MMUError::CanNotModify)?
-            .cross_mappings
-            .lock();
+        let allocation = self.allocation.as_ref().ok_or(MMUError::CanNotModify)?;
+        let mut cross = lock_ordered(&allocation.cross_mappings, LockClass::CrossMappings);
 
         let window = cross.alloc(len, true); // placeholder
         let window = InvokeOnDrop::transform(window, |w| {
@@ -448,7 +563,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         let mut phys = Vec::new();
 
         loop {
-            let (phy, permission, sz) = source.query_virtual(checking).map_err(|e| e.into())?;
+            let (phy, permission, sz) = source.query_virtual(checking).map_err(MMUError::from)?;
 
             ensure_permission(vaddr, permission, true)?;
             phys.push((phy, sz));
@@ -486,7 +601,8 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
     }
 
     fn unmap_cross(&mut self, _source: &dyn IMMU, vaddr: VirtualAddress) {
-        let mut cross = self.allocation.as_ref().unwrap().cross_mappings.lock();
+        let allocation = self.allocation.as_ref().unwrap();
+        let mut cross = lock_ordered(&allocation.cross_mappings, LockClass::CrossMappings);
 
         if let Some(window) = cross.remove(vaddr) {
             drop(cross);
@@ -494,11 +610,74 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             self.unmap_single(window.vaddr).ok();
         }
     }
+
+    /// Host memory is not physical memory the table can map, use the test MMU instead.
+    #[cfg(not(target_os = "none"))]
+    fn register_internal(
+        &mut self,
+        _vaddr: VirtualAddress,
+        _len: usize,
+        _mutable: bool,
+    ) -> Result<(), MMUError> {
+        Err(MMUError::InvalidAddress)
+    }
+
+    #[cfg(not(target_os = "none"))]
+    fn unregister_internal(&mut self, _vaddr: VirtualAddress) -> Result<(), MMUError> {
+        Err(MMUError::InvalidAddress)
+    }
 }
 
 impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static>
     PageTableNative<Arch, PTE>
 {
+    /// Records a buffer handed out by `map_buffer`/`map_buffer_mut`.
+    ///
+    /// Overlapping immutable requests share the existing record if it covers the whole
+    /// request. Any overlap involving a mutable buffer is rejected, so a mutable slice is
+    /// never aliased. Empty buffers alias nothing and are not recorded, as their guards
+    /// never unmap them.
+    fn track_buffer(
+        &self,
+        vaddr: VirtualAddress,
+        len: usize,
+        mutable: bool,
+    ) -> Result<(), MMUError> {
+        if len == 0 {
+            return Ok(());
+        }
+
+        let requested = VirtualAddressRange::from_start_len(vaddr, len);
+
+        let mut buffers = self.buffers.lock();
+
+        match buffers
+            .iter_mut()
+            .find(|(base, buffer)| buffer.vaddr_range(**base).intersects(requested))
+        {
+            Some((base, buffer)) => {
+                if !buffer.vaddr_range(*base).contains_range(requested) || mutable || buffer.mutable
+                {
+                    return Err(MMUError::Borrowed);
+                }
+
+                buffer.rc += 1;
+            }
+            None => match buffers.entry(vaddr) {
+                Entry::Vacant(entry) => {
+                    entry.insert(MappedBuffer {
+                        len,
+                        mutable,
+                        rc: 1,
+                    });
+                }
+                Entry::Occupied(_) => return Err(MMUError::Borrowed),
+            },
+        }
+
+        Ok(())
+    }
+
     fn inspect_permission(
         &self,
         vaddr: VirtualAddress,
@@ -511,7 +690,8 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         let mut remaining_len = len;
 
         loop {
-            let (paddr, flags, size) = self.query_virtual(checking_vaddr).map_err(|e| e.into())?;
+            let (paddr, flags, size) =
+                self.query_virtual(checking_vaddr).map_err(MMUError::from)?;
 
             ensure_permission(vaddr, flags, mutable)?;
 
@@ -543,7 +723,8 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
         let mut remaining_len = len;
 
         loop {
-            let (paddr, flags, size) = self.query_virtual(checking_vaddr).map_err(|e| e.into())?;
+            let (paddr, flags, size) =
+                self.query_virtual(checking_vaddr).map_err(MMUError::from)?;
 
             ensure_linear_permission(flags)?;
 
@@ -555,7 +736,7 @@ impl<Arch: IPageTableArchAttribute + 'static, PTE: IArchPageTableEntry + 'static
             {
                 let slice = unsafe {
                     core::slice::from_raw_parts_mut(
-                        paddr.to_high_virtual().as_mut::<u8>(),
+                        self.linear_vaddr(paddr).as_mut::<u8>(),
                         avaliable_len,
                     )
                 };
@@ -586,38 +767,49 @@ const fn ensure_vaddr_valid(vaddr: VirtualAddress) -> Result<(), MMUError> {
 }
 
 const fn ensure_linear_permission(flags: GenericMappingFlags) -> Result<(), MMUError> {
-    if !flags.contains(GenericMappingFlags::User) {
+    if !flags.contains_all(GenericMappingFlags::User) {
         return Err(MMUError::PrivilegeError);
     }
 
     Ok(())
 }
 
+const USER_READ: GenericMappingFlags =
+    GenericMappingFlags::User.union(GenericMappingFlags::Readable);
+const USER_READ_WRITE: GenericMappingFlags = USER_READ.union(GenericMappingFlags::Writable);
+
 const fn ensure_permission(
     vaddr: VirtualAddress,
     flags: GenericMappingFlags,
     mutable: bool,
 ) -> Result<(), MMUError> {
-    if !flags.contains(GenericMappingFlags::User) {
-        return Err(MMUError::PrivilegeError);
+    let required = match mutable {
+        true => USER_READ_WRITE,
+        false => USER_READ,
+    };
+
+    if flags.contains_all(required) {
+        return Ok(());
     }
 
-    if !flags.contains(GenericMappingFlags::Readable) {
-        return Err(MMUError::PageNotReadable { vaddr });
+    if !flags.contains_all(GenericMappingFlags::User) {
+        return Err(MMUError::PrivilegeError);
     }
 
-    if mutable && !flags.contains(GenericMappingFlags::Writable) {
-        return Err(MMUError::PageNotWritable { vaddr });
+    if !flags.contains_all(GenericMappingFlags::Readable) {
+        return Err(MMUError::PageNotReadable { vaddr });
     }
 
-    Ok(())
+    Err(MMUError::PageNotWritable { vaddr })
 }
 
 impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Arch, PTE> {
-    const fn from_borrowed(root: PhysicalAddress) -> Self {
+    fn from_borrowed(root: PhysicalAddress) -> Self {
         Self {
             root,
             allocation: None,
+            buffers: SpinMutex::new(BTreeMap::new()),
+            linear: default_linear_mapper(),
             _marker: PhantomData,
         }
     }
@@ -637,15 +829,25 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
                         VirtualAddress::null(), // FIXME
                     )),
                 }),
+                buffers: SpinMutex::new(BTreeMap::new()),
+                linear: default_linear_mapper(),
                 _marker: PhantomData,
             },
         }
     }
 
     pub fn alloc(allocator: Arc<SpinMutex<dyn IFrameAllocator>>) -> Self {
-        let frame = allocator.lock().alloc_frame().unwrap();
+        Self::try_alloc(allocator).expect("Out of memory while allocating a page table")
+    }
 
-        let mut pt = Self::from_borrowed(frame.0);
+    /// Allocates the root table of a new page table, failing with `OutOfMemory` instead of
+    /// panicking if no frame is available.
+    pub fn try_alloc(allocator: Arc<SpinMutex<dyn IFrameAllocator>>) -> PagingResult<Self> {
+        let frame = lock_ordered(&allocator, LockClass::FrameAllocator)
+            .alloc_frame()
+            .ok_or(PagingError::OutOfMemory)?;
+
+        let mut pt = Self::from_borrowed(frame.addr());
 
         pt.allocation = Some(PageTableAllocation {
             frames: vec![frame],
@@ -655,7 +857,123 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
             )),
         });
 
-        pt
+        Ok(pt)
+    }
+
+    /// Accesses physical memory through a linear mapping at `offset` instead of the platform's.
+    pub fn with_linear_offset(self, offset: usize) -> Self {
+        self.with_linear_mapper(Arc::new(OffsetLinearMapper::new(offset)))
+    }
+
+    /// Accesses physical memory through `linear` instead of the platform's linear mapping.
+    pub fn with_linear_mapper(mut self, linear: Arc<dyn ILinearMapper>) -> Self {
+        self.linear = linear;
+        self
+    }
+
+    fn linear_vaddr(&self, paddr: PhysicalAddress) -> VirtualAddress {
+        self.linear.linear_vaddr(paddr)
+    }
+
+    /// Calls `f` with the virtual address, physical address, flags and size of every page
+    /// mapped by this page table, in ascending order of the table indices.
+    pub fn walk(
+        &self,
+        mut f: impl FnMut(VirtualAddress, PhysicalAddress, GenericMappingFlags, PageSize),
+    ) {
+        if let Ok(root) = self.raw_table_of(self.root()) {
+            self.walk_table(root, Arch::LEVELS, 0, &mut f);
+        }
+    }
+
+    fn walk_table(
+        &self,
+        table: &[PTE],
+        level: usize,
+        base: usize,
+        f: &mut impl FnMut(VirtualAddress, PhysicalAddress, GenericMappingFlags, PageSize),
+    ) {
+        let shift = 12 + 9 * (level - 1);
+
+        for (idx, entry) in table.iter().enumerate() {
+            if entry.is_empty() {
+                continue;
+            }
+
+            let vaddr = base | (idx << shift);
+
+            if level == 1 || entry.is_huge() {
+                // Sign extend addresses in the higher half
+                let unused_bits = usize::BITS as usize - (12 + 9 * Arch::LEVELS);
+                let vaddr = (((vaddr << unused_bits) as isize) >> unused_bits) as usize;
+
+                f(
+                    VirtualAddress::from_usize(vaddr),
+                    entry.paddr(),
+                    entry.flags(),
+                    PageSize::from(1 << shift),
+                );
+            } else if let Ok(next) = self.raw_table_of(entry.paddr()) {
+                self.walk_table(next, level - 1, vaddr, f);
+            }
+        }
+    }
+
+    /// Unmaps every page in the user half of the address space and returns the tables
+    /// below it to the allocator, e.g. to replace the image of a process.
+    ///
+    /// Returns the number of pages unmapped. Root entries in the upper half, like the
+    /// kernel area installed by the platform, are kept along with the tables below them.
+    /// Fails with `CanNotModify` for borrowed tables or while cross mappings are live.
+    pub fn clear(&mut self) -> PagingResult<usize> {
+        let alloc = self.ensure_can_modify()?;
+
+        if !lock_ordered(&alloc.cross_mappings, LockClass::CrossMappings)
+            .windows
+            .is_empty()
+        {
+            return Err(PagingError::CanNotModify);
+        }
+
+        let user = &mut self.raw_table_of(self.root())?[..Self::NUM_ENTRIES / 2];
+
+        let mut count = 0;
+        self.walk_table(user, Arch::LEVELS, 0, &mut |_, _, _, _| count += 1);
+
+        let mut tables = BTreeSet::new();
+        self.collect_tables(user, Arch::LEVELS, &mut tables);
+
+        user.fill(PTE::empty());
+
+        let alloc = self.ensure_can_modify_mut()?;
+        let mut allocator = lock_ordered(&alloc.allocator, LockClass::FrameAllocator);
+
+        for frame in core::mem::take(&mut alloc.frames) {
+            match tables.contains(&frame.addr()) {
+                true => allocator.dealloc(frame),
+                false => alloc.frames.push(frame),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Collects the physical addresses of every table referenced by `table`, recursively.
+    fn collect_tables(&self, table: &[PTE], level: usize, tables: &mut BTreeSet<PhysicalAddress>) {
+        if level == 1 {
+            return;
+        }
+
+        for entry in table
+            .iter()
+            .filter(|entry| !entry.is_empty() && !entry.is_huge())
+        {
+            tables.insert(entry.paddr());
+
+            if let Ok(next) = self.raw_table_of(entry.paddr()) {
+                self.collect_tables(next, level - 1, tables);
+            }
+        }
     }
 
     const fn root(&self) -> PhysicalAddress {
@@ -713,14 +1031,14 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
 
     fn raw_table_of<'a>(&self, paddr: PhysicalAddress) -> PagingResult<&'a mut [PTE]> {
         if !paddr.is_page_aligned() {
-            return Err(PagingError::NotAligned);
+            return Err(PagingError::NotAlignedPaddr);
         }
 
         if paddr.is_null() {
             return Err(PagingError::NotMapped);
         }
 
-        let ptr = unsafe { paddr.to_high_virtual().as_mut_ptr() };
+        let ptr = unsafe { self.linear_vaddr(paddr).as_mut_ptr() };
         Ok(unsafe { core::slice::from_raw_parts_mut(ptr, Self::NUM_ENTRIES) })
     }
 
@@ -754,10 +1072,20 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
         }
     }
 
+    /// Looks up the entry mapping `vaddr` for modification.
+    ///
+    /// The existence of the mapping is checked first, so a missing mapping is reported as
+    /// such even for borrowed tables, which only fail with `CanNotModify` afterwards.
     fn get_entry_mut(&mut self, vaddr: VirtualAddress) -> PagingResult<(&mut PTE, PageSize)> {
+        let (entry, size) = unsafe { self.get_entry_internal(vaddr) }?;
+
+        if entry.is_empty() {
+            return Err(PagingError::NotMapped);
+        }
+
         let _ = self.ensure_can_modify()?;
 
-        unsafe { self.get_entry_internal(vaddr) }
+        Ok((entry, size))
     }
 
     fn get_create_entry(
@@ -767,7 +1095,7 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
     ) -> PagingResult<&mut PTE> {
         let _ = self.ensure_can_modify()?;
         if !vaddr.is_page_aligned() {
-            return Err(PagingError::NotAligned);
+            return Err(PagingError::NotAlignedVaddr);
         }
 
         let vaddr = vaddr.as_usize();
@@ -803,18 +1131,21 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
         let alloc = self.ensure_can_modify_mut()?;
 
         if entry.is_empty() {
-            let frame = alloc
-                .allocator
-                .lock()
+            let frame = lock_ordered(&alloc.allocator, LockClass::FrameAllocator)
                 .alloc_frame()
                 .ok_or(PagingError::OutOfMemory)?;
 
-            let paddr = frame.0;
+            let paddr = frame.addr();
 
             alloc.frames.push(frame);
+
+            // The frame may have been used before, stale bits must not show up as entries
+            let table = self.raw_table_of(paddr)?;
+            table.fill(PTE::empty());
+
             *entry = PTE::new_table(paddr);
 
-            self.raw_table_of(paddr)
+            Ok(table)
         } else {
             self.get_next_level(entry)
         }
@@ -827,21 +1158,827 @@ impl<Arch: IPageTableArchAttribute, PTE: IArchPageTableEntry> PageTableNative<Ar
     #[allow(unused)]
     #[inline(always)]
     const fn p4_index(vaddr: usize) -> usize {
-        (vaddr >> (12 + 27)) & (Self::NUM_ENTRIES - 1)
+        extract_bits(vaddr, 39, 48)
     }
 
     #[inline(always)]
     const fn p3_index(vaddr: usize) -> usize {
-        (vaddr >> (12 + 18)) & (Self::NUM_ENTRIES - 1)
+        extract_bits(vaddr, 30, 39)
     }
 
     #[inline(always)]
     const fn p2_index(vaddr: usize) -> usize {
-        (vaddr >> (12 + 9)) & (Self::NUM_ENTRIES - 1)
+        extract_bits(vaddr, 21, 30)
     }
 
     #[inline(always)]
     const fn p1_index(vaddr: usize) -> usize {
-        (vaddr >> 12) & (Self::NUM_ENTRIES - 1)
+        extract_bits(vaddr, 12, 21)
+    }
+}
+
+#[cfg(all(test, not(feature = "loongarch64")))]
+mod tests {
+    use alloc::{collections::BTreeSet, vec::Vec};
+    use allocation::FrameAllocator;
+    use test_utilities::fork::{assert_fork_isolated, fork, setup_fork_parent};
+
+    use crate::{IArchPageTableEntryBase, RV64PageTableEntry, SV39PageTableAttribute};
+
+    use super::*;
+
+    type TestPageTable = PageTableNative<SV39PageTableAttribute, RV64PageTableEntry>;
+
+    const MEMORY_BASE: usize = 0x8000_0000;
+    const MEMORY_SIZE: usize = 1024 * 1024;
+
+    const USER_RW: GenericMappingFlags = GenericMappingFlags::User
+        .union(GenericMappingFlags::Readable)
+        .union(GenericMappingFlags::Writable);
+
+    const KERNEL_RW: GenericMappingFlags = GenericMappingFlags::Kernel
+        .union(GenericMappingFlags::Readable)
+        .union(GenericMappingFlags::Writable);
+
+    /// Simulates the physical memory `[MEMORY_BASE, MEMORY_BASE + MEMORY_SIZE)` with host memory
+    struct TestPhysicalMemory {
+        _memory: Vec<u8>,
+        base: *mut u8,
+    }
+
+    unsafe impl Send for TestPhysicalMemory {}
+    unsafe impl Sync for TestPhysicalMemory {}
+
+    impl TestPhysicalMemory {
+        fn new() -> Self {
+            let mut memory = vec![0u8; MEMORY_SIZE + constants::PAGE_SIZE];
+
+            // Page tables must be page aligned
+            let base = unsafe {
+                let ptr = memory.as_mut_ptr();
+                ptr.add(ptr.align_offset(constants::PAGE_SIZE))
+            };
+
+            Self {
+                _memory: memory,
+                base,
+            }
+        }
+    }
+
+    impl ILinearMapper for TestPhysicalMemory {
+        fn linear_vaddr(&self, paddr: PhysicalAddress) -> VirtualAddress {
+            let offset = paddr.as_usize() - MEMORY_BASE;
+            assert!(
+                offset < MEMORY_SIZE,
+                "{paddr:?} is out of the physical memory"
+            );
+
+            VirtualAddress::from_ptr(unsafe { self.base.add(offset) })
+        }
+    }
+
+    fn setup() -> (Arc<SpinMutex<dyn IFrameAllocator>>, TestPageTable) {
+        let allocator: Arc<SpinMutex<dyn IFrameAllocator>> =
+            Arc::new(SpinMutex::new(FrameAllocator::new(
+                PhysicalAddress::from_usize(MEMORY_BASE + MEMORY_SIZE),
+                PhysicalAddress::from_usize(MEMORY_BASE),
+            )));
+
+        let pt = TestPageTable::alloc(allocator.clone())
+            .with_linear_mapper(Arc::new(TestPhysicalMemory::new()));
+
+        (allocator, pt)
+    }
+
+    #[test]
+    fn test_platform_payload_is_root() {
+        let (_alloc, pt) = setup();
+
+        assert_eq!(pt.platform_payload(), Some(pt.root().as_usize()));
+    }
+
+    #[test]
+    fn test_bound_alloc() {
+        let (alloc, pt) = setup();
+
+        assert!(Arc::ptr_eq(&pt.bound_alloc().unwrap(), &alloc));
+
+        let borrowed = TestPageTable::new(pt.root(), None);
+        assert!(borrowed.bound_alloc().is_none());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let (_alloc, pt) = setup();
+
+        assert!(pt
+            .capabilities()
+            .contains(MmuCapabilities::PLATFORM_PAYLOAD));
+        assert_eq!(pt.capabilities(), MmuCapabilities::all());
+    }
+
+    #[test]
+    fn test_map_query() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+
+        let (queried, flags, size) = pt.query_virtual(vaddr + 0x123).unwrap();
+
+        assert_eq!(queried, paddr + 0x123);
+        assert!(flags.contains_all(USER_RW));
+        assert_eq!(size, PageSize::_4K);
+
+        assert_eq!(
+            pt.query_virtual(vaddr + constants::PAGE_SIZE),
+            Err(PagingError::NotMapped)
+        );
+    }
+
+    #[test]
+    fn test_map_device() {
+        fn map_device<PTE: IArchPageTableEntry + 'static>(
+            flags: GenericMappingFlags,
+        ) -> GenericMappingFlags {
+            let (alloc, _) = setup();
+            let mut pt = PageTableNative::<SV39PageTableAttribute, PTE>::alloc(alloc)
+                .with_linear_mapper(Arc::new(TestPhysicalMemory::new()));
+
+            let vaddr = VirtualAddress::from_usize(0x1000_0000);
+            let paddr = PhysicalAddress::from_usize(0x1000_0000); // UART of QEMU virt
+
+            pt.map_single(vaddr, paddr, PageSize::_4K, flags).unwrap();
+
+            let (queried, queried_flags, _) = pt.query_virtual(vaddr).unwrap();
+            assert_eq!(queried, paddr);
+
+            queried_flags
+        }
+
+        let flags = KERNEL_RW | GenericMappingFlags::Device;
+
+        let queried = map_device::<RV64PageTableEntry<true>>(flags);
+        assert!(queried.contains_all(flags));
+
+        // Without Svpbmt, the memory type is left to the PMAs
+        let queried = map_device::<RV64PageTableEntry<false>>(flags);
+        assert!(queried.contains_all(KERNEL_RW));
+        assert!(!queried.contains_any(GenericMappingFlags::Device | GenericMappingFlags::Uncached));
+    }
+
+    #[test]
+    fn test_map_twice() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+
+        assert_eq!(
+            pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW),
+            Err(PagingError::AlreadyMapped)
+        );
+    }
+
+    #[test]
+    fn test_map_misaligned() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        assert_eq!(
+            pt.map_single(vaddr + 0x10, paddr, PageSize::_4K, USER_RW),
+            Err(PagingError::NotAlignedVaddr)
+        );
+        assert_eq!(
+            pt.map_single(vaddr, paddr + 0x10, PageSize::_4K, USER_RW),
+            Err(PagingError::NotAlignedPaddr)
+        );
+        assert_eq!(
+            pt.map_single(vaddr, paddr, PageSize::Custom(0x1800), USER_RW),
+            Err(PagingError::NotAlignedSize)
+        );
+    }
+
+    #[test]
+    fn test_map_huge_page_misaligned() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        // Aligned to 4K but not to 2M
+        assert_eq!(
+            pt.map_single(vaddr + 0x1000, paddr, PageSize::_2M, USER_RW),
+            Err(PagingError::NotAlignedVaddr)
+        );
+        assert_eq!(
+            pt.map_single(vaddr, paddr + 0x1000, PageSize::_2M, USER_RW),
+            Err(PagingError::NotAlignedPaddr)
+        );
+
+        pt.map_single(vaddr, paddr, PageSize::_2M, USER_RW).unwrap();
+    }
+
+    #[test]
+    fn test_map_1g_misaligned() {
+        let (_alloc, mut pt) = setup();
+
+        assert_eq!(
+            pt.map_single(
+                VirtualAddress::from_usize(0x4020_0000),
+                PhysicalAddress::from_usize(0x8000_0000),
+                PageSize::_1G,
+                USER_RW
+            ),
+            Err(PagingError::NotAlignedVaddr)
+        );
+        assert_eq!(
+            pt.map_single(
+                VirtualAddress::from_usize(0x4000_0000),
+                PhysicalAddress::from_usize(0x8020_0000),
+                PageSize::_1G,
+                USER_RW
+            ),
+            Err(PagingError::NotAlignedPaddr)
+        );
+    }
+
+    #[test]
+    fn test_map_huge_page_at_wrong_level() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x4000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        // A 4K page lives in a level 1 table below the level 2 entry
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+        assert_eq!(
+            pt.map_single(vaddr, paddr, PageSize::_2M, USER_RW),
+            Err(PagingError::AlreadyMapped)
+        );
+
+        // A 2M leaf at level 2 can not hold 4K pages
+        let vaddr = vaddr + PageSize::_2M.as_usize();
+        pt.map_single(vaddr, paddr, PageSize::_2M, USER_RW).unwrap();
+        assert_eq!(
+            pt.map_single(vaddr + 0x1000, paddr, PageSize::_4K, USER_RW),
+            Err(PagingError::MappedToHugePage)
+        );
+
+        // Without any of R, W and X, the entry would be taken as a table
+        assert_eq!(
+            pt.map_single(
+                vaddr + PageSize::_2M.as_usize(),
+                paddr,
+                PageSize::_2M,
+                GenericMappingFlags::User
+            ),
+            Err(PagingError::NotLeafCapable)
+        );
+    }
+
+    #[test]
+    fn test_map_unsupported_huge_page() {
+        struct NoGigaPages;
+
+        impl IPageTableArchAttribute for NoGigaPages {
+            const LEVELS: usize = 3;
+            const MAX_LEAF_LEVEL: usize = 2;
+            const PA_MAX_BITS: usize = 56;
+            const VA_MAX_BITS: usize = 39;
+        }
+
+        let (alloc, _) = setup();
+        let mut pt = PageTableNative::<NoGigaPages, RV64PageTableEntry>::alloc(alloc)
+            .with_linear_mapper(Arc::new(TestPhysicalMemory::new()));
+
+        let vaddr = VirtualAddress::from_usize(0x4000_0000);
+        let paddr = PhysicalAddress::from_usize(0x8000_0000);
+
+        assert_eq!(
+            pt.map_single(vaddr, paddr, PageSize::_1G, USER_RW),
+            Err(PagingError::NotLeafCapable)
+        );
+
+        pt.map_single(vaddr, paddr, PageSize::_2M, USER_RW).unwrap();
+    }
+
+    #[test]
+    fn test_unmap() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+
+        assert_eq!(pt.unmap_single(vaddr), Ok((paddr, PageSize::_4K)));
+        assert_eq!(pt.query_virtual(vaddr), Err(PagingError::NotMapped));
+        assert_eq!(pt.unmap_single(vaddr), Err(PagingError::NotMapped));
+    }
+
+    #[test]
+    fn test_read_write_through_linear() {
+        let (alloc, mut pt) = setup();
+
+        let frame = alloc.lock().alloc_frame().unwrap();
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        pt.map_single(vaddr, frame.addr(), PageSize::_4K, USER_RW)
+            .unwrap();
+
+        let mmu: &dyn IMMU = &pt;
+        mmu.export(vaddr + 0x10, 0xdead_beef_u64).unwrap();
+
+        assert_eq!(mmu.import::<u64>(vaddr + 0x10), Ok(0xdead_beef));
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_zero_length_transfer() {
+        let (_alloc, pt) = setup();
+
+        assert_eq!(pt.read_bytes(VirtualAddress::null(), &mut []), Ok(()));
+        assert_eq!(pt.write_bytes(VirtualAddress::null(), &[]), Ok(()));
+
+        // Unmapped addresses are never touched either
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        assert_eq!(pt.read_bytes(vaddr, &mut []), Ok(()));
+        assert_eq!(pt.write_bytes(vaddr, &[]), Ok(()));
+    }
+
+    #[test]
+    fn test_register_host_memory_fails() {
+        let (_alloc, mut pt) = setup();
+        let data = [0u8; 16];
+
+        let mmu: &mut dyn IMMU = &mut pt;
+
+        assert_eq!(mmu.register(&data, true), Err(MMUError::InvalidAddress));
+        assert_eq!(mmu.unregister(&data), Err(MMUError::InvalidAddress));
+    }
+
+    #[test]
+    fn test_overlapping_buffers_do_not_alias() {
+        let (alloc, mut pt) = setup();
+
+        let frame = alloc.lock().alloc_frame().unwrap();
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        pt.map_single(vaddr, frame.addr(), PageSize::_4K, USER_RW)
+            .unwrap();
+
+        let mmu: &dyn IMMU = &pt;
+
+        {
+            // Immutable buffers covered by an existing one share its record
+            let whole = mmu.map_buffer(vaddr, 32).unwrap();
+            let inner = mmu.map_buffer(vaddr + 8, 8).unwrap();
+
+            assert!(matches!(
+                mmu.map_buffer_mut(vaddr + 8, 8, false),
+                Err(MMUError::Borrowed)
+            ));
+            // Not covered by the existing record
+            assert!(matches!(
+                mmu.map_buffer(vaddr + 16, 32),
+                Err(MMUError::Borrowed)
+            ));
+
+            drop(inner);
+            drop(whole);
+        }
+
+        {
+            let _whole = mmu.map_buffer_mut(vaddr, 32, false).unwrap();
+
+            assert!(matches!(
+                mmu.map_buffer_mut(vaddr + 8, 8, false),
+                Err(MMUError::Borrowed)
+            ));
+            assert!(matches!(
+                mmu.map_buffer(vaddr + 8, 8),
+                Err(MMUError::Borrowed)
+            ));
+        }
+
+        assert!(pt.buffers.lock().is_empty());
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_empty_buffer_keeps_existing_record() {
+        let (alloc, mut pt) = setup();
+
+        let frame = alloc.lock().alloc_frame().unwrap();
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        pt.map_single(vaddr, frame.addr(), PageSize::_4K, USER_RW)
+            .unwrap();
+
+        let mmu: &dyn IMMU = &pt;
+
+        let whole = mmu.map_buffer_mut(vaddr, 32, false).unwrap();
+
+        // Neither recording nor dropping the empty buffer releases the mutable one
+        drop(mmu.map_buffer(vaddr, 0).unwrap());
+        drop(mmu.map_buffer_mut(vaddr, 0, false).unwrap());
+
+        assert!(matches!(
+            mmu.map_buffer_mut(vaddr, 32, false),
+            Err(MMUError::Borrowed)
+        ));
+        assert_eq!(pt.buffers.lock().len(), 1);
+
+        // Narrowing to nothing releases the buffer
+        drop(whole.subslice(8..8));
+        assert!(pt.buffers.lock().is_empty());
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_force_unmap_leaked_buffer() {
+        let (alloc, mut pt) = setup();
+
+        let frame = alloc.lock().alloc_frame().unwrap();
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        pt.map_single(vaddr, frame.addr(), PageSize::_4K, USER_RW)
+            .unwrap();
+
+        let mmu: &dyn IMMU = &pt;
+        core::mem::forget(mmu.map_buffer(vaddr, 16).unwrap());
+
+        assert!(matches!(
+            mmu.map_buffer_mut(vaddr, 16, false),
+            Err(MMUError::Borrowed)
+        ));
+
+        mmu.force_unmap_buffer(vaddr);
+
+        let memory = mmu.map_buffer_mut(vaddr, 16, false).unwrap();
+        assert_eq!(memory.len(), 16);
+        drop(memory);
+
+        assert!(pt.buffers.lock().is_empty());
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_inspect_framed_outcome() {
+        let (alloc, mut pt) = setup();
+
+        let frames = alloc.lock().alloc_frames(2).unwrap();
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        for (idx, frame) in frames.iter().enumerate() {
+            pt.map_single(
+                vaddr + idx * constants::PAGE_SIZE,
+                frame.addr(),
+                PageSize::_4K,
+                USER_RW,
+            )
+            .unwrap();
+        }
+
+        let mmu: &dyn IMMU = &pt;
+        let len = 2 * constants::PAGE_SIZE;
+
+        assert_eq!(
+            mmu.inspect_framed(vaddr, len, |_, _| false),
+            Ok(InspectOutcome::Stopped {
+                bytes_processed: constants::PAGE_SIZE
+            })
+        );
+        assert_eq!(
+            mmu.inspect_framed_mut(vaddr + 0x800, len, |_, _| false),
+            Ok(InspectOutcome::Stopped {
+                bytes_processed: 0x800
+            })
+        );
+        assert_eq!(
+            mmu.inspect_framed(vaddr, len, |_, _| true),
+            Ok(InspectOutcome::Completed)
+        );
+
+        for frame in frames {
+            alloc.lock().dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_query_huge_page_offsets() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x4000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9020_0000);
+
+        pt.map_single(vaddr, paddr, PageSize::_2M, USER_RW).unwrap();
+
+        for offset in [0, 0x123, 0x1000, 0x1234, 0x10_0000, 0x1f_ffff] {
+            let (queried, _, size) = pt.query_virtual(vaddr + offset).unwrap();
+
+            assert_eq!(queried, paddr + offset);
+            assert_eq!(size, PageSize::_2M);
+        }
+
+        assert_eq!(
+            pt.query_virtual(vaddr + PageSize::_2M.as_usize()),
+            Err(PagingError::NotMapped)
+        );
+    }
+
+    fn collect_mappings(pt: &TestPageTable) -> Vec<(VirtualAddress, PhysicalAddress, PageSize)> {
+        let mut mappings = Vec::new();
+        pt.walk(|vaddr, paddr, _, size| mappings.push((vaddr, paddr, size)));
+        mappings
+    }
+
+    #[test]
+    fn test_walk() {
+        let (_alloc, mut pt) = setup();
+
+        let mappings = [
+            (0x1000_0000, 0x9000_0000, PageSize::_4K),
+            (0x1000_1000, 0x9000_3000, PageSize::_4K),
+            (0x4000_0000, 0x9020_0000, PageSize::_2M),
+            (0x3f_ffff_f000, 0x9000_1000, PageSize::_4K),
+        ]
+        .map(|(vaddr, paddr, size)| {
+            (
+                VirtualAddress::from_usize(vaddr),
+                PhysicalAddress::from_usize(paddr),
+                size,
+            )
+        });
+
+        for (vaddr, paddr, size) in mappings {
+            pt.map_single(vaddr, paddr, size, USER_RW).unwrap();
+        }
+
+        assert_eq!(collect_mappings(&pt), mappings);
+    }
+
+    #[test]
+    fn test_walk_sign_extends_higher_half() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0xffff_ffc0_8000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+
+        assert_eq!(collect_mappings(&pt), [(vaddr, paddr, PageSize::_4K)]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let (alloc, mut pt) = setup();
+
+        for (vaddr, size) in [
+            (0x1000_0000, PageSize::_4K),
+            (0x1000_1000, PageSize::_4K),
+            (0x2000_0000, PageSize::_4K),
+            (0x4000_0000, PageSize::_2M),
+        ] {
+            pt.map_single(
+                VirtualAddress::from_usize(vaddr),
+                PhysicalAddress::from_usize(0x9000_0000),
+                size,
+                USER_RW,
+            )
+            .unwrap();
+        }
+
+        let root = pt.root();
+        let tables = pt
+            .allocation
+            .as_ref()
+            .unwrap()
+            .frames
+            .iter()
+            .map(|frame| frame.addr())
+            .filter(|&paddr| paddr != root)
+            .collect::<BTreeSet<_>>();
+
+        assert!(!tables.is_empty());
+        assert_eq!(pt.clear(), Ok(4));
+        assert!(collect_mappings(&pt).is_empty());
+
+        // Only the root is kept, every other table is handed out again
+        assert_eq!(pt.allocation.as_ref().unwrap().frames.len(), 1);
+
+        let mut alloc = alloc.lock();
+        let frames = alloc.alloc_frames(tables.len()).unwrap();
+        let recycled = frames.iter().map(|f| f.addr()).collect::<BTreeSet<_>>();
+
+        assert_eq!(recycled, tables);
+
+        for frame in frames {
+            alloc.dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_clear_keeps_kernel_area() {
+        let (_alloc, mut pt) = setup();
+
+        // The first entries of the upper half, like the platform's kernel area
+        let kernel_huge = VirtualAddress::from_usize(0xffff_ffc0_0000_0000);
+        let kernel_page = VirtualAddress::from_usize(0xffff_ffc0_4000_0000);
+        let user_page = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(kernel_huge, paddr, PageSize::_2M, KERNEL_RW)
+            .unwrap();
+        pt.map_single(kernel_page, paddr, PageSize::_4K, KERNEL_RW)
+            .unwrap();
+        pt.map_single(user_page, paddr, PageSize::_4K, USER_RW)
+            .unwrap();
+
+        let frames = pt.allocation.as_ref().unwrap().frames.len();
+
+        // Only the user page is counted
+        assert_eq!(pt.clear(), Ok(1));
+
+        assert_eq!(pt.query_virtual(user_page), Err(PagingError::NotMapped));
+        assert_eq!(pt.query_virtual(kernel_huge).unwrap().0, paddr);
+        assert_eq!(pt.query_virtual(kernel_page).unwrap().0, paddr);
+
+        // The two tables below the user entry are freed, the kernel ones are kept
+        assert_eq!(pt.allocation.as_ref().unwrap().frames.len(), frames - 2);
+    }
+
+    #[test]
+    fn test_clear_then_map() {
+        let (_alloc, mut pt) = setup();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+        pt.clear().unwrap();
+
+        assert_eq!(pt.query_virtual(vaddr), Err(PagingError::NotMapped));
+
+        pt.map_single(vaddr, paddr, PageSize::_4K, USER_RW).unwrap();
+
+        assert_eq!(pt.query_virtual(vaddr).unwrap().0, paddr);
+    }
+
+    #[test]
+    fn test_borrowed_table_errors() {
+        let (_alloc, mut pt) = setup();
+
+        let mapped = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+
+        pt.map_single(mapped, paddr, PageSize::_4K, USER_RW)
+            .unwrap();
+
+        let mut borrowed =
+            TestPageTable::new(pt.root(), None).with_linear_mapper(pt.linear.clone());
+
+        // Reading a borrowed table is fine
+        assert!(borrowed.query_virtual(mapped).is_ok());
+
+        // Existing mappings can not be modified
+        assert_eq!(
+            borrowed.unmap_single(mapped),
+            Err(PagingError::CanNotModify)
+        );
+        assert_eq!(
+            borrowed.remap_single(mapped, paddr, USER_RW),
+            Err(PagingError::CanNotModify)
+        );
+        assert_eq!(borrowed.clear(), Err(PagingError::CanNotModify));
+
+        // Missing mappings are reported as such, in an existing and a missing table
+        for unmapped in [
+            mapped + constants::PAGE_SIZE,
+            VirtualAddress::from_usize(0x2000_0000),
+        ] {
+            assert_eq!(borrowed.unmap_single(unmapped), Err(PagingError::NotMapped));
+            assert_eq!(
+                borrowed.remap_single(unmapped, paddr, USER_RW),
+                Err(PagingError::NotMapped)
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_alloc_out_of_memory() {
+        // No frame at all
+        let allocator: Arc<SpinMutex<dyn IFrameAllocator>> =
+            Arc::new(SpinMutex::new(FrameAllocator::new(
+                PhysicalAddress::from_usize(MEMORY_BASE),
+                PhysicalAddress::from_usize(MEMORY_BASE),
+            )));
+
+        assert!(matches!(
+            TestPageTable::try_alloc(allocator),
+            Err(PagingError::OutOfMemory)
+        ));
+    }
+
+    #[test]
+    fn test_try_alloc() {
+        let (alloc, _) = setup();
+
+        assert!(TestPageTable::try_alloc(alloc).is_ok());
+    }
+
+    #[test]
+    fn test_new_table_is_zeroed() {
+        let (alloc, mut pt) = setup();
+
+        // Leave garbage in a frame, so it is recycled for the next table
+        let frame = alloc.lock().alloc_frame().unwrap();
+        let dirty = frame.addr();
+
+        unsafe {
+            let ptr = pt.linear_vaddr(dirty).as_mut_ptr::<u8>();
+            core::ptr::write_bytes(ptr, 0xff, constants::PAGE_SIZE);
+        }
+
+        alloc.lock().dealloc(frame);
+
+        let mut entry = RV64PageTableEntry::empty();
+        let table = pt.get_create_next_level(&mut entry).unwrap();
+
+        assert_eq!(entry.paddr(), dirty);
+        assert_eq!(table.len(), 512);
+        assert!(table.iter().all(|entry| entry.is_empty()));
+    }
+
+    fn map_cross_page(pt: &mut TestPageTable, source: &mut TestPageTable) -> VirtualAddress {
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        source
+            .map_single(
+                vaddr,
+                PhysicalAddress::from_usize(0x9000_0000),
+                PageSize::_4K,
+                USER_RW,
+            )
+            .unwrap();
+
+        // The windows start at null by default, which can not back a slice
+        pt.allocation.as_mut().unwrap().cross_mappings = SpinMutex::new(
+            CrossMappingAllocator::new(VirtualAddress::from_usize(0x2000_0000)),
+        );
+
+        let window = pt
+            .map_cross_internal(source, vaddr, constants::PAGE_SIZE)
+            .unwrap();
+
+        VirtualAddress::from_ptr(window.as_ptr())
+    }
+
+    #[test]
+    fn test_drop_after_unmap_cross() {
+        let (alloc, mut pt) = setup();
+        let mut source = TestPageTable::alloc(alloc).with_linear_mapper(pt.linear.clone());
+
+        let window = map_cross_page(&mut pt, &mut source);
+        pt.unmap_cross(&source, window);
+
+        drop(pt);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "live cross mappings")]
+    fn test_drop_with_live_cross_mapping() {
+        let (alloc, mut pt) = setup();
+        let mut source = TestPageTable::alloc(alloc).with_linear_mapper(pt.linear.clone());
+
+        map_cross_page(&mut pt, &mut source);
+
+        drop(pt);
+    }
+
+    #[test]
+    fn test_fork_is_isolated() {
+        let (alloc, pt) = setup();
+        let linear = pt.linear.clone();
+
+        let child_pt = TestPageTable::alloc(alloc.clone()).with_linear_mapper(linear);
+
+        let parent = setup_fork_parent(Arc::new(SpinMutex::new(pt)), alloc);
+        let child = fork(&parent, Arc::new(SpinMutex::new(child_pt)));
+
+        assert_fork_isolated(&parent, &child);
     }
 }
@@ -28,6 +28,10 @@ pub trait IArchPageTableEntryBase:
 
     fn is_empty(&self) -> bool;
 
+    /// Creates an entry pointing to the next level table at `paddr`.
+    ///
+    /// The entry is never empty, so walks descend into it, and never a leaf: it is not huge
+    /// and its flags are contained in [`GenericMappingFlags::TABLE_POINTER`].
     fn new_table(paddr: PhysicalAddress) -> Self;
     fn paddr(&self) -> PhysicalAddress;
     fn flags(&self) -> GenericMappingFlags;
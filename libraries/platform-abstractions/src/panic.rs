@@ -22,7 +22,6 @@ unsafe fn _rust_begin_unwind(info: &::core::panic::PanicInfo) -> ! {
 #[cfg(target_os = "none")]
 unsafe extern "Rust" fn panic_handler(info: &::core::panic::PanicInfo) -> ! {
     use platform_specific::legacy_println;
-    use unwinding::StackTraceWalker;
 
     let nesting_depth = PANIC_NESTING_DEPTH.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
 
@@ -51,7 +50,10 @@ unsafe extern "Rust" fn panic_handler(info: &::core::panic::PanicInfo) -> ! {
 
         legacy_println!("[BAKA-OS]     Can unwind: {}", info.can_unwind());
 
+        #[cfg(feature = "backtrace")]
         if nesting_depth < 1 {
+            use unwinding::StackTraceWalker;
+
             let mut skip_frames = 2;
 
             if SKIP_PANIC_FRAME.load(core::sync::atomic::Ordering::Relaxed) {
@@ -592,7 +592,7 @@ macro_rules! impl_stream {
 
                 while cur < end {
                     let (_pa, flags, size) =
-                        self.source().query_virtual(cur).map_err(|e| e.into())?;
+                        self.source().query_virtual(cur).map_err(MMUError::from)?;
 
                     access = access.min(flags_to_access(flags));
 
@@ -0,0 +1,203 @@
+//! # Bit manipulation helpers
+//!
+//! Small `const fn` helpers for the shift and mask patterns that paging code keeps
+//! reimplementing, e.g. extracting page table indices from a virtual address.
+//!
+//! All bit ranges are half open, `lo` is inclusive and `hi` is exclusive, so `lo == hi`
+//! describes an empty range.
+//!
+//! ## Examples
+//!
+//! ```
+//! use utilities::{extract_bits, set_bits};
+//!
+//! // The level 2 index of a Sv39 virtual address
+//! let vaddr = 0x8020_1000usize;
+//! assert_eq!(extract_bits(vaddr, 21, 30), 0x001);
+//!
+//! assert_eq!(set_bits(0, 4, 8, 0xf), 0xf0);
+//! ```
+
+/// Returns a mask covering the bits in `[lo, hi)`.
+///
+/// # Examples
+///
+/// ```
+/// use utilities::bit_mask;
+///
+/// assert_eq!(bit_mask(4, 8), 0xf0);
+/// assert_eq!(bit_mask(0, usize::BITS), usize::MAX);
+/// assert_eq!(bit_mask(3, 3), 0);
+/// ```
+#[inline(always)]
+pub const fn bit_mask(lo: u32, hi: u32) -> usize {
+    debug_assert!(lo <= hi && hi <= usize::BITS);
+
+    let width = hi - lo;
+
+    if width == 0 {
+        return 0;
+    }
+
+    (usize::MAX >> (usize::BITS - width)) << lo
+}
+
+/// Extracts the bits in `[lo, hi)` of `value`, shifted down to bit 0.
+///
+/// # Examples
+///
+/// ```
+/// use utilities::extract_bits;
+///
+/// assert_eq!(extract_bits(0xabcd, 4, 12), 0xbc);
+/// assert_eq!(extract_bits(0xabcd, 8, 8), 0);
+/// ```
+#[inline(always)]
+pub const fn extract_bits(value: usize, lo: u32, hi: u32) -> usize {
+    (value & bit_mask(lo, hi)) >> lo
+}
+
+/// Replaces the bits in `[lo, hi)` of `value` with the low bits of `field`.
+///
+/// Bits of `field` that do not fit in the range are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use utilities::set_bits;
+///
+/// assert_eq!(set_bits(0xffff, 4, 8, 0x0), 0xff0f);
+/// assert_eq!(set_bits(0x0, 4, 8, 0x1ff), 0xf0);
+/// ```
+#[inline(always)]
+pub const fn set_bits(value: usize, lo: u32, hi: u32, field: usize) -> usize {
+    let mask = bit_mask(lo, hi);
+
+    if mask == 0 {
+        return value;
+    }
+
+    (value & !mask) | ((field << lo) & mask)
+}
+
+/// Rounds `value` down to a multiple of `align`, which must be a power of two.
+///
+/// # Examples
+///
+/// ```
+/// use utilities::align_down;
+///
+/// assert_eq!(align_down(0x1234, 0x1000), 0x1000);
+/// assert_eq!(align_down(0x2000, 0x1000), 0x2000);
+/// ```
+#[inline(always)]
+pub const fn align_down(value: usize, align: usize) -> usize {
+    debug_assert!(align.is_power_of_two());
+
+    value & !(align - 1)
+}
+
+/// Rounds `value` up to a multiple of `align`, which must be a power of two.
+///
+/// # Examples
+///
+/// ```
+/// use utilities::align_up;
+///
+/// assert_eq!(align_up(0x1234, 0x1000), 0x2000);
+/// assert_eq!(align_up(0x2000, 0x1000), 0x2000);
+/// ```
+#[inline(always)]
+pub const fn align_up(value: usize, align: usize) -> usize {
+    debug_assert!(align.is_power_of_two());
+
+    align_down(value + (align - 1), align)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_full_width() {
+        assert_eq!(bit_mask(0, usize::BITS), usize::MAX);
+    }
+
+    #[test]
+    fn test_mask_top_bit() {
+        assert_eq!(
+            bit_mask(usize::BITS - 1, usize::BITS),
+            1 << (usize::BITS - 1)
+        );
+    }
+
+    #[test]
+    fn test_mask_empty_range() {
+        assert_eq!(bit_mask(0, 0), 0);
+        assert_eq!(bit_mask(usize::BITS, usize::BITS), 0);
+    }
+
+    #[test]
+    fn test_extract_low_bits() {
+        assert_eq!(extract_bits(0xff, 0, 4), 0xf);
+    }
+
+    #[test]
+    fn test_extract_high_bits() {
+        assert_eq!(extract_bits(usize::MAX, usize::BITS - 4, usize::BITS), 0xf);
+    }
+
+    #[test]
+    fn test_extract_empty_range() {
+        assert_eq!(extract_bits(usize::MAX, 12, 12), 0);
+    }
+
+    #[test]
+    fn test_extract_page_table_indices() {
+        let vaddr = (0x1ab << 39) | (0x0cd << 30) | (0x1ef << 21) | (0x012 << 12) | 0x345;
+
+        assert_eq!(extract_bits(vaddr, 39, 48), 0x1ab);
+        assert_eq!(extract_bits(vaddr, 30, 39), 0x0cd);
+        assert_eq!(extract_bits(vaddr, 21, 30), 0x1ef);
+        assert_eq!(extract_bits(vaddr, 12, 21), 0x012);
+        assert_eq!(extract_bits(vaddr, 0, 12), 0x345);
+    }
+
+    #[test]
+    fn test_set_bits_keeps_other_bits() {
+        assert_eq!(set_bits(usize::MAX, 8, 16, 0), usize::MAX & !0xff00);
+    }
+
+    #[test]
+    fn test_set_bits_truncates_field() {
+        assert_eq!(set_bits(0, 0, 4, 0xff), 0xf);
+    }
+
+    #[test]
+    fn test_set_bits_full_width() {
+        assert_eq!(set_bits(0x1234, 0, usize::BITS, 0x5678), 0x5678);
+    }
+
+    #[test]
+    fn test_set_bits_empty_range() {
+        assert_eq!(set_bits(0x1234, 8, 8, 0xff), 0x1234);
+    }
+
+    #[test]
+    fn test_set_then_extract() {
+        let value = set_bits(0, 21, 30, 0x1ff);
+
+        assert_eq!(extract_bits(value, 21, 30), 0x1ff);
+        assert_eq!(extract_bits(value, 30, 39), 0);
+        assert_eq!(extract_bits(value, 12, 21), 0);
+    }
+
+    #[test]
+    fn test_align() {
+        assert_eq!(align_down(0, 0x1000), 0);
+        assert_eq!(align_up(0, 0x1000), 0);
+        assert_eq!(align_down(0x1fff, 0x1000), 0x1000);
+        assert_eq!(align_up(0x1001, 0x1000), 0x2000);
+        assert_eq!(align_up(0x1234, 1), 0x1234);
+    }
+}
@@ -7,6 +7,10 @@
 //! - **RAII Cleanup**: The [`InvokeOnDrop`] type provides automatic cleanup functionality
 //!   by invoking a closure when the value goes out of scope, ensuring proper resource
 //!   management in no_std environments.
+//! - **Pseudo Random Numbers**: The [`SplitMix64`] type generates reproducible pseudo random
+//!   data where crates like `rand` are unavailable.
+//! - **Bit Manipulation**: [`extract_bits`], [`set_bits`], [`align_down`] and [`align_up`]
+//!   share the shift and mask patterns used by paging code.
 //!
 //! ## no_std Support
 //!
@@ -28,6 +32,10 @@
 
 #![cfg_attr(not(test), no_std)]
 
+mod bits;
 mod invoke_on_drop;
+mod random;
 
+pub use bits::*;
 pub use invoke_on_drop::*;
+pub use random::*;
@@ -0,0 +1,176 @@
+//! # Deterministic pseudo random numbers
+//!
+//! This module provides [`SplitMix64`], a tiny pseudo random number generator that works
+//! in `no_std` environments where crates like `rand` are unavailable.
+//!
+//! The generator is fully determined by its seed, so the same seed always yields the same
+//! sequence. This makes it suitable for reproducible test data, but it must NOT be used
+//! for anything security related.
+//!
+//! ## Examples
+//!
+//! ```
+//! use utilities::SplitMix64;
+//!
+//! let mut rng = SplitMix64::new(42);
+//!
+//! let mut buffer = [0u8; 16];
+//! rng.fill_bytes(&mut buffer);
+//!
+//! // Same seed, same bytes
+//! let mut other = [0u8; 16];
+//! SplitMix64::new(42).fill_bytes(&mut other);
+//! assert_eq!(buffer, other);
+//! ```
+
+/// A SplitMix64 pseudo random number generator.
+///
+/// See <https://prng.di.unimi.it/splitmix64.c> for the reference implementation.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Creates a generator starting from the given seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use utilities::SplitMix64;
+    ///
+    /// let mut a = SplitMix64::new(7);
+    /// let mut b = SplitMix64::new(7);
+    /// assert_eq!(a.next_u64(), b.next_u64());
+    /// ```
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Restarts the sequence from the given seed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use utilities::SplitMix64;
+    ///
+    /// let mut rng = SplitMix64::new(1);
+    /// let first = rng.next_u64();
+    ///
+    /// rng.seed(1);
+    /// assert_eq!(rng.next_u64(), first);
+    /// ```
+    pub const fn seed(&mut self, seed: u64) {
+        self.state = seed;
+    }
+
+    /// Returns the next pseudo random 64 bits value of the sequence.
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Fills the buffer with pseudo random bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use utilities::SplitMix64;
+    ///
+    /// let mut buffer = [0u8; 13];
+    /// SplitMix64::new(3).fill_bytes(&mut buffer);
+    /// assert!(buffer.iter().any(|&b| b != 0));
+    /// ```
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+        let mut chunks = buf.chunks_exact_mut(8);
+
+        for chunk in chunks.by_ref() {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes());
+        }
+
+        let remainder = chunks.into_remainder();
+
+        if !remainder.is_empty() {
+            let bytes = self.next_u64().to_le_bytes();
+            remainder.copy_from_slice(&bytes[..remainder.len()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = SplitMix64::new(0xdead_beef);
+        let mut b = SplitMix64::new(0xdead_beef);
+
+        for _ in 0..1000 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn test_different_seed_different_sequence() {
+        let mut a = SplitMix64::new(1);
+        let mut b = SplitMix64::new(2);
+
+        assert_ne!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn test_reference_values() {
+        // Values from the reference implementation seeded with 0
+        let mut rng = SplitMix64::new(0);
+
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+    }
+
+    #[test]
+    fn test_reseed() {
+        let mut rng = SplitMix64::new(5);
+        let first = rng.next_u64();
+        rng.next_u64();
+
+        rng.seed(5);
+
+        assert_eq!(rng.next_u64(), first);
+    }
+
+    #[test]
+    fn test_fill_bytes_unaligned_length() {
+        let mut a = [0u8; 21];
+        let mut b = [0u8; 21];
+
+        SplitMix64::new(9).fill_bytes(&mut a);
+        SplitMix64::new(9).fill_bytes(&mut b);
+
+        assert_eq!(a, b);
+        assert!(a[16..].iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    fn test_byte_distribution() {
+        const LEN: usize = 256 * 1024;
+
+        let mut buffer = std::vec![0u8; LEN];
+        SplitMix64::new(42).fill_bytes(&mut buffer);
+
+        let mut histogram = [0usize; 256];
+        for byte in buffer {
+            histogram[byte as usize] += 1;
+        }
+
+        // Every byte value is expected about 1024 times
+        let expected = LEN / 256;
+        for count in histogram {
+            assert!(count > expected * 3 / 4 && count < expected * 5 / 4);
+        }
+    }
+}
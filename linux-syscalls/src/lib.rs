@@ -21,16 +21,26 @@ pub mod sys_write;
 pub type SyscallResult = Result<isize, ErrNo>;
 
 pub trait ISyscallResult {
+    /// The value returned to user space, `-errno` on failure, as the Linux syscall ABI does.
+    ///
+    /// This is the only place errors are converted, e.g. `MAP_FAILED` of `mmap` is never
+    /// returned directly, libc turns any value in `[-4095, -1]` into it and sets `errno`.
+    fn into_abi(self) -> isize;
+
     fn as_usize(&self) -> usize;
 }
 
 impl ISyscallResult for SyscallResult {
-    fn as_usize(&self) -> usize {
-        match *self {
-            Ok(v) => v as usize,
-            Err(e) => e as usize,
+    fn into_abi(self) -> isize {
+        match self {
+            Ok(v) => v,
+            Err(e) => e as isize,
         }
     }
+
+    fn as_usize(&self) -> usize {
+        self.into_abi() as usize
+    }
 }
 
 pub struct SyscallContext {
@@ -70,3 +80,69 @@ macro_rules! syscall_internal {
         $ctx.$name($p.arg0(), $p.arg1(), $p.arg2(), $p.arg3(), $p.arg4(), $p.arg5())
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use constants::SyscallError;
+
+    use super::*;
+
+    #[test]
+    fn test_into_abi_success() {
+        assert_eq!(SyscallError::Success.into_abi(), 0);
+        assert_eq!(SyscallResult::Ok(0x1000_0000).into_abi(), 0x1000_0000);
+    }
+
+    #[test]
+    fn test_into_abi_errors() {
+        let cases = [
+            (SyscallError::OperationNotPermitted, -1),
+            (SyscallError::NoSuchFileOrDirectory, -2),
+            (SyscallError::BadFileDescriptor, -9),
+            (SyscallError::ResourceTemporarilyUnavailable, -11),
+            (SyscallError::CannotAllocateMemory, -12),
+            (SyscallError::PermissionDenied, -13),
+            (SyscallError::BadAddress, -14),
+            (SyscallError::FileExists, -17),
+            (SyscallError::InvalidArgument, -22),
+            (SyscallError::FunctionNotImplemented, -38),
+            (SyscallError::MemoryPageHasHardwareError, -133),
+        ];
+
+        for (result, errno) in cases {
+            assert_eq!(result.into_abi(), errno, "{result:?}");
+        }
+    }
+
+    #[test]
+    fn test_errors_are_in_errno_range() {
+        // libc only treats [-4095, -1] as errors
+        for errno in [
+            ErrNo::OperationNotPermitted,
+            ErrNo::MemoryPageHasHardwareError,
+        ] {
+            let ret = SyscallResult::Err(errno).into_abi();
+
+            assert!((-4095..=-1).contains(&ret));
+        }
+    }
+
+    #[test]
+    fn test_into_abi_matches_errno() {
+        // Consistent with what user space sees
+        for err in [
+            ErrNo::OperationNotPermitted,
+            ErrNo::UnknownError41,
+            ErrNo::OperationNotSupported,
+            ErrNo::MemoryPageHasHardwareError,
+        ] {
+            assert_eq!(SyscallResult::Err(err).into_abi(), -(err.errno() as isize));
+        }
+    }
+
+    #[test]
+    fn test_as_usize_matches_into_abi() {
+        assert_eq!(SyscallError::InvalidArgument.as_usize(), -22isize as usize);
+        assert_eq!(SyscallResult::Ok(42).as_usize(), 42);
+    }
+}
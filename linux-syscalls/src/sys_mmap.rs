@@ -2,8 +2,9 @@ use abstractions::IUsizeAlias;
 use address::{
     IAddressBase, IAlignableAddress, IPageNum, IToPageNum, VirtualAddress, VirtualPageNumRange,
 };
+use address_v2::{VirtAddr, VirtAddrRange};
 use alloc::vec::Vec;
-use constants::SyscallError;
+use constants::{ErrNo, SyscallError};
 use memory_space::{AreaType, MapType, MappingArea, MemorySpace};
 use mmap_abstractions::{MemoryMapFlags, MemoryMapProt};
 use mmu_abstractions::GenericMappingFlags;
@@ -15,7 +16,14 @@ impl SyscallContext {
     const VMA_MIN_ADDR: VirtualAddress = VirtualAddress::from_usize(0x1000);
     const VMA_BASE: VirtualAddress = VirtualAddress::from_usize(0x10000000);
     const VMA_GAP: usize = constants::PAGE_SIZE;
+    // The user half of Sv39, which is also valid for the larger user half of LoongArch
+    const VMA_WINDOW: VirtAddrRange = VirtAddrRange::new(
+        VirtAddr::new(Self::VMA_MIN_ADDR.as_usize()),
+        VirtAddr::new(1 << 38),
+    );
 
+    /// Failures reach user space as `-errno` rather than `MAP_FAILED`, see
+    /// [`ISyscallResult::into_abi`](crate::ISyscallResult::into_abi).
     pub fn sys_mmap(
         &self,
         addr: VirtualAddress,
@@ -26,7 +34,10 @@ impl SyscallContext {
         fd: usize,
         offset: usize,
     ) -> SyscallResult {
-        if !addr.is_page_aligned() || (!addr.is_null() && addr < Self::VMA_MIN_ADDR) {
+        let fixed = flags.contains(MemoryMapFlags::FIXED);
+
+        // A fixed mapping can not be placed at null
+        if !addr.is_page_aligned() || ((!addr.is_null() || fixed) && addr < Self::VMA_MIN_ADDR) {
             return SyscallError::BadAddress;
         }
 
@@ -36,30 +47,72 @@ impl SyscallContext {
 
         // man page says:
         // The address addr must be a multiple of the page size (but length need not be).
-        let len = len.div_ceil(constants::PAGE_SIZE) * constants::PAGE_SIZE;
+        let Some(len) = len.checked_next_multiple_of(constants::PAGE_SIZE) else {
+            return SyscallError::CannotAllocateMemory;
+        };
 
         if len > Self::VMA_MAX_LEN {
             return SyscallError::CannotAllocateMemory;
         }
 
+        if !addr.is_null() {
+            let start = VirtAddr::from(addr);
+            let requested = VirtAddrRange::new(start, start.saturating_add(len));
+
+            // The whole range must be in the user half, not just its start
+            if requested.clamp_to(Self::VMA_WINDOW) != Some(requested) {
+                return SyscallError::CannotAllocateMemory;
+            }
+        }
+
         if !offset.is_multiple_of(constants::PAGE_SIZE) {
             return SyscallError::InvalidArgument;
         }
 
         let permissions = Self::prot_to_permissions(prot);
 
+        Self::validate_flags(flags)?;
+
+        // Anonymous mappings are private unless told otherwise
+        let mut flags = flags;
+        if !flags.contains(MemoryMapFlags::SHARED) {
+            flags |= MemoryMapFlags::PRIVATE;
+        }
+
+        // STACK and NORESERVE are hints that don't change how we allocate the area
+        let flags = flags
+            .difference(MemoryMapFlags::STACK | MemoryMapFlags::NORESERVE | MemoryMapFlags::FIXED);
+
         match flags {
-            MemoryMapFlags::ANONYMOUS => self.sys_mmap_anonymous(addr, len, permissions, offset),
+            MemoryMapFlags::PRIVATE => {
+                self.sys_mmap_anonymous(addr, len, permissions, offset, fixed)
+            }
             _ => SyscallError::InvalidArgument, // not implemented
         }
     }
 
+    /// Checks that `flags` only contains known bits and at most one of `SHARED` and `PRIVATE`.
+    ///
+    /// Having neither of them is accepted, the mapping is then treated as `PRIVATE`.
+    fn validate_flags(flags: MemoryMapFlags) -> Result<(), ErrNo> {
+        if flags.bits() & !MemoryMapFlags::all().bits() != 0 {
+            return Err(ErrNo::InvalidArgument);
+        }
+
+        if flags.contains(MemoryMapFlags::SHARED | MemoryMapFlags::PRIVATE) {
+            return Err(ErrNo::InvalidArgument);
+        }
+
+        Ok(())
+    }
+
     fn sys_mmap_anonymous(
         &self,
         mut addr: VirtualAddress,
         len: usize,
         permissions: GenericMappingFlags,
         offset: usize,
+        fixed: bool,
     ) -> SyscallResult {
         // ensure offset is valid
         // some implementations require fd to be -1 for anonymous mapping, but we don't
@@ -71,7 +124,9 @@ impl SyscallContext {
 
         let mut mem = process.memory_space().lock();
 
-        addr = Self::sys_mmap_select_addr(&mut mem, addr, len);
+        if !fixed {
+            addr = Self::sys_mmap_select_addr(&mut mem, addr, len);
+        }
 
         // No avaliable address
         if addr.is_null() {
@@ -81,13 +136,23 @@ impl SyscallContext {
         let start = addr.to_floor_page_num();
         let end = (addr + len).to_ceil_page_num();
 
-        mem.alloc_and_map_area(MappingArea {
-            range: VirtualPageNumRange::from_start_end(start, end),
-            area_type: AreaType::VMA,
-            map_type: MapType::Framed,
-            permissions,
-            allocation: None,
-        });
+        // A fixed mapping replaces whatever is mapped there, including its permissions
+        if fixed {
+            mem.unmap_range(VirtualPageNumRange::from_start_end(start, end));
+        }
+
+        if mem
+            .alloc_and_map_area(MappingArea {
+                range: VirtualPageNumRange::from_start_end(start, end),
+                area_type: AreaType::VMA,
+                map_type: MapType::Framed,
+                permissions,
+                allocation: None,
+            })
+            .is_err()
+        {
+            return SyscallError::CannotAllocateMemory;
+        }
 
         Ok(addr.as_usize() as isize)
     }
@@ -99,12 +164,25 @@ impl SyscallContext {
     ) -> VirtualAddress {
         debug_assert!(len.is_multiple_of(constants::PAGE_SIZE));
 
-        let mut mappings = mem.mappings().iter().collect::<Vec<_>>();
-        mappings.sort_by_key(|lhs| lhs.range().end());
+        let mappings = mem.iter_areas().collect::<Vec<_>>();
+
+        // Honor the hint as is if nothing occupies it, only relocate on collision
+        if !addr.is_null() {
+            let hinted = VirtualPageNumRange::from_start_count(
+                addr.to_floor_page_num(),
+                len / constants::PAGE_SIZE,
+            );
+
+            if mappings.iter().all(|mapping| {
+                let range = mapping.range();
+                range.end() <= hinted.start() || hinted.end() <= range.start()
+            }) {
+                return addr;
+            }
+        }
 
         // Try find the first avaliable hole
         let mut last_hole_start = match (addr.is_null(), mappings.len()) {
-            (false, 0) => return addr,
             (true, 0) => return Self::VMA_BASE,
             // We start from a mapping's end to avoid overlap with it
             (true, _) => mappings[0].range().end().end_addr() + Self::VMA_GAP,
@@ -155,17 +233,22 @@ impl SyscallContext {
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{collections::BTreeSet, sync::Arc};
 
-    use address::{VirtualAddress, VirtualPageNum};
+    use address::{PhysicalAddress, VirtualAddress, VirtualPageNum};
     use allocation_abstractions::IFrameAllocator;
     use hermit_sync::SpinMutex;
     use kernel_abstractions::IKernel;
+    use linux_loader::{LinuxLoader, ProcessContext};
     use memory_space::{MappingAreaAllocation, MemorySpace};
     use mmap_abstractions::MemoryMapProt;
-    use mmu_abstractions::IMMU;
+    use mmu_abstractions::{MMUError, PageSize, PagingError, IMMU};
     use test_utilities::{
-        allocation::contiguous::TestFrameAllocator, kernel::TestKernel, task::TestProcess,
+        allocation::contiguous::TestFrameAllocator,
+        elf::{TestElfBuilder, PF_R, PF_W, PF_X},
+        kernel::TestKernel,
+        memory::MemorySpaceBuilder,
+        task::TestProcess,
     };
 
     use super::*;
@@ -179,7 +262,11 @@ mod tests {
     fn setup_kernel_with_memory() -> KernelSetup {
         const MEMORY_RANGE: usize = 1024 * 1024 * 1024; // 1 GB
 
-        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(MEMORY_RANGE);
+        setup_kernel_with_memory_size(MEMORY_RANGE)
+    }
+
+    fn setup_kernel_with_memory_size(memory_size: usize) -> KernelSetup {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(memory_size);
 
         let kernel = TestKernel::new()
             .with_allocator(Some(alloc.clone()))
@@ -189,16 +276,26 @@ mod tests {
     }
 
     fn setup_memory_space() -> MemorySpace {
-        let (_, alloc, mmu) = setup_kernel_with_memory();
+        let (memory_space, _) = MemorySpaceBuilder::new().build();
 
-        MemorySpace::new(mmu, alloc)
+        memory_space
     }
 
     fn setup_syscall_context() -> SyscallContext {
         let (kernel, alloc, mmu) = setup_kernel_with_memory();
 
+        setup_syscall_context_with(kernel, alloc, mmu)
+    }
+
+    fn setup_syscall_context_with(
+        kernel: Arc<dyn IKernel>,
+        alloc: Arc<SpinMutex<dyn IFrameAllocator>>,
+        mmu: Arc<SpinMutex<dyn IMMU>>,
+    ) -> SyscallContext {
+        let (memory_space, _) = MemorySpaceBuilder::new().with_mmu(alloc, mmu).build();
+
         let (_, task) = TestProcess::new()
-            .with_memory_space(Some(MemorySpace::new(mmu, alloc)))
+            .with_memory_space(Some(memory_space))
             .build();
 
         SyscallContext::new(task, kernel)
@@ -272,7 +369,8 @@ mod tests {
             map_type: MapType::Framed,
             permissions: GenericMappingFlags::User,
             allocation: None,
-        });
+        })
+        .unwrap();
 
         mem.alloc_and_map_area(MappingArea {
             range: second,
@@ -280,7 +378,8 @@ mod tests {
             map_type: MapType::Framed,
             permissions: GenericMappingFlags::User,
             allocation: None,
-        });
+        })
+        .unwrap();
 
         let addr = SyscallContext::sys_mmap_select_addr(&mut mem, VirtualAddress::null(), 0x1000);
 
@@ -319,6 +418,368 @@ mod tests {
         assert!(addr > end_page.end_addr());
     }
 
+    #[test]
+    fn test_addr_specified_free_hint_honored() {
+        let mut mem = setup_memory_space();
+
+        let existing = VirtualPageNumRange::from_start_count(VirtualPageNum::from_usize(0x10), 1);
+
+        mem.alloc_and_map_area(MappingArea {
+            range: existing,
+            area_type: AreaType::VMA,
+            map_type: MapType::Framed,
+            permissions: GenericMappingFlags::User,
+            allocation: None,
+        })
+        .unwrap();
+
+        // The hint is below the existing mapping, with no hole search needed
+        let hint = VirtualAddress::from_usize(0x4000);
+        let addr = SyscallContext::sys_mmap_select_addr(&mut mem, hint, 0x2000);
+        assert_eq!(addr, hint);
+
+        // Right after the existing mapping, touching it but not overlapping
+        let hint = existing.end().start_addr();
+        let addr = SyscallContext::sys_mmap_select_addr(&mut mem, hint, 0x2000);
+        assert_eq!(addr, hint);
+    }
+
+    #[test]
+    fn test_iter_areas_sorted() {
+        let mut mem = setup_memory_space();
+
+        for start in [0x30, 0x10, 0x20] {
+            mem.map_area(MappingArea {
+                range: VirtualPageNumRange::from_start_count(VirtualPageNum::from_usize(start), 1),
+                area_type: AreaType::VMA,
+                map_type: MapType::Framed,
+                permissions: GenericMappingFlags::User,
+                allocation: Some(MappingAreaAllocation::empty(mem.allocator().clone())),
+            });
+        }
+
+        assert_eq!(mem.area_count(), 3);
+
+        let starts = mem
+            .iter_areas()
+            .map(|area| area.range().start().as_usize())
+            .collect::<Vec<_>>();
+
+        assert_eq!(starts, [0x10, 0x20, 0x30]);
+    }
+
+    #[test]
+    fn test_memory_space_with_bound_allocator() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+
+        let memory_space = MemorySpace::new(mmu, alloc.clone());
+
+        assert!(Arc::ptr_eq(memory_space.allocator(), &alloc));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "bound to another allocator")]
+    fn test_memory_space_with_mismatched_allocator() {
+        let (_, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let (other, _) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+
+        MemorySpace::new(mmu, other);
+    }
+
+    #[test]
+    fn test_backing_frames() {
+        let ctx = setup_syscall_context();
+
+        let addr = ctx
+            .sys_mmap(
+                VirtualAddress::null(),
+                0x3000,
+                MemoryMapProt::READ,
+                MemoryMapFlags::ANONYMOUS,
+                0,
+                0,
+            )
+            .unwrap();
+        let vpn = VirtualAddress::from_usize(addr as usize).to_floor_page_num();
+
+        let process = ctx.task.process();
+        let mem = process.memory_space().lock();
+        let area = mem.iter_areas().find(|area| area.contains(vpn)).unwrap();
+
+        assert_eq!(area.backing_frames().count(), area.range().page_count());
+    }
+
+    #[test]
+    fn test_frame_for_distinct_frames() {
+        let ctx = setup_syscall_context();
+
+        let addr = ctx
+            .sys_mmap(
+                VirtualAddress::null(),
+                0x4000,
+                MemoryMapProt::READ,
+                MemoryMapFlags::ANONYMOUS,
+                0,
+                0,
+            )
+            .unwrap();
+        let vpn = VirtualAddress::from_usize(addr as usize).to_floor_page_num();
+
+        let process = ctx.task.process();
+        let mem = process.memory_space().lock();
+        let area = mem.iter_areas().find(|area| area.contains(vpn)).unwrap();
+        let allocation = area.allocation.as_ref().unwrap();
+
+        let frames = area
+            .range()
+            .iter()
+            .map(|vpn| allocation.frame_for(vpn).unwrap())
+            .collect::<BTreeSet<_>>();
+
+        assert_eq!(frames.len(), area.range().page_count());
+        assert!(allocation.frame_for(area.range().end()).is_none());
+    }
+
+    #[test]
+    fn test_backing_frames_reserved() {
+        let ctx = setup_syscall_context();
+
+        reserve_at_vma_base(&ctx, 2);
+
+        let process = ctx.task.process();
+        let mem = process.memory_space().lock();
+
+        assert_eq!(mem.iter_areas().next().unwrap().backing_frames().count(), 0);
+    }
+
+    fn map_twice(mmu: &Arc<SpinMutex<dyn IMMU>>) -> SyscallResult {
+        let vaddr = SyscallContext::VMA_BASE;
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+        let flags = GenericMappingFlags::User | GenericMappingFlags::Readable;
+
+        let mut mmu = mmu.lock();
+
+        mmu.map_single(vaddr, paddr, PageSize::_4K, flags)
+            .map_err(Into::<ErrNo>::into)?;
+        mmu.map_single(vaddr, paddr, PageSize::_4K, flags)
+            .map_err(Into::<ErrNo>::into)?;
+
+        Ok(0)
+    }
+
+    #[test]
+    fn test_map_twice_is_file_exists() {
+        let (_, _, mmu) = setup_kernel_with_memory();
+
+        assert_eq!(map_twice(&mmu), SyscallError::FileExists);
+    }
+
+    fn reserve_at_vma_base(ctx: &SyscallContext, count: usize) -> VirtualPageNumRange {
+        let reserved = VirtualPageNumRange::from_start_count(
+            SyscallContext::VMA_BASE.to_floor_page_num(),
+            count,
+        );
+
+        let process = ctx.task.process();
+        process
+            .memory_space()
+            .lock()
+            .reserve_range(reserved, AreaType::VMA)
+            .unwrap();
+
+        reserved
+    }
+
+    #[test]
+    fn test_syscall_avoids_reserved_range() {
+        let ctx = setup_syscall_context();
+        let reserved = reserve_at_vma_base(&ctx, 4);
+
+        for hint in [VirtualAddress::null(), SyscallContext::VMA_BASE] {
+            let ret = ctx.sys_mmap(
+                hint,
+                0x1000,
+                MemoryMapProt::READ,
+                MemoryMapFlags::ANONYMOUS,
+                0,
+                0,
+            );
+
+            let vaddr = VirtualAddress::from_usize(ret.unwrap() as usize);
+
+            assert!(vaddr >= reserved.end().start_addr(), "vaddr: {vaddr:?}");
+        }
+    }
+
+    #[test]
+    fn test_reserved_range_faults() {
+        let ctx = setup_syscall_context();
+        let reserved = reserve_at_vma_base(&ctx, 4);
+
+        let process = ctx.task.process();
+        let mmu = process.mmu();
+        let mmu = mmu.lock();
+
+        let mut buf = [0u8; 8];
+        for vpn in reserved.iter() {
+            assert!(mmu.query_virtual(vpn.start_addr()).is_err());
+            assert!(mmu.read_bytes(vpn.start_addr(), &mut buf).is_err());
+        }
+    }
+
+    #[test]
+    fn test_reserve_overlapping_range() {
+        let ctx = setup_syscall_context();
+        let reserved = reserve_at_vma_base(&ctx, 4);
+
+        let process = ctx.task.process();
+        let mut mem = process.memory_space().lock();
+
+        let overlapping = VirtualPageNumRange::from_start_count(reserved.end() - 1, 2);
+        assert_eq!(
+            mem.reserve_range(overlapping, AreaType::VMA),
+            Err(PagingError::AlreadyMapped)
+        );
+
+        // Adjacent is fine
+        let adjacent = VirtualPageNumRange::from_start_count(reserved.end(), 2);
+        assert!(mem.reserve_range(adjacent, AreaType::VMA).is_ok());
+
+        // Reserved areas can be unmapped without page table entries
+        assert!(mem.unmap_area_starts_with(reserved.start()));
+        assert!(mem.unmap_area_starts_with(adjacent.start()));
+        assert_eq!(mem.area_count(), 0);
+    }
+
+    #[test]
+    fn test_rss_and_virtual_bytes() {
+        let ctx = setup_syscall_context();
+        reserve_at_vma_base(&ctx, 4);
+
+        ctx.sys_mmap(
+            VirtualAddress::null(),
+            0x3000,
+            MemoryMapProt::READ,
+            MemoryMapFlags::ANONYMOUS,
+            0,
+            0,
+        )
+        .unwrap();
+
+        let process = ctx.task.process();
+        let mem = process.memory_space().lock();
+
+        // The reserved area only counts toward the virtual size
+        assert_eq!(mem.rss_bytes(), 0x3000);
+        assert_eq!(mem.virtual_bytes(), 0x7000);
+    }
+
+    #[test]
+    fn test_syscall_out_of_memory_rolls_back() {
+        const FRAME_COUNT: usize = 4;
+
+        let (kernel, alloc, mmu) =
+            setup_kernel_with_memory_size(FRAME_COUNT * constants::PAGE_SIZE);
+        let ctx = setup_syscall_context_with(kernel, alloc.clone(), mmu.clone());
+
+        let ret = ctx.sys_mmap(
+            SyscallContext::VMA_BASE,
+            2 * FRAME_COUNT * constants::PAGE_SIZE,
+            MemoryMapProt::READ | MemoryMapProt::WRITE,
+            MemoryMapFlags::ANONYMOUS,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, SyscallError::CannotAllocateMemory);
+
+        // No partial area is recorded
+        assert_eq!(ctx.task.process().memory_space().lock().area_count(), 0);
+
+        // Pages mapped before running out of memory are unmapped
+        for page in 0..FRAME_COUNT {
+            let vaddr = SyscallContext::VMA_BASE + page * constants::PAGE_SIZE;
+            assert!(mmu.lock().query_virtual(vaddr).is_err());
+        }
+
+        // All frames are returned to the allocator
+        let frames = alloc.lock().alloc_frames(FRAME_COUNT).unwrap();
+        assert_eq!(frames.len(), FRAME_COUNT);
+
+        for frame in frames {
+            alloc.lock().dealloc(frame);
+        }
+    }
+
+    #[test]
+    fn test_failed_syscall_matches_snapshot() {
+        const FRAME_COUNT: usize = 4;
+
+        let (kernel, alloc, mmu) =
+            setup_kernel_with_memory_size(FRAME_COUNT * constants::PAGE_SIZE);
+        let ctx = setup_syscall_context_with(kernel, alloc, mmu);
+
+        let mmap = |len: usize| {
+            ctx.sys_mmap(
+                VirtualAddress::null(),
+                len,
+                MemoryMapProt::READ | MemoryMapProt::WRITE,
+                MemoryMapFlags::ANONYMOUS,
+                0,
+                0,
+            )
+        };
+
+        assert!(mmap(constants::PAGE_SIZE).is_ok());
+
+        let snapshot = ctx.task.process().memory_space().lock().snapshot();
+        assert_eq!(snapshot.area_count(), 1);
+
+        assert_eq!(
+            mmap(2 * FRAME_COUNT * constants::PAGE_SIZE),
+            SyscallError::CannotAllocateMemory
+        );
+
+        assert_eq!(
+            ctx.task.process().memory_space().lock().snapshot(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn test_restore_snapshot() {
+        let mut mem = setup_memory_space();
+
+        let first = VirtualPageNumRange::from_start_count(VirtualPageNum::from_usize(0x10), 4);
+        let area = |range| {
+            MappingArea::new(
+                range,
+                AreaType::VMA,
+                MapType::Framed,
+                GenericMappingFlags::User | GenericMappingFlags::Readable,
+                None,
+            )
+        };
+
+        mem.alloc_and_map_area(area(first)).unwrap();
+        let snapshot = mem.snapshot();
+
+        // Added and trimmed since the snapshot
+        let second = VirtualPageNumRange::from_start_count(VirtualPageNum::from_usize(0x20), 2);
+        mem.alloc_and_map_area(area(second)).unwrap();
+        mem.unmap_range(VirtualPageNumRange::from_start_count(first.start(), 1));
+        assert_ne!(mem.snapshot(), snapshot);
+
+        mem.restore(&snapshot).unwrap();
+
+        assert_eq!(mem.snapshot(), snapshot);
+        assert!(!mem.mmu().lock().is_mapped(second.start().start_addr()));
+        assert!(first
+            .iter()
+            .all(|vpn| mem.mmu().lock().is_mapped(vpn.start_addr())));
+    }
+
     #[test]
     fn test_syscall_misaligned_addr() {
         let ctx = setup_syscall_context();
@@ -367,6 +828,66 @@ mod tests {
         assert_eq!(ret, SyscallError::CannotAllocateMemory);
     }
 
+    #[test]
+    fn test_syscall_range_past_user_half() {
+        let ctx = setup_syscall_context();
+        let end = SyscallContext::VMA_WINDOW.end();
+
+        // Starts inside the user half but extends past it
+        let ret = ctx.sys_mmap(
+            VirtualAddress::from(end - constants::PAGE_SIZE),
+            2 * constants::PAGE_SIZE,
+            MemoryMapProt::READ,
+            MemoryMapFlags::ANONYMOUS,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, SyscallError::CannotAllocateMemory);
+
+        // Entirely in the kernel half
+        let ret = ctx.sys_mmap(
+            VirtualAddress::from_usize(0xffff_ffc0_8020_0000),
+            constants::PAGE_SIZE,
+            MemoryMapProt::READ,
+            MemoryMapFlags::ANONYMOUS,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, SyscallError::CannotAllocateMemory);
+
+        // Would overflow the address space
+        let ret = ctx.sys_mmap(
+            VirtualAddress::from_usize(usize::MAX & !(constants::PAGE_SIZE - 1)),
+            2 * constants::PAGE_SIZE,
+            MemoryMapProt::READ,
+            MemoryMapFlags::ANONYMOUS,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, SyscallError::CannotAllocateMemory);
+    }
+
+    #[test]
+    fn test_syscall_range_ends_at_user_half_end() {
+        let ctx = setup_syscall_context();
+        let end = SyscallContext::VMA_WINDOW.end();
+        let addr = VirtualAddress::from(end - constants::PAGE_SIZE);
+
+        let ret = ctx.sys_mmap(
+            addr,
+            constants::PAGE_SIZE,
+            MemoryMapProt::READ,
+            MemoryMapFlags::ANONYMOUS,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, Ok(addr.as_usize() as isize));
+    }
+
     #[test]
     fn test_syscall_misaligned_offset() {
         let ctx = setup_syscall_context();
@@ -606,12 +1127,205 @@ mod tests {
         assert_eq!(random_content, read_buffer);
     }
 
-    fn fill_buffer_with_random_bytes(buf: &mut [u8]) {
-        use rand::Rng;
+    fn mmap_fixed(ctx: &SyscallContext, vaddr: VirtualAddress, len: usize, prot: MemoryMapProt) {
+        let ret = ctx.sys_mmap(
+            vaddr,
+            len,
+            prot,
+            MemoryMapFlags::ANONYMOUS | MemoryMapFlags::FIXED,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, Ok(vaddr.as_usize() as isize));
+    }
 
-        let mut rng = rand::rng();
+    #[test]
+    fn test_syscall_fixed_replaces_permissions() {
+        let ctx = setup_syscall_context();
+        let vaddr = SyscallContext::VMA_BASE;
 
-        rng.fill(buf);
+        mmap_fixed(
+            &ctx,
+            vaddr,
+            0x2000,
+            MemoryMapProt::READ | MemoryMapProt::WRITE,
+        );
+
+        let process = ctx.task.process();
+        process.mmu().lock().export(vaddr, 42u64).unwrap();
+
+        mmap_fixed(&ctx, vaddr, 0x2000, MemoryMapProt::READ);
+
+        let mmu = process.mmu();
+        let mmu = mmu.lock();
+
+        assert_eq!(
+            mmu.export(vaddr, 42u64),
+            Err(MMUError::PageNotWritable { vaddr })
+        );
+
+        let mem = process.memory_space().lock();
+        assert_eq!(mem.area_count(), 1);
+        assert_eq!(mem.rss_bytes(), 0x2000);
+    }
+
+    #[test]
+    fn test_syscall_fixed_splits_area() {
+        let ctx = setup_syscall_context();
+        let vaddr = SyscallContext::VMA_BASE;
+
+        mmap_fixed(
+            &ctx,
+            vaddr,
+            0x3000,
+            MemoryMapProt::READ | MemoryMapProt::WRITE,
+        );
+        mmap_fixed(&ctx, vaddr + 0x1000, 0x1000, MemoryMapProt::READ);
+
+        let process = ctx.task.process();
+        let mmu = process.mmu();
+        let mmu = mmu.lock();
+
+        assert!(mmu.export(vaddr, 1u8).is_ok());
+        assert!(mmu.export(vaddr + 0x1000, 1u8).is_err());
+        assert!(mmu.export(vaddr + 0x2000, 1u8).is_ok());
+
+        let mem = process.memory_space().lock();
+        let starts = mem
+            .iter_areas()
+            .map(|area| area.range().start().start_addr())
+            .collect::<Vec<_>>();
+
+        assert_eq!(starts, [vaddr, vaddr + 0x1000, vaddr + 0x2000]);
+        assert_eq!(mem.rss_bytes(), 0x3000);
+    }
+
+    fn setup_loaded_context() -> SyscallContext {
+        let (kernel, alloc, mmu) = setup_kernel_with_memory();
+
+        let elf = TestElfBuilder::new()
+            .with_entry(0x10000)
+            .with_load(0x10000, PF_R | PF_X, &[0x13; 0x100])
+            .with_load(0x20000, PF_R | PF_W, &[0x42; 0x100])
+            .build();
+
+        let loader = LinuxLoader::from_elf(
+            &elf.as_slice(),
+            "/test",
+            ProcessContext::new(),
+            &(mmu, alloc),
+        )
+        .unwrap();
+
+        let (_, task) = TestProcess::new()
+            .with_memory_space(Some(loader.memory_space))
+            .build();
+
+        SyscallContext::new(task, kernel)
+    }
+
+    #[test]
+    fn test_syscall_fixed_over_segment_then_brk() {
+        let ctx = setup_loaded_context();
+
+        mmap_fixed(
+            &ctx,
+            VirtualAddress::from_usize(0x10000),
+            0x1000,
+            MemoryMapProt::READ,
+        );
+
+        let process = ctx.task.process();
+        let mut mem = process.memory_space().lock();
+
+        let brk = mem.brk_page_range();
+        assert_eq!(brk.start().start_addr(), mem.brk_start());
+        assert_eq!(mem.mappings()[mem.brk_area_idx()].area_type, AreaType::Heap);
+
+        mem.increase_brk(brk.end() + 2).unwrap();
+        assert_eq!(mem.brk_page_range().page_count(), brk.page_count() + 2);
+
+        let brk_start = mem.brk_start();
+        drop(mem);
+
+        process.mmu().lock().export(brk_start, 42u64).unwrap();
+    }
+
+    #[test]
+    fn test_syscall_fixed_over_brk() {
+        let ctx = setup_loaded_context();
+
+        let process = ctx.task.process();
+        let brk_start = process.memory_space().lock().brk_start();
+        let brk_end = brk_start.to_floor_page_num() + 2;
+
+        process.memory_space().lock().increase_brk(brk_end).unwrap();
+
+        mmap_fixed(&ctx, brk_start, 0x2000, MemoryMapProt::READ);
+
+        let mut mem = process.memory_space().lock();
+
+        // The brk area is kept, empty, and can not grow into the new mapping
+        let brk = &mem.mappings()[mem.brk_area_idx()];
+        assert!(brk.range().is_empty());
+        assert_eq!(brk.backing_frames().count(), 0);
+        assert!(mem.increase_brk(brk_end).is_err());
+    }
+
+    #[test]
+    fn test_restore_loaded_space_keeps_brk() {
+        let ctx = setup_loaded_context();
+        let process = ctx.task.process();
+
+        let (snapshot, brk) = {
+            let mem = process.memory_space().lock();
+            (mem.snapshot(), mem.brk_page_range())
+        };
+
+        // Removes the text area and changes the brk area, which is mapped again last
+        mmap_fixed(
+            &ctx,
+            VirtualAddress::from_usize(0x10000),
+            0x1000,
+            MemoryMapProt::READ,
+        );
+        process
+            .memory_space()
+            .lock()
+            .increase_brk(brk.end() + 2)
+            .unwrap();
+
+        let mut mem = process.memory_space().lock();
+        mem.restore(&snapshot).unwrap();
+
+        assert_eq!(mem.snapshot(), snapshot);
+        assert_eq!(mem.mappings()[mem.brk_area_idx()].area_type, AreaType::Heap);
+        assert_eq!(mem.brk_page_range(), brk);
+
+        mem.increase_brk(brk.end() + 1).unwrap();
+    }
+
+    #[test]
+    fn test_syscall_fixed_requires_address() {
+        let ctx = setup_syscall_context();
+
+        let ret = ctx.sys_mmap(
+            VirtualAddress::null(),
+            0x1000,
+            MemoryMapProt::READ,
+            MemoryMapFlags::ANONYMOUS | MemoryMapFlags::FIXED,
+            0,
+            0,
+        );
+
+        assert_eq!(ret, SyscallError::BadAddress);
+    }
+
+    fn fill_buffer_with_random_bytes(buf: &mut [u8]) {
+        use utilities::SplitMix64;
+
+        SplitMix64::new(0x1234_5678).fill_bytes(buf);
     }
 
     fn test_syscall_nonsense_flags_return_invalid_argument(flags: MemoryMapFlags) {
@@ -643,6 +1357,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_syscall_stack_noreserve_flags() {
+        let ctx = setup_syscall_context();
+
+        let ret = ctx.sys_mmap(
+            VirtualAddress::null(),
+            0x1000,
+            MemoryMapProt::READ | MemoryMapProt::WRITE,
+            MemoryMapFlags::ANONYMOUS | MemoryMapFlags::STACK | MemoryMapFlags::NORESERVE,
+            0,
+            0,
+        );
+
+        assert!(ret.is_ok());
+    }
+
+    #[test]
+    fn test_syscall_composite_flags_with_hints() {
+        test_syscall_nonsense_flags_return_invalid_argument(
+            MemoryMapFlags::SHARED | MemoryMapFlags::PRIVATE | MemoryMapFlags::STACK,
+        );
+    }
+
+    #[test]
+    fn test_validate_flags_valid() {
+        for flags in [
+            MemoryMapFlags::ANONYMOUS,
+            MemoryMapFlags::PRIVATE,
+            MemoryMapFlags::SHARED,
+            MemoryMapFlags::PRIVATE | MemoryMapFlags::STACK,
+            MemoryMapFlags::SHARED | MemoryMapFlags::NORESERVE,
+            MemoryMapFlags::PRIVATE | MemoryMapFlags::FIXED,
+        ] {
+            assert_eq!(SyscallContext::validate_flags(flags), Ok(()), "{flags:?}");
+        }
+    }
+
+    #[test]
+    fn test_validate_flags_invalid() {
+        for flags in [
+            MemoryMapFlags::SHARED | MemoryMapFlags::PRIVATE,
+            MemoryMapFlags::SHARED | MemoryMapFlags::PRIVATE | MemoryMapFlags::STACK,
+            MemoryMapFlags::from_bits_retain(0x80000000),
+            MemoryMapFlags::from_bits_retain(0x80000000) | MemoryMapFlags::PRIVATE,
+            MemoryMapFlags::from_bits_retain(0xdeadbeef),
+        ] {
+            assert_eq!(
+                SyscallContext::validate_flags(flags),
+                Err(ErrNo::InvalidArgument),
+                "{flags:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_syscall_private_flags() {
+        let ctx = setup_syscall_context();
+
+        let ret = ctx.sys_mmap(
+            VirtualAddress::null(),
+            0x1000,
+            MemoryMapProt::READ | MemoryMapProt::WRITE,
+            MemoryMapFlags::PRIVATE,
+            0,
+            0,
+        );
+
+        assert!(ret.is_ok());
+    }
+
+    #[test]
+    fn test_syscall_unknown_flags_with_private() {
+        test_syscall_nonsense_flags_return_invalid_argument(
+            MemoryMapFlags::from_bits_retain(0x80000000) | MemoryMapFlags::PRIVATE,
+        );
+    }
+
     fn test_invalid_len(len: usize) {
         let ctx = setup_syscall_context();
 
@@ -678,4 +1469,23 @@ mod tests {
 
         assert_eq!(ret, SyscallError::CannotAllocateMemory);
     }
+
+    #[test]
+    fn test_syscall_len_rounding_overflow() {
+        let ctx = setup_syscall_context();
+
+        // Rounding these up to the page size overflows
+        for len in [usize::MAX, usize::MAX - constants::PAGE_SIZE + 2] {
+            let ret = ctx.sys_mmap(
+                VirtualAddress::null(),
+                len,
+                MemoryMapProt::READ | MemoryMapProt::WRITE,
+                MemoryMapFlags::ANONYMOUS,
+                0,
+                0,
+            );
+
+            assert_eq!(ret, SyscallError::CannotAllocateMemory);
+        }
+    }
 }
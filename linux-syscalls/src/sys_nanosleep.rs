@@ -100,7 +100,7 @@ mod tests {
 
         let req = TimeSpec::new(1, 0);
 
-        mmu.lock().register(&req, false);
+        mmu.lock().register(&req, false).unwrap();
 
         let before_call = SystemTime::now();
         let ret =
@@ -121,7 +121,7 @@ mod tests {
 
         let req = TimeSpec::new(1, 0);
 
-        mmu.lock().register(&req, false);
+        mmu.lock().register(&req, false).unwrap();
 
         let before_call = SystemTime::now();
         let ret =
@@ -163,7 +163,7 @@ mod tests {
     fn test_syscall_invalid_argument(req: TimeSpec) {
         let (mmu, ctx) = setup_syscall_context();
 
-        mmu.lock().register(&req, false);
+        mmu.lock().register(&req, false).unwrap();
 
         let ret =
             block_on!(ctx.sys_nanosleep(VirtualAddress::from_ref(&req), VirtualAddress::null()));
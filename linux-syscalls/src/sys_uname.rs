@@ -140,7 +140,11 @@ mod tests {
         let utsname = Box::new(UtsName::default());
 
         // Map the buffer to user space
-        task.process().mmu().lock().register(utsname.as_ref(), true);
+        task.process()
+            .mmu()
+            .lock()
+            .register(utsname.as_ref(), true)
+            .unwrap();
 
         (SyscallContext::new(task, kernel), mmu, utsname)
     }
@@ -175,7 +179,7 @@ mod tests {
 
         let utsname = UtsName::default();
 
-        mmu.lock().register(&utsname, false);
+        mmu.lock().register(&utsname, false).unwrap();
 
         let result = context.sys_uname(VirtualAddress::from_ref(&utsname));
         assert_eq!(result, Err(ErrNo::BadAddress));
@@ -195,7 +199,7 @@ mod tests {
 
         // Create a buffer smaller than UtsName size (6*65=390 bytes)
         let small_buffer = [0u8; 10];
-        mmu.lock().register(&small_buffer, true);
+        mmu.lock().register(&small_buffer, true).unwrap();
 
         let result = context.sys_uname(small_buffer.as_ref().into());
 
@@ -139,7 +139,7 @@ mod tests {
         let ctx = SyscallContext::new(task, kernel);
 
         let buf = b"Hello, world";
-        mmu.lock().register(buf, false); // let the mmu know about the buffer
+        mmu.lock().register(buf, false).unwrap(); // let the mmu know about the buffer
 
         let ret = block_on!(ctx.sys_write(0, buf.into(), buf.len()));
 
@@ -207,7 +207,7 @@ mod tests {
         let ctx = SyscallContext::new(task, kernel);
 
         let buf = b"Hello, world";
-        mmu.lock().register(buf, false); // let the mmu know about the buffer
+        mmu.lock().register(buf, false).unwrap(); // let the mmu know about the buffer
 
         let ret = block_on!(ctx.sys_write(0, buf.into(), buf.len()));
 
@@ -295,7 +295,7 @@ mod tests {
 
         let ctx = SyscallContext::new(task, kernel);
 
-        mmu.lock().register(buffer, false);
+        mmu.lock().register(buffer, false).unwrap();
 
         let ret = block_on!(ctx.sys_write(0, buffer.into(), buffer.len()));
 
@@ -340,7 +340,12 @@ mod tests {
 
         let buf = b"Hello, world";
 
-        ctx.task.process().mmu().lock().register(buf, false);
+        ctx.task
+            .process()
+            .mmu()
+            .lock()
+            .register(buf, false)
+            .unwrap();
 
         let mut fut = ctx.sys_write(0, buf.into(), buf.len());
         let mut cx = Context::from_waker(Waker::noop());
@@ -2,9 +2,9 @@ use core::alloc::Layout;
 use std::sync::Arc;
 
 use abstractions::IUsizeAlias;
-use address::PhysicalAddress;
+use address::{PhysicalAddress, PhysicalAddressRange};
 use allocation::FrameAllocator;
-use allocation_abstractions::IFrameAllocator;
+use allocation_abstractions::{IFrameAllocator, ReserveError};
 use hermit_sync::SpinMutex;
 use mmu_abstractions::IMMU;
 
@@ -74,6 +74,14 @@ impl IFrameAllocator for TestFrameAllocator {
         self.inner.alloc_contiguous(count)
     }
 
+    fn alloc_contiguous_aligned(
+        &mut self,
+        count: usize,
+        align: usize,
+    ) -> Option<allocation_abstractions::FrameRangeDesc> {
+        self.inner.alloc_contiguous_aligned(count, align)
+    }
+
     fn dealloc(&mut self, frame: allocation_abstractions::FrameDesc) {
         self.inner.dealloc(frame);
     }
@@ -81,6 +89,10 @@ impl IFrameAllocator for TestFrameAllocator {
     fn dealloc_range(&mut self, range: allocation_abstractions::FrameRangeDesc) {
         self.inner.dealloc_range(range)
     }
+
+    fn reserve(&mut self, range: PhysicalAddressRange) -> Result<(), ReserveError> {
+        self.inner.reserve(range)
+    }
 }
 
 impl Drop for TestFrameAllocator {
@@ -3,7 +3,7 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use abstractions::IUsizeAlias;
 use address::{PhysicalAddress, PhysicalAddressRange};
-use allocation_abstractions::{FrameDesc, FrameRangeDesc, IFrameAllocator};
+use allocation_abstractions::{FrameDesc, FrameRangeDesc, IFrameAllocator, ReserveError};
 use hermit_sync::SpinMutex;
 use mmu_abstractions::IMMU;
 
@@ -69,6 +69,13 @@ impl HostMemory {
         (pa, Self { ptr, layout })
     }
 
+    pub fn alloc_aligned(num_frames: usize, align: usize) -> (PhysicalAddress, Self) {
+        let layout = Layout::from_size_align(constants::PAGE_SIZE * num_frames, align).unwrap();
+        let (pa, ptr) = heap_allocate(layout);
+
+        (pa, Self { ptr, layout })
+    }
+
     pub fn paddr(&self) -> PhysicalAddress {
         PhysicalAddress::from_usize(self.ptr.as_ptr() as usize)
     }
@@ -111,11 +118,24 @@ impl IFrameAllocator for TestFrameAllocator {
 
         self.records.insert(pa, mem);
 
-        Some(unsafe { FrameRangeDesc::new(pa, count) })
+        Some(unsafe { FrameRangeDesc::new(pa, count * constants::PAGE_SIZE) })
+    }
+
+    fn alloc_contiguous_aligned(
+        &mut self,
+        count: usize,
+        align: usize,
+    ) -> Option<allocation_abstractions::FrameRangeDesc> {
+        // Records are freed as a whole, so the host allocation itself must be aligned
+        let (pa, mem) = HostMemory::alloc_aligned(count, align.max(constants::PAGE_SIZE));
+
+        self.records.insert(pa, mem);
+
+        Some(unsafe { FrameRangeDesc::new(pa, count * constants::PAGE_SIZE) })
     }
 
     fn dealloc(&mut self, frame: allocation_abstractions::FrameDesc) {
-        self.records.remove(&frame.0);
+        self.records.remove(&frame.addr());
         core::mem::forget(frame);
     }
 
@@ -123,6 +143,11 @@ impl IFrameAllocator for TestFrameAllocator {
         self.records.remove(&range.start);
         core::mem::forget(range);
     }
+
+    fn reserve(&mut self, _range: PhysicalAddressRange) -> Result<(), ReserveError> {
+        // Frames come from the host heap, which never hands out memory we don't own
+        Ok(())
+    }
 }
 
 const fn create_layout(num_frame: usize) -> Layout {
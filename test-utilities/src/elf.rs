@@ -0,0 +1,170 @@
+//! A tiny ELF64 image builder so loader tests don't need prebuilt binaries.
+
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+
+pub const EM_RISCV: u16 = 0xf3;
+pub const EM_LOONGARCH: u16 = 0x102;
+
+pub const PT_LOAD: u32 = 1;
+pub const PT_INTERP: u32 = 3;
+pub const PT_PHDR: u32 = 6;
+
+pub const PF_X: u32 = 1;
+pub const PF_W: u32 = 2;
+pub const PF_R: u32 = 4;
+
+const EHDR_SIZE: usize = 64;
+const PHDR_SIZE: usize = 56;
+const SEGMENT_ALIGN: usize = constants::PAGE_SIZE;
+
+struct TestSegment {
+    p_type: u32,
+    flags: u32,
+    vaddr: usize,
+    data: Vec<u8>,
+    mem_size: usize,
+}
+
+pub struct TestElfBuilder {
+    e_type: u16,
+    machine: u16,
+    entry: usize,
+    segments: Vec<TestSegment>,
+}
+
+impl Default for TestElfBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestElfBuilder {
+    pub fn new() -> Self {
+        Self {
+            e_type: ET_EXEC,
+            machine: EM_RISCV,
+            entry: 0,
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn with_type(mut self, e_type: u16) -> Self {
+        self.e_type = e_type;
+        self
+    }
+
+    pub fn with_machine(mut self, machine: u16) -> Self {
+        self.machine = machine;
+        self
+    }
+
+    pub fn with_entry(mut self, entry: usize) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Add a `PT_LOAD` segment whose memory size equals the length of `data`.
+    pub fn with_load(self, vaddr: usize, flags: u32, data: &[u8]) -> Self {
+        self.with_load_sized(vaddr, flags, data, data.len())
+    }
+
+    /// Add a `PT_LOAD` segment, the memory beyond `data` is zero filled (.bss).
+    pub fn with_load_sized(
+        mut self,
+        vaddr: usize,
+        flags: u32,
+        data: &[u8],
+        mem_size: usize,
+    ) -> Self {
+        debug_assert!(mem_size >= data.len());
+
+        self.segments.push(TestSegment {
+            p_type: PT_LOAD,
+            flags,
+            vaddr,
+            data: data.to_vec(),
+            mem_size,
+        });
+        self
+    }
+
+    /// Add a `PT_INTERP` segment naming the given interpreter.
+    pub fn with_interp(mut self, interp: &str) -> Self {
+        let mut data = interp.as_bytes().to_vec();
+        data.push(0);
+
+        self.segments.push(TestSegment {
+            p_type: PT_INTERP,
+            flags: PF_R,
+            vaddr: 0,
+            mem_size: data.len(),
+            data,
+        });
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let phdrs_end = EHDR_SIZE + PHDR_SIZE * self.segments.len();
+
+        // Place every segment at a page aligned file offset that is congruent to its vaddr
+        let mut offsets = Vec::with_capacity(self.segments.len());
+        let mut cursor = phdrs_end;
+        for segment in self.segments.iter() {
+            let offset = cursor.next_multiple_of(SEGMENT_ALIGN) + segment.vaddr % SEGMENT_ALIGN;
+            offsets.push(offset);
+            cursor = offset + segment.data.len();
+        }
+
+        let mut image = vec![0u8; cursor];
+
+        // e_ident
+        image[0..4].copy_from_slice(b"\x7fELF");
+        image[4] = 2; // ELFCLASS64
+        image[5] = 1; // ELFDATA2LSB
+        image[6] = 1; // EV_CURRENT
+
+        put_u16(&mut image, 16, self.e_type);
+        put_u16(&mut image, 18, self.machine);
+        put_u32(&mut image, 20, 1); // e_version
+        put_u64(&mut image, 24, self.entry as u64);
+        put_u64(&mut image, 32, EHDR_SIZE as u64); // e_phoff
+        put_u64(&mut image, 40, 0); // e_shoff
+        put_u32(&mut image, 48, 0); // e_flags
+        put_u16(&mut image, 52, EHDR_SIZE as u16);
+        put_u16(&mut image, 54, PHDR_SIZE as u16);
+        put_u16(&mut image, 56, self.segments.len() as u16);
+        put_u16(&mut image, 58, 64); // e_shentsize
+        put_u16(&mut image, 60, 0); // e_shnum
+        put_u16(&mut image, 62, 0); // e_shstrndx
+
+        for (idx, (segment, offset)) in self.segments.iter().zip(offsets).enumerate() {
+            let base = EHDR_SIZE + idx * PHDR_SIZE;
+
+            put_u32(&mut image, base, segment.p_type);
+            put_u32(&mut image, base + 4, segment.flags);
+            put_u64(&mut image, base + 8, offset as u64);
+            put_u64(&mut image, base + 16, segment.vaddr as u64);
+            put_u64(&mut image, base + 24, segment.vaddr as u64); // p_paddr
+            put_u64(&mut image, base + 32, segment.data.len() as u64);
+            put_u64(&mut image, base + 40, segment.mem_size as u64);
+            put_u64(&mut image, base + 48, SEGMENT_ALIGN as u64);
+
+            image[offset..offset + segment.data.len()].copy_from_slice(&segment.data);
+        }
+
+        image
+    }
+}
+
+fn put_u16(image: &mut [u8], offset: usize, value: u16) {
+    image[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(image: &mut [u8], offset: usize, value: u32) {
+    image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(image: &mut [u8], offset: usize, value: u64) {
+    image[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+}
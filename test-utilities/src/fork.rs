@@ -0,0 +1,124 @@
+//! A fork scenario shared by the tests of every MMU backend: a parent space with written
+//! data is cloned, then the child writes to it and the two spaces must stay isolated.
+
+use std::sync::Arc;
+
+use abstractions::IUsizeAlias;
+use address::{IToPageNum, VirtualAddress, VirtualPageNumRange};
+use allocation_abstractions::IFrameAllocator;
+use hermit_sync::SpinMutex;
+use memory_space::{AreaType, MapType, MappingArea, MemorySpace};
+use mmu_abstractions::{GenericMappingFlags, IMMU};
+
+pub const FORK_AREA_BASE: usize = 0x1000_0000;
+pub const FORK_AREA_PAGES: usize = 4;
+
+const PARENT_BYTE: u8 = 0x42;
+const CHILD_BYTE: u8 = 0x24;
+
+/// Creates the parent space on `mmu` with a writable area filled with a known pattern.
+pub fn setup_fork_parent(
+    mmu: Arc<SpinMutex<dyn IMMU>>,
+    alloc: Arc<SpinMutex<dyn IFrameAllocator>>,
+) -> MemorySpace {
+    let mut parent = MemorySpace::new(mmu, alloc);
+    let base = VirtualAddress::from_usize(FORK_AREA_BASE);
+
+    parent
+        .alloc_and_map_area(MappingArea::new(
+            VirtualPageNumRange::from_start_count(base.to_floor_page_num(), FORK_AREA_PAGES),
+            AreaType::VMA,
+            MapType::Framed,
+            GenericMappingFlags::User
+                | GenericMappingFlags::Readable
+                | GenericMappingFlags::Writable,
+            None,
+        ))
+        .unwrap();
+
+    parent
+        .mmu()
+        .lock()
+        .write_bytes(base, &[PARENT_BYTE; FORK_AREA_PAGES * constants::PAGE_SIZE])
+        .unwrap();
+
+    parent
+}
+
+/// Forks `parent` into a new space on `mmu`, sharing the parent's allocator.
+pub fn fork(parent: &MemorySpace, mmu: Arc<SpinMutex<dyn IMMU>>) -> MemorySpace {
+    MemorySpace::clone_existing(parent, mmu, None)
+}
+
+/// Writes to the first page of the child and checks that only the child sees the change,
+/// and that the two spaces never share a frame.
+pub fn assert_fork_isolated(parent: &MemorySpace, child: &MemorySpace) {
+    let base = VirtualAddress::from_usize(FORK_AREA_BASE);
+    let len = FORK_AREA_PAGES * constants::PAGE_SIZE;
+
+    assert_eq!(read_area(child), vec![PARENT_BYTE; len]);
+
+    let frames_of = |space: &MemorySpace| {
+        space
+            .iter_areas()
+            .flat_map(|area| area.backing_frames())
+            .collect::<Vec<_>>()
+    };
+
+    let parent_frames = frames_of(parent);
+    let child_frames = frames_of(child);
+
+    assert_eq!(parent_frames.len(), child_frames.len());
+    assert!(child_frames
+        .iter()
+        .all(|frame| !parent_frames.contains(frame)));
+
+    child
+        .mmu()
+        .lock()
+        .write_bytes(base, &[CHILD_BYTE; constants::PAGE_SIZE])
+        .unwrap();
+
+    assert_eq!(read_area(parent), vec![PARENT_BYTE; len]);
+
+    let child_bytes = read_area(child);
+    assert!(child_bytes[..constants::PAGE_SIZE]
+        .iter()
+        .all(|&byte| byte == CHILD_BYTE));
+    assert!(child_bytes[constants::PAGE_SIZE..]
+        .iter()
+        .all(|&byte| byte == PARENT_BYTE));
+
+    // Writing must not change which frames back the spaces
+    assert_eq!(frames_of(parent), parent_frames);
+    assert_eq!(frames_of(child), child_frames);
+}
+
+fn read_area(space: &MemorySpace) -> Vec<u8> {
+    let mut buf = vec![0u8; FORK_AREA_PAGES * constants::PAGE_SIZE];
+
+    space
+        .mmu()
+        .lock()
+        .read_bytes(VirtualAddress::from_usize(FORK_AREA_BASE), &mut buf)
+        .unwrap();
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{allocation::contiguous::TestFrameAllocator, memory::TestMMU};
+
+    use super::*;
+
+    #[test]
+    fn test_fork_is_isolated() {
+        let alloc = TestFrameAllocator::new(1024 * 1024);
+
+        let parent = setup_fork_parent(TestMMU::new(alloc.clone()), alloc.clone());
+        let child = fork(&parent, TestMMU::new(alloc));
+
+        assert_fork_isolated(&parent, &child);
+    }
+}
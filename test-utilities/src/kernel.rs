@@ -2,18 +2,26 @@ use allocation_abstractions::IFrameAllocator;
 use filesystem_abstractions::DirectoryTreeNode;
 use hermit_sync::SpinMutex;
 use kernel_abstractions::{IKernel, IKernelSerial};
+use linux_task_abstractions::ILinuxTask;
+use memory_space::MemorySpace;
 use std::{
     collections::vec_deque::VecDeque,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
     time::{SystemTime, UNIX_EPOCH},
     vec::Vec,
 };
 use timing::TimeSpec;
 
+use crate::{allocation::contiguous::TestFrameAllocator, memory::TestMMU, task::TestProcess};
+
 pub struct TestKernel {
     pub serial: Option<Arc<dyn IKernelSerial>>,
     pub fs: Option<Arc<SpinMutex<Arc<DirectoryTreeNode>>>>,
     pub allocator: Option<Arc<SpinMutex<dyn IFrameAllocator>>>,
+    next_pid: AtomicU32,
 }
 
 unsafe impl Send for TestKernel {}
@@ -31,6 +39,7 @@ impl TestKernel {
             serial: None,
             fs: None,
             allocator: None,
+            next_pid: AtomicU32::new(1),
         }
     }
 
@@ -52,6 +61,41 @@ impl TestKernel {
     pub fn build(self) -> Arc<dyn IKernel> {
         Arc::new(self)
     }
+
+    /// Creates a process owning `memory_space` with a fresh pid and returns its main thread.
+    pub fn spawn_process(&self, memory_space: MemorySpace) -> Arc<dyn ILinuxTask> {
+        let pid = self.next_pid.fetch_add(1, Ordering::Relaxed);
+
+        let mut process = TestProcess::new()
+            .with_pid(pid)
+            .with_pgid(pid)
+            .with_memory_space(Some(memory_space));
+
+        process.configure_main_thread(|thread| {
+            *thread = core::mem::take(thread).with_tid(pid).with_tgid(pid);
+        });
+
+        let (_, task) = process.build();
+
+        task
+    }
+
+    /// Spawns two processes with distinct memory spaces whose mmus are bound to the same
+    /// allocator, so memory of one can be accessed from the other with `map_cross`.
+    pub fn spawn_process_pair(
+        &self,
+        memory_size: usize,
+    ) -> (Arc<dyn ILinuxTask>, Arc<dyn ILinuxTask>) {
+        let alloc = TestFrameAllocator::new(memory_size);
+
+        let spawn = || {
+            let mmu = TestMMU::new(alloc.clone());
+
+            self.spawn_process(MemorySpace::new(mmu, alloc.clone()))
+        };
+
+        (spawn(), spawn())
+    }
 }
 
 impl IKernel for TestKernel {
@@ -122,3 +166,61 @@ impl IKernelSerial for TestSerial {
         self.input.lock().pop_front()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use abstractions::IUsizeAlias;
+    use address::{IToPageNum, VirtualAddress, VirtualPageNumRange};
+    use memory_space::{AreaType, MapType, MappingArea};
+    use mmu_abstractions::GenericMappingFlags;
+
+    use super::*;
+
+    #[test]
+    fn test_spawn_process_pids() {
+        let kernel = TestKernel::new();
+
+        let (a, b) = kernel.spawn_process_pair(1024 * 1024);
+
+        assert_ne!(a.tgid(), b.tgid());
+        assert_eq!(a.process().pid(), a.tgid());
+        assert_eq!(b.process().pid(), b.tgid());
+    }
+
+    #[test]
+    fn test_map_cross_between_processes() {
+        let kernel = TestKernel::new();
+        let (a, b) = kernel.spawn_process_pair(1024 * 1024);
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        let process_a = a.process();
+        let mut memory_a = process_a.memory_space().lock();
+        memory_a
+            .alloc_and_map_area(MappingArea::new(
+                VirtualPageNumRange::from_start_count(vaddr.to_floor_page_num(), 1),
+                AreaType::VMA,
+                MapType::Framed,
+                GenericMappingFlags::User
+                    | GenericMappingFlags::Readable
+                    | GenericMappingFlags::Writable,
+                None,
+            ))
+            .unwrap();
+
+        let mmu_a = memory_a.mmu().lock();
+        mmu_a.write_bytes(vaddr, b"hello").unwrap();
+
+        let process_b = b.process();
+        let memory_b = process_b.memory_space().lock();
+        assert!(!Arc::ptr_eq(memory_a.mmu(), memory_b.mmu()));
+
+        let mut mmu_b = memory_b.mmu().lock();
+
+        #[allow(deprecated)]
+        let window = mmu_b.map_cross_internal(&*mmu_a, vaddr, 5).unwrap();
+        assert_eq!(window, b"hello");
+
+        mmu_b.unmap_cross(&*mmu_a, vaddr);
+    }
+}
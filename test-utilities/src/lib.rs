@@ -1,4 +1,6 @@
 pub mod allocation;
+pub mod elf;
+pub mod fork;
 pub mod fs;
 pub mod kernel;
 pub mod memory;
@@ -5,14 +5,24 @@ use std::{
 };
 
 use abstractions::IUsizeAlias;
-use address::{IAddress, IAlignableAddress, PhysicalAddress, VirtualAddress, VirtualAddressRange};
+use address::{
+    IAddress, IAddressBase, IAlignableAddress, PhysicalAddress, VirtualAddress, VirtualAddressRange,
+};
+use allocation_abstractions::IFrameAllocator;
 use hermit_sync::SpinMutex;
-use mmu_abstractions::{GenericMappingFlags, MMUError, PageSize, PagingError, PagingResult, IMMU};
+use mmu_abstractions::{
+    GenericMappingFlags, InspectOutcome, MMUError, MmuCapabilities, PageSize, PagingError,
+    PagingResult, IMMU,
+};
 
-use crate::allocation::ITestFrameAllocator;
+use memory_space::MemorySpace;
+
+use crate::allocation::{contiguous::TestFrameAllocator, ITestFrameAllocator};
 
 pub struct TestMMU {
     alloc: Arc<SpinMutex<dyn ITestFrameAllocator>>,
+    // The same allocator as `alloc`, which can not be upcasted behind the mutex
+    bound_alloc: Arc<SpinMutex<dyn IFrameAllocator>>,
     mappings: Vec<MappingRecord>,
     mapped: SpinMutex<BTreeMap<VirtualAddress, MappedMemory>>,
 }
@@ -28,17 +38,73 @@ struct MappingRecord {
     from_test_env: bool,
 }
 
+impl MappingRecord {
+    /// Compares offsets, so a mapping of the last page does not overflow its end
+    fn contains(&self, vaddr: VirtualAddress) -> bool {
+        self.virt <= vaddr && (vaddr.as_usize() - self.virt.as_usize()) < self.len
+    }
+}
+
 impl TestMMU {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(alloc: Arc<SpinMutex<dyn ITestFrameAllocator>>) -> Arc<SpinMutex<dyn IMMU>> {
+    pub fn new<T: ITestFrameAllocator + 'static>(
+        alloc: Arc<SpinMutex<T>>,
+    ) -> Arc<SpinMutex<dyn IMMU>> {
         Arc::new(SpinMutex::new(Self {
-            alloc,
+            alloc: alloc.clone(),
+            bound_alloc: alloc,
             mappings: Vec::new(),
             mapped: SpinMutex::new(BTreeMap::new()),
         }))
     }
 }
 
+type AllocatorHandle = Arc<SpinMutex<dyn IFrameAllocator>>;
+
+/// Builds a [`MemorySpace`] backed by a [`TestMMU`], so tests don't have to wire the
+/// allocator and the mmu themselves.
+pub struct MemorySpaceBuilder {
+    capacity: usize,
+    mmu: Option<(AllocatorHandle, Arc<SpinMutex<dyn IMMU>>)>,
+}
+
+impl Default for MemorySpaceBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemorySpaceBuilder {
+    const DEFAULT_CAPACITY: usize = 1024 * 1024 * 1024; // 1 GB
+
+    pub fn new() -> Self {
+        Self {
+            capacity: Self::DEFAULT_CAPACITY,
+            mmu: None,
+        }
+    }
+
+    /// The size of the memory managed by the allocator, ignored if an mmu is given.
+    pub fn with_capacity(mut self, bytes: usize) -> Self {
+        self.capacity = bytes;
+        self
+    }
+
+    /// Uses an existing mmu and the allocator it is bound to.
+    pub fn with_mmu(mut self, alloc: AllocatorHandle, mmu: Arc<SpinMutex<dyn IMMU>>) -> Self {
+        self.mmu = Some((alloc, mmu));
+        self
+    }
+
+    pub fn build(self) -> (MemorySpace, AllocatorHandle) {
+        let (alloc, mmu) = self
+            .mmu
+            .unwrap_or_else(|| TestFrameAllocator::new_with_mmu(self.capacity));
+
+        (MemorySpace::new(mmu, alloc.clone()), alloc)
+    }
+}
+
 impl IMMU for TestMMU {
     fn map_single(
         &mut self,
@@ -47,12 +113,13 @@ impl IMMU for TestMMU {
         size: PageSize,
         flags: GenericMappingFlags,
     ) -> PagingResult<()> {
-        paging_ensure_addr_valid(vaddr)?;
-        paging_ensure_addr_valid(target)?;
+        paging_ensure_vaddr_valid(vaddr)?;
+        paging_ensure_paddr_valid(target)?;
+        paging_ensure_valid_size(size)?;
 
         // Check overlapping
         for mapping in &self.mappings {
-            if mapping.virt <= vaddr && vaddr < mapping.virt + mapping.len {
+            if mapping.contains(vaddr) {
                 return Err(PagingError::AlreadyMapped);
             }
         }
@@ -75,8 +142,8 @@ impl IMMU for TestMMU {
         new_target: PhysicalAddress,
         flags: GenericMappingFlags,
     ) -> PagingResult<PageSize> {
-        paging_ensure_addr_valid(vaddr)?;
-        paging_ensure_addr_valid(new_target)?;
+        paging_ensure_vaddr_valid(vaddr)?;
+        paging_ensure_paddr_valid(new_target)?;
 
         // Find and modify the mapping
         for mapping in self.mappings.iter_mut() {
@@ -129,11 +196,11 @@ impl IMMU for TestMMU {
         paddr: Option<PhysicalAddress>,
         flags: Option<GenericMappingFlags>,
     ) -> PagingResult<()> {
-        paging_ensure_addr_valid(vaddr)?;
+        paging_ensure_vaddr_valid(vaddr)?;
         paging_ensure_valid_size(size)?;
 
         if let Some(paddr) = paddr {
-            paging_ensure_addr_valid(paddr)?;
+            paging_ensure_paddr_valid(paddr)?;
         }
 
         // Find and update the mapping
@@ -159,7 +226,7 @@ impl IMMU for TestMMU {
         vaddr: VirtualAddress,
         len: usize,
         callback: &mut dyn FnMut(&[u8], usize) -> bool,
-    ) -> Result<(), MMUError> {
+    ) -> Result<InspectOutcome, MMUError> {
         mmu_ensure_addr_valid(vaddr)?;
 
         let mut checking_vaddr = vaddr;
@@ -185,14 +252,16 @@ impl IMMU for TestMMU {
             let slice = unsafe { std::slice::from_raw_parts(ptr.add(offset), len) };
 
             if !callback(slice, checking_offset) {
-                break;
+                return Ok(InspectOutcome::Stopped {
+                    bytes_processed: checking_offset + len,
+                });
             }
 
             checking_offset += len;
             checking_vaddr += len;
         }
 
-        Ok(())
+        Ok(InspectOutcome::Completed)
     }
 
     fn inspect_framed_mut_internal(
@@ -200,7 +269,7 @@ impl IMMU for TestMMU {
         vaddr: VirtualAddress,
         len: usize,
         callback: &mut dyn FnMut(&mut [u8], usize) -> bool,
-    ) -> Result<(), MMUError> {
+    ) -> Result<InspectOutcome, MMUError> {
         mmu_ensure_addr_valid(vaddr)?;
 
         let mut checking_vaddr = vaddr;
@@ -226,28 +295,40 @@ impl IMMU for TestMMU {
             let slice = unsafe { std::slice::from_raw_parts_mut(ptr.add(offset), len) };
 
             if !callback(slice, checking_offset) {
-                break;
+                return Ok(InspectOutcome::Stopped {
+                    bytes_processed: checking_offset + len,
+                });
             }
 
             checking_offset += len;
             checking_vaddr += len;
         }
 
-        Ok(())
+        Ok(InspectOutcome::Completed)
     }
 
     fn read_bytes(&self, vaddr: VirtualAddress, buf: &mut [u8]) -> Result<(), MMUError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
         self.inspect_framed_internal(vaddr, buf.len(), &mut |src, offset| {
             buf[offset..offset + src.len()].copy_from_slice(src);
             true
         })
+        .map(|_| ())
     }
 
     fn write_bytes(&self, vaddr: VirtualAddress, buf: &[u8]) -> Result<(), MMUError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
         self.inspect_framed_mut_internal(vaddr, buf.len(), &mut |dst, offset| {
             dst.copy_from_slice(&buf[offset..offset + dst.len()]);
             true
         })
+        .map(|_| ())
     }
 
     fn translate_phys(
@@ -274,12 +355,37 @@ impl IMMU for TestMMU {
         Ok(unsafe { std::slice::from_raw_parts_mut(ptr, len) })
     }
 
-    fn platform_payload(&self) -> usize {
-        panic!("There's no platform payload for test environment")
+    fn platform_payload(&self) -> Option<usize> {
+        // There's no platform payload for test environment
+        None
+    }
+
+    fn bound_alloc(&self) -> Option<Arc<SpinMutex<dyn IFrameAllocator>>> {
+        Some(self.bound_alloc.clone())
+    }
+
+    fn capabilities(&self) -> MmuCapabilities {
+        let mut capabilities = MmuCapabilities::CROSS_MAP | MmuCapabilities::HUGE_PAGES;
+
+        if self
+            .alloc
+            .lock()
+            .linear_map(PhysicalAddress::null())
+            .is_some()
+        {
+            capabilities |= MmuCapabilities::LINEAR_MAP;
+        }
+
+        capabilities
     }
 
     #[cfg(not(target_os = "none"))]
-    fn register_internal(&mut self, vaddr: VirtualAddress, len: usize, mutable: bool) {
+    fn register_internal(
+        &mut self,
+        vaddr: VirtualAddress,
+        len: usize,
+        mutable: bool,
+    ) -> Result<(), MMUError> {
         let mut flags = GenericMappingFlags::User | GenericMappingFlags::Readable;
 
         if mutable {
@@ -293,10 +399,12 @@ impl IMMU for TestMMU {
             len,
             from_test_env: true,
         });
+
+        Ok(())
     }
 
     #[cfg(not(target_os = "none"))]
-    fn unregister_internal(&mut self, vaddr: VirtualAddress) {
+    fn unregister_internal(&mut self, vaddr: VirtualAddress) -> Result<(), MMUError> {
         let mut i = 0;
 
         while i < self.mappings.len() {
@@ -306,9 +414,19 @@ impl IMMU for TestMMU {
                 i += 1;
             }
         }
+
+        Ok(())
     }
 
     fn map_buffer_internal(&self, vaddr: VirtualAddress, len: usize) -> Result<&'_ [u8], MMUError> {
+        // Zero-length reads succeed anywhere, but a buffer must still point somewhere valid
+        mmu_ensure_addr_valid(vaddr)?;
+
+        // Empty buffers are not recorded, as their guards never unmap them
+        if len == 0 {
+            return Ok(&[]);
+        }
+
         let mem = MappedMemory::alloc(vaddr, len, false);
         let mut mapped = self.mapped.lock();
 
@@ -339,6 +457,13 @@ impl IMMU for TestMMU {
         len: usize,
         _force_mut: bool,
     ) -> Result<&'_ mut [u8], MMUError> {
+        // Zero-length reads succeed anywhere, but a buffer must still point somewhere valid
+        mmu_ensure_addr_valid(vaddr)?;
+
+        if len == 0 {
+            return Ok(&mut []);
+        }
+
         let mem = MappedMemory::alloc(vaddr, len, true);
         let mut mapped = self.mapped.lock();
 
@@ -384,6 +509,21 @@ impl IMMU for TestMMU {
         }
     }
 
+    fn force_unmap_buffer(&self, vaddr: VirtualAddress) {
+        let mut locked = self.mapped.lock();
+
+        let key = locked
+            .iter()
+            .find(|(_, m)| m.range().contains(vaddr))
+            .map(|(key, _)| *key);
+
+        if let Some(mapped) = key.and_then(|key| locked.remove(&key)) {
+            if mapped.mutable {
+                let _ = self.write_bytes(mapped.vaddr, mapped.slice_mut());
+            }
+        }
+    }
+
     fn map_cross_internal<'a>(
         &'a mut self,
         source: &'a dyn IMMU,
@@ -419,7 +559,7 @@ impl TestMMU {
     fn query_mapping(&self, vaddr: VirtualAddress) -> Option<&MappingRecord> {
         self.mappings
             .iter()
-            .find(|&mapping| mapping.virt <= vaddr && vaddr < mapping.virt + mapping.len)
+            .find(|&mapping| mapping.contains(vaddr))
             .map(|v| v as _)
     }
 }
@@ -427,16 +567,24 @@ impl TestMMU {
 fn paging_ensure_valid_size(size: PageSize) -> PagingResult<()> {
     if let PageSize::Custom(size) = size {
         if size % constants::PAGE_SIZE != 0 {
-            return Err(PagingError::NotAligned);
+            return Err(PagingError::NotAlignedSize);
         }
     }
 
     Ok(())
 }
 
-fn paging_ensure_addr_valid<T: IAlignableAddress>(addr: T) -> PagingResult<()> {
-    if !addr.is_page_aligned() {
-        return Err(PagingError::NotAligned);
+fn paging_ensure_vaddr_valid(vaddr: VirtualAddress) -> PagingResult<()> {
+    if !vaddr.is_page_aligned() {
+        return Err(PagingError::NotAlignedVaddr);
+    }
+
+    Ok(())
+}
+
+fn paging_ensure_paddr_valid(paddr: PhysicalAddress) -> PagingResult<()> {
+    if !paddr.is_page_aligned() {
+        return Err(PagingError::NotAlignedPaddr);
     }
 
     Ok(())
@@ -450,24 +598,33 @@ fn mmu_ensure_addr_valid<T: IAlignableAddress>(addr: T) -> Result<(), MMUError>
     Ok(())
 }
 
+const USER_READ: GenericMappingFlags =
+    GenericMappingFlags::User.union(GenericMappingFlags::Readable);
+const USER_READ_WRITE: GenericMappingFlags = USER_READ.union(GenericMappingFlags::Writable);
+
 fn mmu_ensure_permisssion(
     vaddr: VirtualAddress,
     flags: GenericMappingFlags,
     mutable: bool,
 ) -> Result<(), MMUError> {
-    if !flags.contains(GenericMappingFlags::User) {
-        return Err(MMUError::PrivilegeError);
+    let required = match mutable {
+        true => USER_READ_WRITE,
+        false => USER_READ,
+    };
+
+    if flags.contains_all(required) {
+        return Ok(());
     }
 
-    if !flags.contains(GenericMappingFlags::Readable) {
-        return Err(MMUError::PageNotReadable { vaddr });
+    if !flags.contains_all(GenericMappingFlags::User) {
+        return Err(MMUError::PrivilegeError);
     }
 
-    if mutable && !flags.contains(GenericMappingFlags::Writable) {
-        return Err(MMUError::PageNotWritable { vaddr });
+    if !flags.contains_all(GenericMappingFlags::Readable) {
+        return Err(MMUError::PageNotReadable { vaddr });
     }
 
-    Ok(())
+    Err(MMUError::PageNotWritable { vaddr })
 }
 
 struct MappedMemory {
@@ -515,3 +672,573 @@ impl Drop for MappedMemory {
         unsafe { std::alloc::dealloc(self.ptr, self.layout) };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use address::{IToPageNum, VirtualPageNumRange};
+    use allocation_abstractions::FrameDesc;
+    use memory_space::{AreaType, MapType, MappingArea};
+
+    use super::*;
+
+    fn setup_mmu() -> Arc<SpinMutex<dyn IMMU>> {
+        let (_, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        mmu
+    }
+
+    #[test]
+    fn test_memory_space_builder() {
+        let (mut memory_space, alloc) =
+            MemorySpaceBuilder::new().with_capacity(1024 * 1024).build();
+
+        assert!(Arc::ptr_eq(memory_space.allocator(), &alloc));
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let area = MappingArea::new(
+            VirtualPageNumRange::from_start_count(vaddr.to_floor_page_num(), 2),
+            AreaType::VMA,
+            MapType::Framed,
+            GenericMappingFlags::User
+                | GenericMappingFlags::Readable
+                | GenericMappingFlags::Writable,
+            None,
+        );
+
+        memory_space.alloc_and_map_area(area).unwrap();
+
+        let mmu = memory_space.mmu().lock();
+        mmu.export(vaddr + constants::PAGE_SIZE, 0xdead_beef_u32)
+            .unwrap();
+        assert_eq!(
+            mmu.import::<u32>(vaddr + constants::PAGE_SIZE),
+            Ok(0xdead_beef)
+        );
+    }
+
+    #[test]
+    fn test_memory_space_builder_with_mmu() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+
+        let (memory_space, built_alloc) = MemorySpaceBuilder::new()
+            .with_mmu(alloc.clone(), mmu.clone())
+            .build();
+
+        assert!(Arc::ptr_eq(&built_alloc, &alloc));
+        assert!(Arc::ptr_eq(memory_space.mmu(), &mmu));
+    }
+
+    #[test]
+    fn test_memory_subslice() {
+        let mmu = setup_mmu();
+        let data: [u8; 16] = core::array::from_fn(|i| i as u8);
+
+        let vaddr = mmu.lock().register(&data, false).unwrap();
+
+        let mmu = mmu.lock();
+        let memory = mmu.map_buffer(vaddr, data.len()).unwrap();
+        let sub = memory.subslice(4..12);
+
+        assert_eq!(&*sub, &data[4..12]);
+    }
+
+    #[test]
+    fn test_memory_mut_subslice() {
+        let mmu = setup_mmu();
+        let data = [0u8; 16];
+
+        let vaddr = mmu.lock().register(&data, true).unwrap();
+
+        let mmu = mmu.lock();
+        let memory = mmu.map_buffer_mut(vaddr, data.len(), false).unwrap();
+        let mut sub = memory.subslice(8..16);
+
+        assert_eq!(sub.len(), 8);
+        sub.fill(0xff);
+    }
+
+    fn mapped_count(mmu: &dyn IMMU) -> usize {
+        let mmu = mmu.downcast_ref::<TestMMU>().unwrap();
+        let count = mmu.mapped.lock().len();
+        count
+    }
+
+    #[test]
+    fn test_memory_drop_unmaps_buffer() {
+        let mmu = setup_mmu();
+        let data = [0x42u8; 16];
+
+        let vaddr = mmu.lock().register(&data, false).unwrap();
+
+        {
+            let locked = mmu.lock();
+            let memory = locked.map_buffer(vaddr, data.len()).unwrap();
+
+            assert_eq!(&*memory, &data);
+            assert_eq!(mapped_count(&*locked), 1);
+        }
+
+        assert_eq!(mapped_count(&*mmu.lock()), 0);
+    }
+
+    #[test]
+    fn test_memory_mut_drop_unmaps_buffer() {
+        let mmu = setup_mmu();
+        let data = [0u8; 16];
+
+        let vaddr = mmu.lock().register(&data, true).unwrap();
+
+        {
+            let locked = mmu.lock();
+            let mut memory = locked.map_buffer_mut(vaddr, data.len(), false).unwrap();
+            memory.fill(0x42);
+        }
+
+        assert_eq!(mapped_count(&*mmu.lock()), 0);
+        // Written back on unmap
+        assert_eq!(data, [0x42; 16]);
+    }
+
+    #[test]
+    fn test_memory_subslice_drop_unmaps_buffer() {
+        let mmu = setup_mmu();
+        let data = [0u8; 16];
+
+        let vaddr = mmu.lock().register(&data, false).unwrap();
+
+        {
+            let locked = mmu.lock();
+            let memory = locked.map_buffer(vaddr, data.len()).unwrap();
+            let _sub = memory.subslice(4..12);
+        }
+
+        assert_eq!(mapped_count(&*mmu.lock()), 0);
+    }
+
+    #[test]
+    fn test_force_unmap_leaked_buffer() {
+        let mmu = setup_mmu();
+        let data = [0x42u8; 16];
+
+        let vaddr = mmu.lock().register(&data, true).unwrap();
+
+        let locked = mmu.lock();
+        core::mem::forget(locked.map_buffer(vaddr, data.len()).unwrap());
+
+        assert!(matches!(
+            locked.map_buffer_mut(vaddr, data.len(), false),
+            Err(MMUError::Borrowed)
+        ));
+
+        locked.force_unmap_buffer(vaddr);
+        assert_eq!(mapped_count(&*locked), 0);
+
+        {
+            let mut memory = locked.map_buffer_mut(vaddr, data.len(), false).unwrap();
+            memory.fill(0x24);
+        }
+
+        assert_eq!(mapped_count(&*locked), 0);
+        assert_eq!(data, [0x24; 16]);
+    }
+
+    fn map_page(mmu: &mut dyn IMMU, vaddr: usize, paddr: usize) {
+        mmu.map_single(
+            VirtualAddress::from_usize(vaddr),
+            PhysicalAddress::from_usize(paddr),
+            PageSize::_4K,
+            GenericMappingFlags::User | GenericMappingFlags::Readable,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_map_misaligned() {
+        let mmu = setup_mmu();
+        let mut mmu = mmu.lock();
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let paddr = PhysicalAddress::from_usize(0x9000_0000);
+        let flags = GenericMappingFlags::User | GenericMappingFlags::Readable;
+
+        assert_eq!(
+            mmu.map_single(vaddr + 0x10, paddr, PageSize::_4K, flags),
+            Err(PagingError::NotAlignedVaddr)
+        );
+        assert_eq!(
+            mmu.map_single(vaddr, paddr + 0x10, PageSize::_4K, flags),
+            Err(PagingError::NotAlignedPaddr)
+        );
+        assert_eq!(
+            mmu.map_single(vaddr, paddr, PageSize::Custom(0x1800), flags),
+            Err(PagingError::NotAlignedSize)
+        );
+    }
+
+    #[test]
+    fn test_is_mapped() {
+        let mmu = setup_mmu();
+        let mut mmu = mmu.lock();
+
+        map_page(&mut *mmu, 0x1000_0000, 0x8000_0000);
+
+        assert!(mmu.is_mapped(VirtualAddress::from_usize(0x1000_0000)));
+        assert!(mmu.is_mapped(VirtualAddress::from_usize(0x1000_0fff)));
+        assert!(!mmu.is_mapped(VirtualAddress::from_usize(0x1000_1000)));
+        assert!(!mmu.is_mapped(VirtualAddress::from_usize(0x0fff_ffff)));
+    }
+
+    #[test]
+    fn test_is_range_mapped() {
+        let mmu = setup_mmu();
+        let mut mmu = mmu.lock();
+
+        map_page(&mut *mmu, 0x1000_0000, 0x8000_0000);
+        map_page(&mut *mmu, 0x1000_1000, 0x8000_1000);
+
+        let base = VirtualAddress::from_usize(0x1000_0000);
+
+        assert!(mmu.is_range_mapped(base, 0x2000));
+        assert!(mmu.is_range_mapped(base + 0x800, 0x1000));
+        assert!(mmu.is_range_mapped(base + 0x3000, 0));
+    }
+
+    #[test]
+    fn test_is_range_mapped_unmapped() {
+        let mmu = setup_mmu();
+        let mmu = mmu.lock();
+
+        assert!(!mmu.is_range_mapped(VirtualAddress::from_usize(0x1000_0000), 0x1000));
+    }
+
+    #[test]
+    fn test_is_range_mapped_partially() {
+        let mmu = setup_mmu();
+        let mut mmu = mmu.lock();
+
+        // Leave a hole at 0x1000_1000
+        map_page(&mut *mmu, 0x1000_0000, 0x8000_0000);
+        map_page(&mut *mmu, 0x1000_2000, 0x8000_2000);
+
+        let base = VirtualAddress::from_usize(0x1000_0000);
+
+        assert!(!mmu.is_range_mapped(base, 0x3000));
+        // Only the last byte falls into the hole
+        assert!(!mmu.is_range_mapped(base, 0x1001));
+        // Only the first byte falls into the hole
+        assert!(!mmu.is_range_mapped(base + 0x1fff, 2));
+        assert!(mmu.is_range_mapped(base + 0x2000, 0x1000));
+    }
+
+    #[test]
+    fn test_is_range_mapped_at_end_of_address_space() {
+        let mmu = setup_mmu();
+        let mut mmu = mmu.lock();
+
+        map_page(&mut *mmu, usize::MAX - 0xfff, 0x8000_0000);
+
+        let tail = VirtualAddress::from_usize(usize::MAX - 3);
+
+        // Ends exactly at usize::MAX
+        assert!(mmu.is_range_mapped(tail, 4));
+        assert!(mmu.is_range_mapped(VirtualAddress::from_usize(usize::MAX - 0xfff), 0x1000));
+
+        // Crosses usize::MAX
+        assert!(!mmu.is_range_mapped(tail, 8));
+        assert!(!mmu.is_range_mapped(VirtualAddress::from_usize(usize::MAX), usize::MAX));
+        assert_eq!(mmu.import::<u64>(tail), Err(MMUError::AccessFault));
+        assert_eq!(mmu.export::<u64>(tail, 0), Err(MMUError::AccessFault));
+    }
+
+    #[test]
+    fn test_with_temp_phys() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mmu = mmu.lock();
+
+        let frame = alloc.lock().alloc_frame().unwrap();
+
+        mmu.with_temp_phys(frame.addr(), constants::PAGE_SIZE, |page| page.fill(0xff))
+            .unwrap();
+        mmu.with_temp_phys(frame.addr(), constants::PAGE_SIZE, |page| page.fill(0))
+            .unwrap();
+
+        let zeroed = mmu
+            .with_temp_phys(frame.addr(), constants::PAGE_SIZE, |page| {
+                page.iter().all(|&b| b == 0)
+            })
+            .unwrap();
+        assert!(zeroed);
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_with_temp_phys_invalid_paddr() {
+        let mmu = setup_mmu();
+        let mmu = mmu.lock();
+
+        let result = mmu.with_temp_phys(PhysicalAddress::from_usize(0x1000), 0x1000, |_| ());
+
+        assert_eq!(result, Err(MMUError::AccessFault));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Pair {
+        a: u64,
+        b: u64,
+    }
+
+    /// Maps a single frame filled with `0x42` at `vaddr`
+    fn map_frame(
+        alloc: &Arc<SpinMutex<dyn IFrameAllocator>>,
+        mmu: &mut dyn IMMU,
+        vaddr: usize,
+    ) -> FrameDesc {
+        let frame = alloc.lock().alloc_frame().unwrap();
+
+        mmu.with_temp_phys(frame.addr(), constants::PAGE_SIZE, |page| page.fill(0x42))
+            .unwrap();
+        mmu.map_single(
+            VirtualAddress::from_usize(vaddr),
+            frame.addr(),
+            PageSize::_4K,
+            GenericMappingFlags::User
+                | GenericMappingFlags::Readable
+                | GenericMappingFlags::Writable,
+        )
+        .unwrap();
+
+        frame
+    }
+
+    #[test]
+    fn test_import_export_mapped() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let frame = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        let vaddr = VirtualAddress::from_usize(0x1000_0ff0);
+
+        let pair = mmu.import::<Pair>(vaddr).unwrap();
+        assert_eq!(pair.a, 0x4242_4242_4242_4242);
+
+        let pair = Pair { a: 1, b: 2 };
+        mmu.export(vaddr, pair).unwrap();
+        assert_eq!(mmu.import::<Pair>(vaddr), Ok(pair));
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_import_export_half_mapped() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let frame = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        // The second half of the value lies in the unmapped page
+        let vaddr = VirtualAddress::from_usize(0x1000_0ff8);
+
+        assert_eq!(mmu.import::<Pair>(vaddr), Err(MMUError::AccessFault));
+        assert_eq!(
+            mmu.export(vaddr, Pair { a: 1, b: 2 }),
+            Err(MMUError::AccessFault)
+        );
+
+        // Nothing is written on failure
+        assert_eq!(mmu.import::<u64>(vaddr), Ok(0x4242_4242_4242_4242));
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_inspect_framed_stopped() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let first = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        let second = map_frame(&alloc, &mut *mmu, 0x1000_1000);
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        assert_eq!(
+            mmu.inspect_framed(vaddr, 2 * constants::PAGE_SIZE, |_, _| false),
+            Ok(InspectOutcome::Stopped {
+                bytes_processed: constants::PAGE_SIZE
+            })
+        );
+        assert_eq!(
+            mmu.inspect_framed_mut(vaddr + 0x800, 2 * constants::PAGE_SIZE, |_, _| false),
+            Ok(InspectOutcome::Stopped {
+                bytes_processed: 0x800
+            })
+        );
+
+        alloc.lock().dealloc(first);
+        alloc.lock().dealloc(second);
+    }
+
+    #[test]
+    fn test_zero_length_transfer() {
+        let (_alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mmu = mmu.lock();
+
+        assert_eq!(mmu.read_bytes(VirtualAddress::null(), &mut []), Ok(()));
+        assert_eq!(mmu.write_bytes(VirtualAddress::null(), &[]), Ok(()));
+
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        assert_eq!(mmu.read_bytes(vaddr, &mut []), Ok(()));
+        assert_eq!(mmu.write_bytes(vaddr, &[]), Ok(()));
+    }
+
+    #[test]
+    fn test_inspect_framed_completed() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let first = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        let second = map_frame(&alloc, &mut *mmu, 0x1000_1000);
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        assert_eq!(
+            mmu.inspect_framed(vaddr, 2 * constants::PAGE_SIZE, |_, _| true),
+            Ok(InspectOutcome::Completed)
+        );
+
+        // Stopping at the last chunk is still reported as a stop
+        let mut chunks = 0;
+        assert_eq!(
+            mmu.inspect_framed_mut(vaddr, 2 * constants::PAGE_SIZE, |_, _| {
+                chunks += 1;
+                chunks < 2
+            }),
+            Ok(InspectOutcome::Stopped {
+                bytes_processed: 2 * constants::PAGE_SIZE
+            })
+        );
+
+        alloc.lock().dealloc(first);
+        alloc.lock().dealloc(second);
+    }
+
+    #[test]
+    fn test_cas_u32_success() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let frame = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        let vaddr = VirtualAddress::from_usize(0x1000_0ffc);
+
+        assert_eq!(mmu.cas_u32(vaddr, 0x4242_4242, 7), Ok(0x4242_4242));
+        assert_eq!(mmu.import::<u32>(vaddr), Ok(7));
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_cas_u32_failure() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let frame = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+
+        assert_eq!(mmu.cas_u32(vaddr, 0, 7), Ok(0x4242_4242));
+        assert_eq!(mmu.import::<u32>(vaddr), Ok(0x4242_4242));
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_cas_u32_invalid() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let frame = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+
+        assert_eq!(
+            mmu.cas_u32(VirtualAddress::from_usize(0x1000_0002), 0x4242_4242, 7),
+            Err(MMUError::MisalignedAddress)
+        );
+        assert!(mmu
+            .cas_u32(VirtualAddress::from_usize(0x1000_1000), 0, 7)
+            .is_err());
+
+        mmu.create_or_update_single(
+            VirtualAddress::from_usize(0x1000_0000),
+            PageSize::_4K,
+            None,
+            Some(GenericMappingFlags::User | GenericMappingFlags::Readable),
+        )
+        .unwrap();
+
+        assert!(matches!(
+            mmu.cas_u32(VirtualAddress::from_usize(0x1000_0000), 0x4242_4242, 7),
+            Err(MMUError::PageNotWritable { .. })
+        ));
+        assert_eq!(
+            mmu.import::<u32>(VirtualAddress::from_usize(0x1000_0000)),
+            Ok(0x4242_4242)
+        );
+
+        alloc.lock().dealloc(frame);
+    }
+
+    #[test]
+    fn test_export_is_atomic() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+        let mut mmu = mmu.lock();
+
+        let first = map_frame(&alloc, &mut *mmu, 0x1000_0000);
+        let second = map_frame(&alloc, &mut *mmu, 0x1000_1000);
+
+        mmu.create_or_update_single(
+            VirtualAddress::from_usize(0x1000_1000),
+            PageSize::_4K,
+            None,
+            Some(GenericMappingFlags::User | GenericMappingFlags::Readable),
+        )
+        .unwrap();
+
+        // Spans both pages, the second one is read only
+        let vaddr = VirtualAddress::from_usize(0x1000_0000);
+        let value = [0u64; 1024];
+
+        assert!(matches!(
+            mmu.export(vaddr, value),
+            Err(MMUError::PageNotWritable { .. })
+        ));
+
+        let page = mmu.import::<[u8; constants::PAGE_SIZE]>(vaddr).unwrap();
+        assert!(page.iter().all(|&byte| byte == 0x42));
+
+        alloc.lock().dealloc(first);
+        alloc.lock().dealloc(second);
+    }
+
+    #[test]
+    fn test_no_platform_payload() {
+        assert_eq!(setup_mmu().lock().platform_payload(), None);
+    }
+
+    #[test]
+    fn test_bound_alloc() {
+        let (alloc, mmu) = TestFrameAllocator::new_with_mmu(1024 * 1024);
+
+        assert!(Arc::ptr_eq(&mmu.lock().bound_alloc().unwrap(), &alloc));
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = setup_mmu().lock().capabilities();
+
+        assert!(!capabilities.contains(MmuCapabilities::PLATFORM_PAYLOAD));
+        assert!(capabilities.contains(MmuCapabilities::LINEAR_MAP));
+    }
+
+    #[test]
+    fn test_capabilities_without_linear_map() {
+        let (_, mmu) = crate::allocation::segment::TestFrameAllocator::new_with_mmu();
+        let capabilities = mmu.lock().capabilities();
+
+        assert!(!capabilities.contains(MmuCapabilities::PLATFORM_PAYLOAD));
+        assert!(!capabilities.contains(MmuCapabilities::LINEAR_MAP));
+    }
+}